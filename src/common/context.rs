@@ -0,0 +1,74 @@
+use tokio_util::sync::{CancellationToken, WaitForCancellationFuture};
+
+use crate::common::traits::CancellableContext;
+
+/// A [`CancellableContext`] backed by a [`CancellationToken`].
+#[derive(Clone, Debug, Default)]
+pub struct DriverContext {
+    cancel: CancellationToken,
+}
+
+impl DriverContext {
+    pub fn new(cancel: CancellationToken) -> Self {
+        Self { cancel }
+    }
+
+    /// Returns a context that is cancelled along with this one, but can also be cancelled on
+    /// its own without affecting this one.
+    pub fn child(&self) -> Self {
+        Self {
+            cancel: self.cancel.child_token(),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub fn token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+}
+
+impl CancellableContext for DriverContext {
+    fn cancelled(&self) -> WaitForCancellationFuture<'_> {
+        self.cancel.cancelled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn cancelling_parent_cancels_child() {
+        let parent = DriverContext::default();
+        let child = parent.child();
+
+        parent.cancel();
+
+        timeout(Duration::from_secs(1), child.cancelled())
+            .await
+            .expect("child context was not cancelled");
+        assert!(child.is_cancelled());
+    }
+
+    #[test]
+    fn cancelling_child_leaves_parent_running() {
+        let parent = DriverContext::default();
+        let child = parent.child();
+
+        child.cancel();
+
+        assert!(child.is_cancelled());
+        assert!(!parent.is_cancelled());
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    fmt::{self, Debug, Display},
+    sync::Arc,
+};
+
+use alloy::primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The commitment scheme a DA layer uses for payload hashes.
+pub trait Hasher: Debug + Send + Sync {
+    fn hash(&self, data: &[u8]) -> B256;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Keccak256Hasher;
+
+impl Hasher for Keccak256Hasher {
+    fn hash(&self, data: &[u8]) -> B256 {
+        keccak256(data)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn hash(&self, data: &[u8]) -> B256 {
+        B256::from_slice(&Sha256::digest(data))
+    }
+}
+
+/// A [`Hasher`] selectable from config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashScheme {
+    #[default]
+    Keccak256,
+    Sha256,
+    /// Whichever of the other schemes a data hash matches, for DA sources that do not agree on
+    /// one; see [`HashScheme::detect`].
+    Auto,
+}
+
+impl HashScheme {
+    /// The schemes [`HashScheme::Auto`] tries, in order.
+    pub const DETECTABLE: [HashScheme; 2] = [HashScheme::Keccak256, HashScheme::Sha256];
+
+    /// The hasher for this scheme; `keccak256`, the first one tried, for [`HashScheme::Auto`].
+    pub fn hasher(self) -> Arc<dyn Hasher> {
+        match self {
+            HashScheme::Keccak256 | HashScheme::Auto => Arc::new(Keccak256Hasher),
+            HashScheme::Sha256 => Arc::new(Sha256Hasher),
+        }
+    }
+
+    /// Returns the first [detectable](Self::DETECTABLE) scheme under which `data` hashes to
+    /// `hash`, if any.
+    pub fn detect(hash: B256, data: &[u8]) -> Option<HashScheme> {
+        Self::DETECTABLE
+            .into_iter()
+            .find(|scheme| scheme.hasher().hash(data) == hash)
+    }
+}
+
+impl Display for HashScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HashScheme::Keccak256 => "keccak256",
+            HashScheme::Sha256 => "sha256",
+            HashScheme::Auto => "auto",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::b256;
+
+    use super::*;
+    use crate::da_watcher::common::ProposalManifest;
+
+    #[test]
+    fn hashes_known_input() {
+        assert_eq!(
+            Keccak256Hasher.hash(b"abc"),
+            b256!("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45")
+        );
+        assert_eq!(
+            Sha256Hasher.hash(b"abc"),
+            b256!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
+    #[test]
+    fn manifest_verifies_with_its_own_hasher() {
+        let manifest = ProposalManifest::with_hasher(1, 0, b"abc", &Sha256Hasher);
+
+        assert!(manifest.verify(&Sha256Hasher, b"abc"));
+        assert!(!manifest.verify(&Keccak256Hasher, b"abc"));
+    }
+}
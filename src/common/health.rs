@@ -0,0 +1,194 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::{
+    da_watcher::common::{WatcherProgress, WatcherStatus},
+    event_indexer::common::{BackfillProgress, IndexerProgress, SyncStatus},
+};
+
+type Check = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// What `/readyz` inspects: the indexer must trail its head by at most `max_lag` blocks, and
+/// every extra check must pass.
+#[derive(Clone)]
+pub struct HealthState {
+    progress: IndexerProgress,
+    max_lag: u64,
+    checks: Vec<(String, Check)>,
+    watcher: Option<WatcherProgress>,
+}
+
+impl HealthState {
+    pub fn new(progress: IndexerProgress, max_lag: u64) -> Self {
+        Self {
+            progress,
+            max_lag,
+            checks: Vec::new(),
+            watcher: None,
+        }
+    }
+
+    /// Adds a named readiness condition, e.g. that a watcher's channel is still open.
+    pub fn with_check(
+        mut self,
+        name: impl Into<String>,
+        check: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.checks.push((name.into(), Arc::new(check)));
+        self
+    }
+
+    /// Reports the watcher's counters and fails the "watcher" check once it stops.
+    pub fn with_watcher(mut self, watcher: WatcherProgress) -> Self {
+        self.watcher = Some(watcher.clone());
+        self.with_check("watcher", move || watcher.is_running())
+    }
+
+    pub fn readiness(&self) -> Readiness {
+        let status = self.progress.status();
+        let failed_checks: Vec<String> = self
+            .checks
+            .iter()
+            .filter(|(_, check)| !check())
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        Readiness {
+            ready: status.lag <= self.max_lag && failed_checks.is_empty(),
+            max_lag: self.max_lag,
+            status,
+            failed_checks,
+            backfill: self.progress.backfill(),
+            watcher: self.watcher.as_ref().map(WatcherProgress::status),
+        }
+    }
+}
+
+/// Body of a `/readyz` response.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub max_lag: u64,
+    #[serde(flatten)]
+    pub status: SyncStatus,
+    pub failed_checks: Vec<String>,
+    /// Progress of the range being indexed, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backfill: Option<BackfillProgress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watcher: Option<WatcherStatus>,
+}
+
+/// Serves `GET /healthz` (200 while the process is up) and `GET /readyz` (200 when ready, 503
+/// otherwise, with a JSON [`Readiness`] body either way) until `cancel` fires.
+pub async fn serve(
+    state: HealthState,
+    addr: SocketAddr,
+    cancel: CancellationToken,
+) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/healthz", get(|| async { StatusCode::OK }))
+        .route("/readyz", get(readyz))
+        .with_state(state);
+
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving health checks on {}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { cancel.cancelled().await })
+        .await
+}
+
+async fn readyz(State(state): State<HealthState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.readiness();
+    let code = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(readiness))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    fn progress(last_indexed_block: u64, head: u64) -> IndexerProgress {
+        let progress = IndexerProgress::default();
+        progress.set_head(head);
+        progress.set_last_indexed_block(last_indexed_block);
+        progress
+    }
+
+    #[tokio::test]
+    async fn ready_when_synced() {
+        let state = HealthState::new(progress(95, 100), 10);
+
+        let (code, Json(readiness)) = readyz(State(state)).await;
+
+        assert_eq!(code, StatusCode::OK);
+        assert!(readiness.ready);
+        assert_eq!(readiness.status.lag, 5);
+    }
+
+    #[tokio::test]
+    async fn unavailable_when_lagging() {
+        let state = HealthState::new(progress(50, 100), 10);
+
+        let (code, Json(readiness)) = readyz(State(state)).await;
+
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!readiness.ready);
+        assert_eq!(readiness.status.lag, 50);
+        assert!(readiness.failed_checks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unavailable_when_check_fails() {
+        let watcher_open = Arc::new(AtomicBool::new(true));
+        let state = HealthState::new(progress(100, 100), 10).with_check("watcher", {
+            let watcher_open = watcher_open.clone();
+            move || watcher_open.load(Ordering::Relaxed)
+        });
+        assert!(state.readiness().ready);
+
+        watcher_open.store(false, Ordering::Relaxed);
+        let (code, Json(readiness)) = readyz(State(state)).await;
+
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(readiness.failed_checks, vec!["watcher"]);
+    }
+
+    #[tokio::test]
+    async fn reports_watcher_status() {
+        let watcher = WatcherProgress::default();
+        watcher.set_running(true);
+        watcher.record_error();
+        watcher.record_error();
+        watcher.record_drop();
+        let state = HealthState::new(progress(100, 100), 10).with_watcher(watcher.clone());
+
+        let (code, Json(readiness)) = readyz(State(state.clone())).await;
+        assert_eq!(code, StatusCode::OK);
+        assert_eq!(
+            readiness.watcher,
+            Some(WatcherStatus {
+                running: true,
+                errors: 2,
+                dropped_proposals: 1,
+            })
+        );
+
+        watcher.set_running(false);
+        let (code, Json(readiness)) = readyz(State(state)).await;
+        assert_eq!(code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(readiness.failed_checks, vec!["watcher"]);
+        assert!(!readiness.watcher.unwrap().running);
+    }
+}
@@ -0,0 +1,32 @@
+use std::net::SocketAddr;
+
+use axum::{extract::State, http::StatusCode, routing::get, Router};
+use prometheus::{Encoder, Registry, TextEncoder};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+/// Serves `registry` in the Prometheus text format on `GET /metrics` until `cancel` fires.
+pub async fn serve(
+    registry: Registry,
+    addr: SocketAddr,
+    cancel: CancellationToken,
+) -> std::io::Result<()> {
+    let app = Router::new()
+        .route("/metrics", get(render))
+        .with_state(registry);
+
+    let listener = TcpListener::bind(addr).await?;
+    info!("Serving metrics on {}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { cancel.cancelled().await })
+        .await
+}
+
+async fn render(State(registry): State<Registry>) -> Result<String, StatusCode> {
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut buffer)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    String::from_utf8(buffer).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
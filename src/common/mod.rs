@@ -1 +1,11 @@
+pub mod context;
+pub mod hasher;
+pub mod health;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod provider;
+pub mod rate_limiter;
+pub mod retry;
+pub mod serde_millis;
+pub mod supervisor;
 pub mod traits;
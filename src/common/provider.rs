@@ -0,0 +1,314 @@
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::U64,
+    providers::{Provider, ProviderBuilder, ProviderCall, RootProvider},
+    pubsub::Subscription,
+    rpc::{
+        client::{NoParams, RpcClient},
+        types::{Block, BlockTransactionsKind, Filter, Header, Log},
+    },
+    transports::{
+        http::{
+            reqwest::{
+                header::{HeaderMap, HeaderValue, USER_AGENT},
+                Client, Url,
+            },
+            Http,
+        },
+        BoxTransport, Transport, TransportResult,
+    },
+};
+use async_trait::async_trait;
+use thiserror::Error;
+
+/// Client identifier sent with outgoing provider requests unless overridden.
+pub const DEFAULT_CLIENT_ID: &str = concat!("based-rollup/", env!("CARGO_PKG_VERSION"));
+
+/// Header carrying the client identifier, for correlating traffic on the provider's side.
+pub const CLIENT_ID_HEADER: &str = "x-client-id";
+
+#[derive(Debug, Error)]
+pub enum ProviderBuildError {
+    #[error("Invalid client id: {0}")]
+    InvalidClientId(String),
+    #[error("HTTP client error: {0}")]
+    Client(String),
+    #[error("Failed to connect to {url}: {reason}")]
+    Connect { url: String, reason: String },
+}
+
+/// Builds an HTTP provider whose requests all carry `User-Agent` and `X-Client-Id` headers set
+/// to `client_id` (see [`DEFAULT_CLIENT_ID`]).
+pub fn http_provider_with_client_id(
+    url: Url,
+    client_id: &str,
+) -> Result<RootProvider<Http<Client>>, ProviderBuildError> {
+    let value = HeaderValue::from_str(client_id)
+        .map_err(|e| ProviderBuildError::InvalidClientId(e.to_string()))?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, value.clone());
+    headers.insert(CLIENT_ID_HEADER, value);
+
+    let client = Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(|e| ProviderBuildError::Client(e.to_string()))?;
+
+    let transport = Http::with_client(client, url);
+    let is_local = transport.guess_local();
+
+    Ok(RootProvider::new(RpcClient::new(transport, is_local)))
+}
+
+/// Connects to `url` over HTTP, WebSocket or IPC as its scheme says. HTTP requests carry
+/// `client_id` as [`http_provider_with_client_id`] does; WebSocket and IPC connections cannot
+/// set headers and are opened without it.
+pub async fn connect(
+    url: &str,
+    client_id: &str,
+) -> Result<RootProvider<BoxTransport>, ProviderBuildError> {
+    let connect_error = |reason: String| ProviderBuildError::Connect {
+        url: url.to_string(),
+        reason,
+    };
+
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let url = Url::parse(url).map_err(|e| connect_error(e.to_string()))?;
+        return Ok(http_provider_with_client_id(url, client_id)?.boxed());
+    }
+    ProviderBuilder::new()
+        .on_builtin(url)
+        .await
+        .map_err(|e| connect_error(e.to_string()))
+}
+
+/// Serves block subscriptions from a separate provider, so live heads can come over WebSocket
+/// while `eth_getLogs` and other queries go to another transport or endpoint.
+///
+/// Without a subscription provider, subscriptions go to the query provider like every other
+/// call.
+#[derive(Clone, Debug)]
+pub struct SplitProvider<Q, S = Q> {
+    query: Q,
+    subscription: Option<S>,
+}
+
+impl<Q> SplitProvider<Q> {
+    pub fn new(query: Q) -> Self {
+        Self {
+            query,
+            subscription: None,
+        }
+    }
+}
+
+impl<Q, S> SplitProvider<Q, S> {
+    pub fn with_subscription_provider<S2>(self, subscription: S2) -> SplitProvider<Q, S2> {
+        SplitProvider {
+            query: self.query,
+            subscription: Some(subscription),
+        }
+    }
+
+    pub fn query_provider(&self) -> &Q {
+        &self.query
+    }
+
+    pub fn subscription_provider(&self) -> Option<&S> {
+        self.subscription.as_ref()
+    }
+}
+
+#[async_trait]
+impl<Q, S, T> Provider<T> for SplitProvider<Q, S>
+where
+    Q: Provider<T>,
+    S: Provider<T>,
+    T: Transport + Clone,
+{
+    fn root(&self) -> &RootProvider<T> {
+        self.query.root()
+    }
+
+    fn get_block_number(&self) -> ProviderCall<T, NoParams, U64, u64> {
+        self.query.get_block_number()
+    }
+
+    async fn get_block_by_number(
+        &self,
+        number: BlockNumberOrTag,
+        kind: BlockTransactionsKind,
+    ) -> TransportResult<Option<Block>> {
+        self.query.get_block_by_number(number, kind).await
+    }
+
+    async fn get_logs(&self, filter: &Filter) -> TransportResult<Vec<Log>> {
+        self.query.get_logs(filter).await
+    }
+
+    async fn subscribe_blocks(&self) -> TransportResult<Subscription<Header>> {
+        match &self.subscription {
+            Some(subscription) => subscription.subscribe_blocks().await,
+            None => self.query.subscribe_blocks().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alloy::{
+        primitives::{Address, B256},
+        transports::TransportErrorKind,
+    };
+    use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::event_indexer::{common::EventIndexerConfig, event_indexer::EventIndexer};
+
+    type Seen = Arc<Mutex<Vec<HeaderMap>>>;
+
+    /// Answers every JSON-RPC request with block number `0x10`, recording its headers.
+    async fn serve_rpc() -> (String, Seen) {
+        async fn handle(
+            State(seen): State<Seen>,
+            headers: HeaderMap,
+            Json(request): Json<Value>,
+        ) -> Json<Value> {
+            seen.lock().unwrap_or_else(|e| e.into_inner()).push(headers);
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x10" }))
+        }
+
+        let seen = Seen::default();
+        let app = Router::new()
+            .route("/", post(handle))
+            .with_state(seen.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, seen)
+    }
+
+    fn header(headers: &HeaderMap, name: impl axum::http::header::AsHeaderName) -> Option<&str> {
+        headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn sends_client_id_header() {
+        let (url, seen) = serve_rpc().await;
+        let provider = http_provider_with_client_id(url.parse().unwrap(), "operator/1.2").unwrap();
+
+        assert_eq!(provider.get_block_number().await.unwrap(), 16);
+
+        let seen = seen.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(header(&seen[0], CLIENT_ID_HEADER), Some("operator/1.2"));
+        assert_eq!(header(&seen[0], USER_AGENT), Some("operator/1.2"));
+    }
+
+    #[tokio::test]
+    async fn connect_sends_client_id_over_http() {
+        let (url, seen) = serve_rpc().await;
+        let provider = connect(&url, DEFAULT_CLIENT_ID).await.unwrap();
+
+        provider.get_block_number().await.unwrap();
+
+        let seen = seen.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(header(&seen[0], CLIENT_ID_HEADER), Some(DEFAULT_CLIENT_ID));
+    }
+
+    #[test]
+    fn rejects_invalid_client_id() {
+        let url = "http://127.0.0.1:8545".parse().unwrap();
+        assert!(matches!(
+            http_provider_with_client_id(url, "bad\nid"),
+            Err(ProviderBuildError::InvalidClientId(_))
+        ));
+    }
+
+    /// Records which calls reach it; subscribing fails with its name.
+    struct RoleProvider {
+        root: RootProvider<Http<Client>>,
+        name: &'static str,
+        calls: Mutex<Vec<&'static str>>,
+    }
+
+    impl RoleProvider {
+        fn new(name: &'static str) -> Self {
+            Self {
+                root: ProviderBuilder::new().on_http("http://127.0.0.1:1".parse().unwrap()),
+                name,
+                calls: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn calls(&self) -> Vec<&'static str> {
+            self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+        }
+
+        fn record(&self, call: &'static str) {
+            self.calls
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(call);
+        }
+    }
+
+    #[async_trait]
+    impl Provider<Http<Client>> for RoleProvider {
+        fn root(&self) -> &RootProvider<Http<Client>> {
+            &self.root
+        }
+
+        fn get_block_number(&self) -> ProviderCall<Http<Client>, NoParams, U64, u64> {
+            self.record("eth_blockNumber");
+            ProviderCall::ready(Ok(5))
+        }
+
+        async fn get_logs(&self, _filter: &Filter) -> TransportResult<Vec<Log>> {
+            self.record("eth_getLogs");
+            Ok(Vec::new())
+        }
+
+        async fn subscribe_blocks(&self) -> TransportResult<Subscription<Header>> {
+            self.record("eth_subscribe");
+            Err(TransportErrorKind::custom_str(self.name))
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_each_role_to_its_provider() {
+        let query = RoleProvider::new("query");
+        let subscription = RoleProvider::new("subscription");
+        let provider = SplitProvider::new(&query).with_subscription_provider(&subscription);
+        let mut indexer = EventIndexer::new(
+            &provider,
+            EventIndexerConfig::default(),
+            Address::repeat_byte(0x11),
+            B256::repeat_byte(0x22),
+        )
+        .unwrap();
+
+        indexer.index_events(0, 5).await.unwrap();
+        let err = provider.subscribe_blocks().await.unwrap_err();
+
+        assert_eq!(err.to_string(), "subscription");
+        assert_eq!(query.calls(), vec!["eth_getLogs"]);
+        assert_eq!(subscription.calls(), vec!["eth_subscribe"]);
+    }
+
+    #[tokio::test]
+    async fn subscribes_through_query_provider_by_default() {
+        let query = RoleProvider::new("query");
+        let provider = SplitProvider::new(&query);
+
+        assert_eq!(provider.get_block_number().await.unwrap(), 5);
+        let err = provider.subscribe_blocks().await.unwrap_err();
+
+        assert_eq!(err.to_string(), "query");
+        assert_eq!(query.calls(), vec!["eth_blockNumber", "eth_subscribe"]);
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use tokio::time::sleep;
+
+/// A token bucket shared by every clone, e.g. across indexers that use the same provider.
+///
+/// Up to `burst` requests pass immediately; after that they are spaced `1 / requests_per_second`
+/// apart, in the order they called [`RateLimiter::acquire`].
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative when callers are queued for tokens not yet refilled.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// `burst` is raised to at least one request, and a rate that is not a positive, finite
+    /// number falls back to one request per second.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        let requests_per_second = if requests_per_second.is_finite() && requests_per_second > 0.0 {
+            requests_per_second
+        } else {
+            1.0
+        };
+        Self {
+            requests_per_second,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// Waits until a request may be issued.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.refilled();
+            bucket.tokens -= 1.0;
+            if bucket.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-bucket.tokens / self.requests_per_second)
+        };
+        sleep(wait).await;
+    }
+
+    /// Takes a token if one is available right now, without queuing; returns whether it did.
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = self.refilled();
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+
+    /// Locks the bucket after adding the tokens refilled since it was last touched.
+    fn refilled(&self) -> MutexGuard<'_, Bucket> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refill =
+            now.duration_since(bucket.refilled_at).as_secs_f64() * self.requests_per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.refilled_at = now;
+        bucket
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn spaces_requests_after_burst() {
+        let limiter = RateLimiter::new(50.0, 1);
+        let started = Instant::now();
+        for _ in 0..6 {
+            limiter.acquire().await;
+        }
+        // The first request uses the burst; the other five wait 20ms each.
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn invalid_rates_fall_back_to_one_per_second() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let limiter = RateLimiter::new(rate, 1);
+            assert_eq!(limiter.requests_per_second, 1.0);
+            limiter.acquire().await;
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fmt::Display, future::Future, time::Duration};
+
+use rand::Rng;
+use tokio::time::sleep;
+use tracing::warn;
+
+use crate::common::traits::ActorError;
+
+/// How [`retry_with_backoff`] spaces out attempts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` never retries.
+    pub max_retries: u32,
+    /// Delay before the first retry; each further retry doubles it.
+    pub base_delay: Duration,
+    /// Upper bound on the doubled delay, before jitter.
+    pub max_delay: Duration,
+    /// Upper bound of the random delay added to each retry, so callers restarted together do
+    /// not retry in lockstep.
+    pub jitter: Duration,
+}
+
+impl RetryPolicy {
+    /// Backoff before the `retry`-th retry (1-based): `base_delay * 2^(retry - 1)`, capped at
+    /// `max_delay`, plus up to `jitter`.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        if self.jitter.is_zero() {
+            return backoff;
+        }
+        backoff.saturating_add(rand::thread_rng().gen_range(Duration::ZERO..=self.jitter))
+    }
+}
+
+/// Runs `op` until it succeeds, fails with an [unrecoverable](ActorError::is_unrecoverable)
+/// error or has been retried `max_retries` times, sleeping [`RetryPolicy::delay`] between
+/// attempts. Returns the last attempt's result.
+pub async fn retry_with_backoff<F, Fut, T, E>(policy: &RetryPolicy, mut op: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: ActorError + Display,
+{
+    let mut retries = 0;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if e.is_unrecoverable() || retries >= policy.max_retries => return Err(e),
+            Err(e) => {
+                retries += 1;
+                let delay = policy.delay(retries);
+                warn!(
+                    "Retry {}/{} in {:?}: {}",
+                    retries, policy.max_retries, delay, e
+                );
+                sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use thiserror::Error;
+
+    use super::*;
+
+    #[derive(Debug, Error, PartialEq, Eq)]
+    enum TestError {
+        #[error("transient failure {0}")]
+        Transient(u32),
+        #[error("fatal failure")]
+        Fatal,
+    }
+
+    impl ActorError for TestError {
+        fn is_unrecoverable(&self) -> bool {
+            matches!(self, TestError::Fatal)
+        }
+    }
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Fails the first `failures` attempts, counting every attempt in `attempts`.
+    async fn flaky(attempts: &Cell<u32>, failures: u32) -> Result<u32, TestError> {
+        let attempt = attempts.get() + 1;
+        attempts.set(attempt);
+        if attempt <= failures {
+            Err(TestError::Transient(attempt))
+        } else {
+            Ok(attempt)
+        }
+    }
+
+    #[tokio::test]
+    async fn succeeds_after_failures() {
+        let attempts = Cell::new(0);
+
+        let result = retry_with_backoff(&policy(3), || flaky(&attempts, 3)).await;
+
+        assert_eq!(result, Ok(4));
+    }
+
+    #[tokio::test]
+    async fn returns_last_error_once_exhausted() {
+        let attempts = Cell::new(0);
+
+        let result = retry_with_backoff(&policy(2), || flaky(&attempts, 10)).await;
+
+        assert_eq!(result, Err(TestError::Transient(3)));
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_unrecoverable_errors() {
+        let attempts = Cell::new(0);
+
+        let result: Result<(), _> = retry_with_backoff(&policy(5), || {
+            attempts.set(attempts.get() + 1);
+            async { Err(TestError::Fatal) }
+        })
+        .await;
+
+        assert_eq!(result, Err(TestError::Fatal));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            jitter: Duration::ZERO,
+        };
+
+        let delays: Vec<_> = (1..=5).map(|retry| policy.delay(retry)).collect();
+
+        assert_eq!(
+            delays,
+            [100, 200, 400, 500, 500]
+                .map(Duration::from_millis)
+                .to_vec()
+        );
+    }
+}
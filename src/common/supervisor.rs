@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::{task::JoinSet, time::sleep};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::common::traits::{ActorError, DriverActor};
+
+/// Upper bound on the delay between restarts of a failing actor.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum SupervisorError {
+    #[error("Actor {actor} failed unrecoverably: {reason}")]
+    Unrecoverable { actor: String, reason: String },
+    #[error("Actor {actor} exceeded {restarts} restarts: {reason}")]
+    RestartsExhausted {
+        actor: String,
+        restarts: u32,
+        reason: String,
+    },
+}
+
+/// Runs [`DriverActor`]s on their own tasks, restarting any that fail or panic.
+///
+/// A failed actor is rebuilt and restarted with exponential backoff until it has been restarted
+/// `max_restarts` times, or immediately escalated if its error is
+/// [unrecoverable](ActorError::is_unrecoverable). Escalation cancels the shared token, which
+/// stops every other actor, and is returned from [`ActorSupervisor::join`].
+pub struct ActorSupervisor {
+    cancel: CancellationToken,
+    max_restarts: u32,
+    restart_backoff: Duration,
+    tasks: JoinSet<Result<(), SupervisorError>>,
+}
+
+impl ActorSupervisor {
+    pub fn new(cancel: CancellationToken) -> Self {
+        Self {
+            cancel,
+            max_restarts: 5,
+            restart_backoff: Duration::from_secs(1),
+            tasks: JoinSet::new(),
+        }
+    }
+
+    pub fn with_max_restarts(mut self, max_restarts: u32) -> Self {
+        self.max_restarts = max_restarts;
+        self
+    }
+
+    /// Delay before the first restart; each further restart doubles it.
+    pub fn with_restart_backoff(mut self, restart_backoff: Duration) -> Self {
+        self.restart_backoff = restart_backoff;
+        self
+    }
+
+    /// The token shared by every supervised actor.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Supervises the actor produced by `factory`, which is called again with the shared token
+    /// for every restart.
+    pub fn spawn<A, F>(&mut self, name: impl Into<String>, mut factory: F)
+    where
+        A: DriverActor + Send + 'static,
+        A::Outbond: 'static,
+        A::Error: ActorError + Send + 'static,
+        F: FnMut(CancellationToken) -> (A, A::Outbond) + Send + 'static,
+    {
+        let name = name.into();
+        let cancel = self.cancel.clone();
+        let max_restarts = self.max_restarts;
+        let restart_backoff = self.restart_backoff;
+
+        self.tasks.spawn(async move {
+            let mut restarts = 0;
+            loop {
+                let (actor, outbond) = factory(cancel.clone());
+                let reason = match tokio::spawn(actor.start(outbond)).await {
+                    Ok(Ok(())) => {
+                        info!("Actor {} stopped", name);
+                        return Ok(());
+                    }
+                    Ok(Err(e)) if e.is_unrecoverable() => {
+                        let err = SupervisorError::Unrecoverable {
+                            actor: name,
+                            reason: format!("{:?}", e),
+                        };
+                        error!("{}", err);
+                        cancel.cancel();
+                        return Err(err);
+                    }
+                    Ok(Err(e)) => format!("{:?}", e),
+                    Err(e) => format!("task failed: {}", e),
+                };
+
+                if cancel.is_cancelled() {
+                    info!("Actor {} stopped after cancellation: {}", name, reason);
+                    return Ok(());
+                }
+                if restarts >= max_restarts {
+                    let err = SupervisorError::RestartsExhausted {
+                        actor: name,
+                        restarts,
+                        reason,
+                    };
+                    error!("{}", err);
+                    cancel.cancel();
+                    return Err(err);
+                }
+
+                restarts += 1;
+                let backoff = restart_backoff
+                    .saturating_mul(1 << (restarts - 1).min(16))
+                    .min(MAX_RESTART_BACKOFF);
+                warn!(
+                    "Actor {} failed (restart {} of {}), restarting in {:?}: {}",
+                    name, restarts, max_restarts, backoff, reason
+                );
+                tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    _ = sleep(backoff) => {}
+                }
+            }
+        });
+    }
+
+    /// Waits for every actor to stop, returning the first escalation.
+    pub async fn join(mut self) -> Result<(), SupervisorError> {
+        let mut result = Ok(());
+        while let Some(joined) = self.tasks.join_next().await {
+            match joined {
+                Ok(Err(e)) if result.is_ok() => result = Err(e),
+                Ok(_) => {}
+                Err(e) => warn!("Supervisor task failed: {}", e),
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::common::context::DriverContext;
+
+    #[derive(Debug)]
+    enum FlakyError {
+        Transient,
+        Fatal,
+    }
+
+    impl ActorError for FlakyError {
+        fn is_unrecoverable(&self) -> bool {
+            matches!(self, FlakyError::Fatal)
+        }
+    }
+
+    /// Fails with `error` on its first `failures` starts, then stops cleanly.
+    struct FlakyActor {
+        starts: Arc<AtomicU32>,
+        failures: u32,
+        error: fn() -> FlakyError,
+    }
+
+    #[async_trait]
+    impl DriverActor for FlakyActor {
+        type Error = FlakyError;
+        type Inbond = ();
+        type Outbond = DriverContext;
+        type Config = (Arc<AtomicU32>, u32, fn() -> FlakyError);
+
+        fn build((starts, failures, error): Self::Config) -> ((), Self) {
+            (
+                (),
+                Self {
+                    starts,
+                    failures,
+                    error,
+                },
+            )
+        }
+
+        async fn start(self, _outbond: DriverContext) -> Result<(), FlakyError> {
+            if self.starts.fetch_add(1, Ordering::Relaxed) < self.failures {
+                return Err((self.error)());
+            }
+            Ok(())
+        }
+    }
+
+    fn supervise(
+        failures: u32,
+        error: fn() -> FlakyError,
+        max_restarts: u32,
+    ) -> (ActorSupervisor, Arc<AtomicU32>) {
+        let starts = Arc::new(AtomicU32::new(0));
+        let mut supervisor = ActorSupervisor::new(CancellationToken::new())
+            .with_max_restarts(max_restarts)
+            .with_restart_backoff(Duration::from_millis(1));
+        let config = starts.clone();
+        supervisor.spawn("flaky", move |cancel| {
+            let ((), actor) = FlakyActor::build((config.clone(), failures, error));
+            (actor, DriverContext::new(cancel))
+        });
+        (supervisor, starts)
+    }
+
+    #[tokio::test]
+    async fn restarts_flaky_actor() {
+        let (supervisor, starts) = supervise(2, || FlakyError::Transient, 5);
+
+        supervisor.join().await.unwrap();
+
+        assert_eq!(starts.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn escalates_once_restarts_are_exhausted() {
+        let (supervisor, starts) = supervise(u32::MAX, || FlakyError::Transient, 2);
+        let cancel = supervisor.cancel_token();
+
+        let err = supervisor.join().await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            SupervisorError::RestartsExhausted { restarts: 2, .. }
+        ));
+        assert_eq!(starts.load(Ordering::Relaxed), 3);
+        assert!(cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn does_not_restart_unrecoverable_actor() {
+        let (supervisor, starts) = supervise(1, || FlakyError::Fatal, 5);
+        let cancel = supervisor.cancel_token();
+
+        let err = supervisor.join().await.unwrap_err();
+
+        assert!(matches!(err, SupervisorError::Unrecoverable { .. }));
+        assert_eq!(starts.load(Ordering::Relaxed), 1);
+        assert!(cancel.is_cancelled());
+    }
+}
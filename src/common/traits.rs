@@ -28,3 +28,14 @@ pub trait DriverActor {
     /// Starts the actor.
     async fn start(self, outbond: Self::Outbond) -> Result<(), Self::Error>;
 }
+
+/// Errors returned from [`DriverActor::start`] when the actor runs under a supervisor.
+pub trait ActorError: Debug {
+    /// Whether restarting the actor cannot help, e.g. because its configuration is invalid.
+    fn is_unrecoverable(&self) -> bool;
+
+    /// Whether the error is a stage giving up on a stalled call, rather than the call failing.
+    fn is_timeout(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,407 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use alloy::{
+    primitives::{Address, B256},
+    transports::http::reqwest::{header::HeaderValue, Url},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    common::provider::DEFAULT_CLIENT_ID,
+    da_watcher::common::WatcherConfig,
+    datasource::common::{DataSourceConfig, DataSourceKind},
+    derivation::common::{AnchorConfig, PayloadConfig},
+    driver::common::StageTimeouts,
+    event_indexer::common::EventIndexerConfig,
+    execution_engine::common::EngineConfig,
+};
+
+/// Config file read by the CLI when no path is given.
+pub const DEFAULT_CONFIG_PATH: &str = "./based-rollup.toml";
+
+/// Top-level driver configuration, loaded from a TOML file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DriverConfig {
+    pub l1_rpc_url: String,
+    /// WebSocket endpoint for block subscriptions; `l1_rpc_url` is used when absent.
+    pub ws_url: Option<String>,
+    /// Sent as `User-Agent` and `X-Client-Id` on HTTP RPC requests.
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+    pub contract_address: Address,
+    /// Signature hash (topic0) of the event to index; unset indexes every log the contract
+    /// emits, including anonymous events.
+    pub event_topic: Option<B256>,
+    pub start_block: Option<u64>,
+    /// File the indexer checkpoints its progress to; resumed from when `start_block` is unset.
+    pub checkpoint_path: Option<PathBuf>,
+    /// How often the DA watcher checks for a new L1 head once caught up. The indexer polls on
+    /// its own `indexer.fallback_poll_interval_ms`.
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    #[serde(default)]
+    pub indexer: EventIndexerConfig,
+    #[serde(default)]
+    pub watcher: WatcherConfig,
+    #[serde(default)]
+    pub datasource: DataSourceConfig,
+    /// Fee recipient, gas limit and base fee parameters of derived blocks.
+    #[serde(default)]
+    pub payload: PayloadConfig,
+    /// Proposals whose L1 timestamp is further behind the clock than this are discarded
+    /// instead of derived; unset derives every proposal.
+    pub max_proposal_age_secs: Option<u64>,
+    /// Proposals included more than this after their own timestamp derive to empty blocks;
+    /// unset never expires them.
+    pub sequencing_window_secs: Option<u64>,
+    /// Per-attempt limits on fetching, deriving and executing a proposal.
+    #[serde(default)]
+    pub timeouts: StageTimeouts,
+    /// Execution client to import derived blocks into; only events are indexed without one.
+    pub engine: Option<EngineConfig>,
+    /// State derivation starts from; the first derived proposal is checked against it.
+    pub anchor: Option<AnchorConfig>,
+}
+
+fn default_poll_interval_ms() -> u64 {
+    12_000
+}
+
+fn default_client_id() -> String {
+    DEFAULT_CLIENT_ID.to_string()
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {reason}")]
+    Read { path: String, reason: String },
+    #[error("Invalid config file {path}: {reason}")]
+    Parse { path: String, reason: String },
+    #[error("Invalid {field}: {reason}")]
+    InvalidField { field: String, reason: String },
+}
+
+impl DriverConfig {
+    /// Reads and parses the config file at `path`. Missing required fields and malformed
+    /// values such as an invalid `contract_address` are rejected here rather than at use.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+
+        let contents = fs::read_to_string(path).map_err(|e| ConfigError::Read {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Checks the values a successful parse cannot, returning one error per offending field.
+    pub fn validate(&self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+        let mut check = |field: &str, reason: Option<String>| {
+            if let Some(reason) = reason {
+                errors.push(ConfigError::InvalidField {
+                    field: field.to_string(),
+                    reason,
+                });
+            }
+        };
+
+        check(
+            "l1_rpc_url",
+            check_url(&self.l1_rpc_url, &["http", "https", "ws", "wss"]),
+        );
+        if let Some(ws_url) = &self.ws_url {
+            check("ws_url", check_url(ws_url, &["ws", "wss"]));
+        }
+        check(
+            "client_id",
+            HeaderValue::from_str(&self.client_id)
+                .err()
+                .map(|_| "must be a valid HTTP header value".to_string()),
+        );
+        check(
+            "contract_address",
+            self.contract_address
+                .is_zero()
+                .then(|| "must be non-zero".to_string()),
+        );
+        check(
+            "event_topic",
+            self.event_topic
+                .is_some_and(|topic| topic.is_zero())
+                .then(|| "must be non-zero".to_string()),
+        );
+        check(
+            "poll_interval_ms",
+            (self.poll_interval_ms == 0).then(|| "must be positive".to_string()),
+        );
+        check(
+            "indexer.batch_size",
+            (self.indexer.batch_size == 0).then(|| "must be positive".to_string()),
+        );
+        check(
+            "indexer.max_block_range",
+            (self.indexer.max_block_range == 0).then(|| "must be positive".to_string()),
+        );
+        check(
+            "indexer.max_requests_per_second",
+            self.indexer
+                .max_requests_per_second
+                .is_some_and(|rate| !rate.is_finite() || rate <= 0.0)
+                .then(|| "must be a positive number".to_string()),
+        );
+        check(
+            "indexer.dedup_window",
+            (self.indexer.dedup_window != 0
+                && self.indexer.dedup_window < self.indexer.max_reorg_depth as u64)
+                .then(|| "must be 0 or at least indexer.max_reorg_depth".to_string()),
+        );
+        check(
+            "watcher.rate_limit.proposals_per_second",
+            self.watcher
+                .rate_limit
+                .is_some_and(|limit| {
+                    !limit.proposals_per_second.is_finite() || limit.proposals_per_second <= 0.0
+                })
+                .then(|| "must be a positive number".to_string()),
+        );
+        check(
+            "watcher.buffer_size",
+            (self.watcher.buffer_size == 0).then(|| "must be positive".to_string()),
+        );
+        check(
+            "datasource.sources",
+            self.datasource
+                .sources
+                .is_empty()
+                .then(|| "must list at least one source".to_string()),
+        );
+        if self.datasource.sources.contains(&DataSourceKind::Blob) {
+            check(
+                "datasource.beacon_url",
+                match &self.datasource.beacon_url {
+                    Some(url) => check_url(url, &["http", "https"]),
+                    None => Some("is required by the blob source".to_string()),
+                },
+            );
+        }
+        check(
+            "payload",
+            self.payload.validate().err().map(|e| e.to_string()),
+        );
+        check(
+            "max_proposal_age_secs",
+            (self.max_proposal_age_secs == Some(0)).then(|| "must be positive".to_string()),
+        );
+        check(
+            "sequencing_window_secs",
+            (self.sequencing_window_secs == Some(0)).then(|| "must be positive".to_string()),
+        );
+        for (field, limit) in [
+            ("timeouts.fetch_ms", self.timeouts.fetch_ms),
+            ("timeouts.derive_ms", self.timeouts.derive_ms),
+            ("timeouts.execute_ms", self.timeouts.execute_ms),
+        ] {
+            check(
+                field,
+                (limit == Some(0)).then(|| "must be positive".to_string()),
+            );
+        }
+        if let Some(engine) = &self.engine {
+            check("engine.url", check_url(&engine.url, &["http", "https"]));
+        }
+        if let (Some(engine), Some(anchor)) = (&self.engine, &self.anchor) {
+            // The first derived block's parent is the engine's head.
+            check(
+                "engine.head_block_hash",
+                (engine.head_block_hash != anchor.l2_genesis_hash)
+                    .then(|| "must match anchor.l2_genesis_hash".to_string()),
+            );
+        }
+
+        errors
+    }
+}
+
+/// Returns why `url` is not a well-formed URL with one of `schemes`, if it is not.
+fn check_url(url: &str, schemes: &[&str]) -> Option<String> {
+    match Url::parse(url) {
+        Ok(url) if schemes.contains(&url.scheme()) => None,
+        Ok(url) => Some(format!(
+            "unsupported scheme {:?}, expected one of {}",
+            url.scheme(),
+            schemes.join(", ")
+        )),
+        Err(e) => Some(format!("malformed URL {:?}: {}", url, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{da_watcher::common::WatcherStart, datasource::CompressionType};
+
+    const SAMPLE: &str = r#"
+l1_rpc_url = "http://localhost:8545"
+ws_url = "ws://localhost:8546"
+contract_address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+start_block = 100
+poll_interval_ms = 6000
+
+[indexer]
+batch_size = 500
+
+[watcher]
+start = { block = 100 }
+
+[datasource]
+sources = ["blob", "calldata"]
+beacon_url = "http://localhost:5052"
+compression = "zstd"
+
+[payload]
+gas_limit = 20000000
+
+[engine]
+url = "http://localhost:8551"
+jwt_secret_path = "/etc/jwt.hex"
+head_block_hash = "0x0000000000000000000000000000000000000000000000000000000000000001"
+"#;
+
+    /// Writes `contents` to a fresh file under the system temp directory.
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("based-rollup-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn sample() -> DriverConfig {
+        toml::from_str(SAMPLE).unwrap()
+    }
+
+    #[test]
+    fn loads_toml_file() {
+        let path = write_config("sample", SAMPLE);
+        let config = DriverConfig::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.l1_rpc_url, "http://localhost:8545");
+        assert_eq!(config.ws_url.as_deref(), Some("ws://localhost:8546"));
+        assert_eq!(
+            config.contract_address,
+            "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(config.start_block, Some(100));
+        assert_eq!(config.poll_interval_ms, 6000);
+        assert_eq!(config.client_id, DEFAULT_CLIENT_ID);
+        assert_eq!(config.indexer.batch_size, 500);
+        assert_eq!(config.watcher.start, WatcherStart::Block(100));
+        assert_eq!(
+            config.datasource.sources,
+            vec![DataSourceKind::Blob, DataSourceKind::Calldata]
+        );
+        assert_eq!(config.datasource.compression, CompressionType::Zstd);
+        assert_eq!(config.payload.gas_limit, 20_000_000);
+        assert_eq!(
+            config.engine.as_ref().map(|engine| engine.head_block_hash),
+            Some(B256::with_last_byte(1))
+        );
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let config = sample();
+        let reparsed: DriverConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+
+        assert_eq!(reparsed.l1_rpc_url, config.l1_rpc_url);
+        assert_eq!(reparsed.contract_address, config.contract_address);
+        assert_eq!(reparsed.poll_interval_ms, config.poll_interval_ms);
+        assert_eq!(reparsed.payload, config.payload);
+        assert_eq!(reparsed.datasource.sources, config.datasource.sources);
+    }
+
+    #[test]
+    fn defaults_optional_fields() {
+        let config: DriverConfig = toml::from_str(
+            r#"
+l1_rpc_url = "http://localhost:8545"
+contract_address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.poll_interval_ms, 12_000);
+        assert_eq!(config.datasource.sources, vec![DataSourceKind::Calldata]);
+        assert!(config.engine.is_none());
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn rejects_missing_field_and_file() {
+        let path = write_config("missing-field", "l1_rpc_url = \"http://localhost:8545\"");
+        let result = DriverConfig::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(result, Err(ConfigError::Parse { reason, .. }) if reason.contains("contract_address"))
+        );
+
+        assert!(matches!(
+            DriverConfig::load("/nonexistent/based-rollup.toml"),
+            Err(ConfigError::Read { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_address() {
+        let result: Result<DriverConfig, _> = toml::from_str(
+            r#"
+l1_rpc_url = "http://localhost:8545"
+contract_address = "0x1234"
+"#,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reports_each_invalid_field() {
+        let mut config = sample();
+        config.anchor = Some(AnchorConfig {
+            l2_genesis_hash: B256::with_last_byte(2),
+            l1_origin_block: 99,
+            l1_origin_hash: B256::with_last_byte(3),
+        });
+        config.poll_interval_ms = 0;
+        config.indexer.max_requests_per_second = Some(f64::NAN);
+        config.datasource.beacon_url = None;
+
+        let fields: Vec<String> = config
+            .validate()
+            .into_iter()
+            .filter_map(|error| match error {
+                ConfigError::InvalidField { field, .. } => Some(field),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "poll_interval_ms",
+                "indexer.max_requests_per_second",
+                "datasource.beacon_url",
+                "engine.head_block_hash"
+            ]
+        );
+    }
+}
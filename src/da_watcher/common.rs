@@ -0,0 +1,265 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use alloy::primitives::B256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    common::hasher::{Hasher, Keccak256Hasher},
+    traits::BlockOrdered,
+};
+
+/// A proposal observed on the DA layer, committing to its payload by hash.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalManifest {
+    pub block_number: u64,
+    /// Timestamp of the L1 block that included the proposal.
+    pub timestamp: u64,
+    /// Hash of the canonical (decompressed) payload under the DA layer's [`Hasher`].
+    pub data_hash: B256,
+}
+
+impl ProposalManifest {
+    /// Builds a manifest committing to `payload`, the decompressed batch data, with
+    /// `keccak256`.
+    pub fn new(block_number: u64, timestamp: u64, payload: &[u8]) -> Self {
+        Self::with_hasher(block_number, timestamp, payload, &Keccak256Hasher)
+    }
+
+    pub fn with_hasher<H: Hasher + ?Sized>(
+        block_number: u64,
+        timestamp: u64,
+        payload: &[u8],
+        hasher: &H,
+    ) -> Self {
+        Self {
+            block_number,
+            timestamp,
+            data_hash: hasher.hash(payload),
+        }
+    }
+
+    /// Returns whether `payload` hashes to the manifest's data hash under `hasher`.
+    pub fn verify<H: Hasher + ?Sized>(&self, hasher: &H, payload: &[u8]) -> bool {
+        hasher.hash(payload) == self.data_hash
+    }
+}
+
+impl BlockOrdered for ProposalManifest {
+    fn block_number(&self) -> u64 {
+        self.block_number
+    }
+}
+
+/// Shared view of a watcher's state, readable from other tasks while it runs.
+#[derive(Clone, Debug, Default)]
+pub struct WatcherProgress {
+    running: Arc<AtomicBool>,
+    /// Failed fetches, across every `watch` call.
+    errors: Arc<AtomicU64>,
+    /// Proposals dropped under backpressure or the rate limit, across every `watch` call.
+    dropped: Arc<AtomicU64>,
+}
+
+impl WatcherProgress {
+    /// Whether the polling task is alive, i.e. its proposal channel is open for new items.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_proposals(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn status(&self) -> WatcherStatus {
+        WatcherStatus {
+            running: self.is_running(),
+            errors: self.errors(),
+            dropped_proposals: self.dropped_proposals(),
+        }
+    }
+
+    pub(crate) fn set_running(&self, running: bool) {
+        self.running.store(running, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns how many proposals were dropped, including this one.
+    pub(crate) fn record_drop(&self) -> u64 {
+        self.dropped.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Snapshot of a [`WatcherProgress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatcherStatus {
+    pub running: bool,
+    pub errors: u64,
+    pub dropped_proposals: u64,
+}
+
+/// How the CLI builds its [`DAWatcher`](crate::da_watcher::da_watcher::DAWatcher).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatcherConfig {
+    pub start: WatcherStart,
+    /// Capacity of the proposal channel.
+    pub buffer_size: usize,
+    /// Recently emitted proposals remembered to skip duplicates; `0` disables deduplication.
+    pub dedup_window: usize,
+    pub backpressure: BackpressurePolicy,
+    /// Caps how fast proposals are emitted; unlimited when unset.
+    pub rate_limit: Option<ProposalRateLimit>,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            start: WatcherStart::Genesis,
+            buffer_size: 64,
+            dedup_window: 256,
+            backpressure: BackpressurePolicy::Block,
+            rate_limit: None,
+        }
+    }
+}
+
+/// Where a watcher begins polling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherStart {
+    #[default]
+    Genesis,
+    Block(u64),
+    /// The source's current height.
+    Latest,
+    /// The first block with a timestamp at or after this Unix time.
+    Timestamp(u64),
+}
+
+/// What a watcher does with a new proposal while its channel of `buffer_size` is full.
+///
+/// The dropping policies give the consumer up to the send timeout to make room first, and count
+/// every proposal they drop.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    /// Stop polling and wait until the consumer makes room, so nothing is lost.
+    #[default]
+    Block,
+    /// Keep polling and discard the new proposal.
+    DropNewest,
+    /// Keep polling, holding undelivered proposals in a backlog of `buffer_size` that drops its
+    /// oldest entry when full.
+    DropOldest,
+}
+
+/// A token bucket on a watcher's emission, smoothing catch-up bursts for the consumer.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProposalRateLimit {
+    pub proposals_per_second: f64,
+    /// Proposals emitted back to back before the rate applies.
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    #[serde(default)]
+    pub mode: RateLimitMode,
+}
+
+fn default_burst() -> u32 {
+    1
+}
+
+/// What a watcher does with a proposal that exceeds its rate limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitMode {
+    /// Hold the proposal, and polling with it, until the limit allows it.
+    #[default]
+    Delay,
+    /// Discard the proposal and count it as dropped.
+    Drop,
+}
+
+/// How fast a [`ReplayWatcher`](crate::da_watcher::replay_watcher::ReplayWatcher) emits
+/// recorded proposals, paced by the gaps between their timestamps.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReplaySpeed {
+    /// As far apart as the proposals' timestamps.
+    #[default]
+    Realtime,
+    /// That many times faster than real time; a multiplier that is not a positive number
+    /// replays as fast as possible.
+    Multiplier(f64),
+    /// As fast as the receiver takes them.
+    Unbounded,
+}
+
+impl ReplaySpeed {
+    /// Delay between emitting a proposal stamped `from` and the next one, stamped `to`. A
+    /// timestamp going backwards is emitted right away.
+    pub fn delay(self, from: u64, to: u64) -> Duration {
+        let gap = Duration::from_secs(to.saturating_sub(from));
+        match self {
+            ReplaySpeed::Realtime => gap,
+            ReplaySpeed::Multiplier(multiplier) if multiplier.is_finite() && multiplier > 0.0 => {
+                Duration::try_from_secs_f64(gap.as_secs_f64() / multiplier).unwrap_or(Duration::MAX)
+            }
+            ReplaySpeed::Multiplier(_) | ReplaySpeed::Unbounded => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WatcherError {
+    #[error("Fetch error: {0}")]
+    FetchError(String),
+    #[error("Other error: {0}")]
+    Other(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::b256;
+
+    use super::*;
+
+    #[test]
+    fn data_hash_is_keccak256_of_payload() {
+        let manifest = ProposalManifest::new(1, 0, b"");
+
+        assert_eq!(
+            manifest.data_hash,
+            b256!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        assert!(manifest.verify(&Keccak256Hasher, b""));
+        assert!(!manifest.verify(&Keccak256Hasher, b"tampered"));
+    }
+
+    #[test]
+    fn multiplier_divides_the_gap_between_timestamps() {
+        assert_eq!(
+            ReplaySpeed::Realtime.delay(100, 110),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            ReplaySpeed::Multiplier(2.0).delay(100, 110),
+            Duration::from_secs(5)
+        );
+        assert_eq!(ReplaySpeed::Unbounded.delay(100, 110), Duration::ZERO);
+        assert_eq!(ReplaySpeed::Multiplier(0.0).delay(100, 110), Duration::ZERO);
+        assert_eq!(ReplaySpeed::Realtime.delay(110, 100), Duration::ZERO);
+    }
+}
@@ -0,0 +1,969 @@
+use std::{
+    collections::VecDeque,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use lru::LruCache;
+use rand::Rng;
+use tokio::{
+    sync::mpsc::{self, Permit, Receiver, Sender},
+    time::{sleep, timeout},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    common::{
+        hasher::{Hasher, Keccak256Hasher},
+        rate_limiter::RateLimiter,
+        retry::RetryPolicy,
+        traits::ActorError,
+    },
+    da_watcher::common::{
+        BackpressurePolicy, ProposalManifest, RateLimitMode, WatcherError, WatcherProgress,
+        WatcherStart,
+    },
+    datasource::common::DataQuery,
+    traits::{BlockSource, DataAvailabilityWatcher},
+};
+
+/// Upper bound on the delay between retries of a failing fetch.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Recently emitted `(block_number, data_hash)` pairs.
+type SeenProposals = Arc<Mutex<LruCache<(u64, B256), ()>>>;
+
+type WatchItem = Result<ProposalManifest, WatcherError>;
+
+/// Outcome of one step of the watch loop.
+enum Polled {
+    /// The source's latest block number.
+    Head(u64),
+    /// The proposal of the block that was fetched, if it carries one.
+    Block(Option<ProposalManifest>),
+}
+
+/// Polls a [`DataSourceFetcher`] block by block and emits a [`ProposalManifest`] for every
+/// block that carries a payload.
+///
+/// Blocks up to the source's latest one are fetched back to back; only once caught up does the
+/// watcher wait `poll_interval` before checking for a new head, so it never asks for a block
+/// that does not exist yet.
+///
+/// A failed fetch is sent to the receiver as a [`WatcherError`] and retried with backoff, unless
+/// it is [unrecoverable](ActorError::is_unrecoverable) or the retry policy is exhausted, in which
+/// case watching stops after sending it.
+pub struct DAWatcher<F> {
+    fetcher: Arc<F>,
+    poll_interval: Duration,
+    buffer_size: usize,
+    start_from: WatcherStart,
+    /// Shared across `watch` calls so a re-watch over an overlapping range does not re-emit.
+    seen: Option<SeenProposals>,
+    /// Fraction of each delay randomly added or subtracted, e.g. `0.1` for ±10%.
+    jitter: f64,
+    backpressure: BackpressurePolicy,
+    send_timeout: Option<Duration>,
+    rate_limit: Option<(RateLimiter, RateLimitMode)>,
+    retry: RetryPolicy,
+    hasher: Arc<dyn Hasher>,
+    progress: WatcherProgress,
+    cancel: CancellationToken,
+}
+
+impl<F> DAWatcher<F> {
+    /// Creates a watcher that begins at `start_from` and skips any proposal among the last
+    /// `dedup_window` it emitted; a window of `0` disables deduplication.
+    ///
+    /// `buffer_size` is the capacity of the proposal channel. What happens once it is full is
+    /// set by [`DAWatcher::with_backpressure`].
+    pub fn new(
+        fetcher: F,
+        poll_interval: Duration,
+        buffer_size: usize,
+        dedup_window: usize,
+        start_from: WatcherStart,
+    ) -> Self {
+        Self {
+            fetcher: Arc::new(fetcher),
+            poll_interval,
+            buffer_size,
+            start_from,
+            seen: NonZeroUsize::new(dedup_window)
+                .map(|window| Arc::new(Mutex::new(LruCache::new(window)))),
+            jitter: 0.0,
+            backpressure: BackpressurePolicy::default(),
+            send_timeout: None,
+            rate_limit: None,
+            retry: RetryPolicy {
+                max_retries: u32::MAX,
+                base_delay: poll_interval,
+                max_delay: MAX_BACKOFF,
+                jitter: Duration::ZERO,
+            },
+            hasher: Arc::new(Keccak256Hasher),
+            progress: WatcherProgress::default(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Randomizes every poll and backoff delay by up to `jitter` of its length (clamped to
+    /// `0.0..=1.0`), so watchers restarted together do not poll in lockstep.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Commitment scheme of the DA layer, used for each manifest's data hash; `keccak256` by
+    /// default.
+    pub fn with_hasher(mut self, hasher: Arc<dyn Hasher>) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// What to do with new proposals while the channel is full; blocks by default.
+    pub fn with_backpressure(mut self, policy: BackpressurePolicy) -> Self {
+        self.backpressure = policy;
+        self
+    }
+
+    /// How long the consumer may take to make room. [`BackpressurePolicy::Block`] warns each
+    /// time it elapses and keeps waiting; the dropping policies drop once it elapses, or right
+    /// away when it is unset.
+    pub fn with_send_timeout(mut self, send_timeout: Duration) -> Self {
+        self.send_timeout = Some(send_timeout);
+        self
+    }
+
+    /// Emits proposals no faster than `limiter` allows, delaying or dropping the excess as set
+    /// by `mode`. Watcher errors are not limited.
+    pub fn with_rate_limit(mut self, limiter: RateLimiter, mode: RateLimitMode) -> Self {
+        self.rate_limit = Some((limiter, mode));
+        self
+    }
+
+    /// Returns how many proposals were dropped under backpressure or the rate limit so far.
+    pub fn dropped_proposals(&self) -> u64 {
+        self.progress.dropped_proposals()
+    }
+
+    /// Returns a handle for checking on the watcher from other tasks, e.g. a health check.
+    pub fn progress(&self) -> WatcherProgress {
+        self.progress.clone()
+    }
+
+    /// Backoff between retries of a failing fetch. By default the delay doubles from
+    /// `poll_interval` up to a minute and the watcher never gives up on recoverable errors.
+    /// Jitter is applied on top as set by [`DAWatcher::with_jitter`].
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Stops the spawned polling task once `cancel` fires. The receiver stays open so items
+    /// already buffered can still be drained.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
+#[async_trait]
+impl<F> DataAvailabilityWatcher for DAWatcher<F>
+where
+    F: BlockSource<Query = DataQuery> + Send + Sync + 'static,
+    F::RawDataType: Send,
+    F::DecodedType: Send,
+    F::DecompressedType: AsRef<[u8]> + Send,
+    F::Error: ActorError + Send,
+{
+    type ProposalManifest = ProposalManifest;
+    type DataSourceFetcher = F;
+    type Error = WatcherError;
+
+    async fn watch(
+        &self,
+    ) -> Result<Receiver<Result<ProposalManifest, WatcherError>>, WatcherError> {
+        let start_block = start_block(self.fetcher.as_ref(), self.start_from)
+            .await
+            .map_err(|e| WatcherError::FetchError(format!("start block: {}", e)))?;
+        info!(
+            "Watching from block {} ({:?})",
+            start_block, self.start_from
+        );
+
+        let (tx, rx) = mpsc::channel(self.buffer_size);
+        let fetcher = self.fetcher.clone();
+        let poll_interval = self.poll_interval;
+        let cancel = self.cancel.clone();
+        let jitter = self.jitter;
+        let retry = self.retry.clone();
+        let hasher = self.hasher.clone();
+        let rate_limit = self.rate_limit.clone();
+        let mut outbox = Outbox {
+            tx,
+            backlog: VecDeque::new(),
+            capacity: self.buffer_size.max(1),
+            policy: self.backpressure,
+            send_timeout: self.send_timeout,
+            progress: self.progress.clone(),
+            seen: self.seen.clone(),
+        };
+
+        let progress = self.progress.clone();
+        let running = Running::start(self.progress.clone());
+        tokio::spawn(async move {
+            let _running = running;
+            let mut block_number = start_block;
+            // Highest block known to exist; re-read only once it has been fetched.
+            let mut head = None;
+            let mut failures = 0;
+
+            loop {
+                if !outbox.flush() {
+                    info!("Proposal receiver dropped, stopping watcher");
+                    break;
+                }
+
+                let behind = head.is_some_and(|head| block_number <= head);
+                let result = tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    result = poll(fetcher.as_ref(), block_number, behind, hasher.as_ref()) => result,
+                };
+
+                let delay = match result {
+                    Ok(Polled::Head(latest)) => {
+                        failures = 0;
+                        head = Some(latest);
+                        if latest >= block_number {
+                            continue;
+                        }
+                        poll_interval
+                    }
+                    Ok(Polled::Block(proposal)) => {
+                        failures = 0;
+
+                        if let Some(proposal) = proposal {
+                            if outbox.already_emitted(&proposal) {
+                                info!(
+                                    "Skipping already emitted proposal for block {}",
+                                    block_number
+                                );
+                                block_number += 1;
+                                continue;
+                            }
+
+                            match &rate_limit {
+                                Some((limiter, RateLimitMode::Delay)) => tokio::select! {
+                                    _ = cancel.cancelled() => break,
+                                    _ = limiter.acquire() => {}
+                                },
+                                Some((limiter, RateLimitMode::Drop)) if !limiter.try_acquire() => {
+                                    let dropped = progress.record_drop();
+                                    warn!(
+                                        "Rate limit exceeded, dropping proposal for block {} ({} dropped so far)",
+                                        block_number, dropped
+                                    );
+                                    block_number += 1;
+                                    continue;
+                                }
+                                _ => {}
+                            }
+
+                            let open = tokio::select! {
+                                _ = cancel.cancelled() => break,
+                                open = outbox.send(Ok(proposal)) => open,
+                            };
+                            if !open {
+                                info!("Proposal receiver dropped, stopping watcher");
+                                break;
+                            }
+                        }
+
+                        // Catch up to the head without waiting between blocks.
+                        block_number += 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        let fatal = e.is_unrecoverable() || failures >= retry.max_retries;
+                        let err =
+                            WatcherError::FetchError(format!("block {}: {}", block_number, e));
+                        failures += 1;
+                        progress.record_error();
+                        let backoff = retry.delay(failures);
+                        if fatal {
+                            error!("Stopping watcher: {}", err);
+                        } else {
+                            warn!(
+                                "Fetch for block {} failed (attempt {}), retrying in {:?}: {}",
+                                block_number, failures, backoff, e
+                            );
+                        }
+
+                        let open = tokio::select! {
+                            _ = cancel.cancelled() => break,
+                            open = outbox.send(Err(err)) => open,
+                        };
+                        if fatal {
+                            // Make sure the consumer learns why the channel is closing.
+                            tokio::select! {
+                                _ = cancel.cancelled() => {}
+                                _ = outbox.drain() => {}
+                            }
+                        }
+                        if fatal || !open {
+                            break;
+                        }
+                        backoff
+                    }
+                };
+
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = sleep(jittered(delay, jitter)) => {}
+                }
+            }
+
+            info!("Watcher stopped at block {}", block_number);
+        });
+
+        Ok(rx)
+    }
+}
+
+/// Marks the watcher running until dropped with its polling task, however that task ends.
+struct Running(WatcherProgress);
+
+impl Running {
+    fn start(progress: WatcherProgress) -> Self {
+        progress.set_running(true);
+        Self(progress)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.set_running(false);
+    }
+}
+
+/// Delivers watcher items to the consumer, applying the backpressure policy.
+struct Outbox {
+    tx: Sender<WatchItem>,
+    /// Items not yet accepted by the consumer, oldest first.
+    backlog: VecDeque<WatchItem>,
+    capacity: usize,
+    policy: BackpressurePolicy,
+    send_timeout: Option<Duration>,
+    progress: WatcherProgress,
+    /// Proposals handed to the consumer; one dropped before delivery is not recorded.
+    seen: Option<SeenProposals>,
+}
+
+impl Outbox {
+    /// Queues `item` and delivers the backlog. Returns `false` once the receiver is dropped.
+    async fn send(&mut self, item: WatchItem) -> bool {
+        self.backlog.push_back(item);
+        if self.policy == BackpressurePolicy::DropOldest && self.backlog.len() > self.capacity {
+            let oldest = self.backlog.pop_front();
+            self.record_drop(oldest);
+        }
+
+        while !self.backlog.is_empty() {
+            let reserved = match (self.policy, self.send_timeout) {
+                (BackpressurePolicy::Block, None) => self.tx.reserve().await,
+                (policy, send_timeout) => {
+                    let wait = send_timeout.unwrap_or_default();
+                    match timeout(wait, self.tx.reserve()).await {
+                        Ok(reserved) => reserved,
+                        Err(_) if policy == BackpressurePolicy::Block => {
+                            warn!(
+                                "Consumer did not accept an item within {:?} ({} waiting)",
+                                wait,
+                                self.backlog.len()
+                            );
+                            continue;
+                        }
+                        Err(_) => {
+                            if policy == BackpressurePolicy::DropNewest {
+                                let newest = self.backlog.pop_back();
+                                self.record_drop(newest);
+                            }
+                            return true;
+                        }
+                    }
+                }
+            };
+            let Ok(permit) = reserved else {
+                return false;
+            };
+            if let Some(item) = self.backlog.pop_front() {
+                self.deliver(permit, item);
+            }
+        }
+        true
+    }
+
+    /// Returns whether `proposal` was already delivered within the dedup window.
+    fn already_emitted(&self, proposal: &ProposalManifest) -> bool {
+        self.seen.as_ref().is_some_and(|seen| {
+            seen.lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .contains(&(proposal.block_number, proposal.data_hash))
+        })
+    }
+
+    /// Sends `item` through `permit`, recording a proposal as emitted only once it is delivered.
+    fn deliver(&self, permit: Permit<'_, WatchItem>, item: WatchItem) {
+        if let (Some(seen), Ok(proposal)) = (&self.seen, &item) {
+            seen.lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .put((proposal.block_number, proposal.data_hash), ());
+        }
+        permit.send(item);
+    }
+
+    fn record_drop(&self, item: Option<WatchItem>) {
+        match item {
+            Some(Ok(proposal)) => {
+                let dropped = self.progress.record_drop();
+                warn!(
+                    "Consumer is behind, dropping undelivered proposal for block {} ({} dropped so far)",
+                    proposal.block_number, dropped
+                );
+            }
+            Some(Err(e)) => warn!("Consumer is behind, dropping watcher error: {}", e),
+            None => {}
+        }
+    }
+
+    /// Delivers the whole backlog, however long the consumer takes.
+    async fn drain(&mut self) {
+        while let Some(item) = self.backlog.pop_front() {
+            let Ok(permit) = self.tx.reserve().await else {
+                return;
+            };
+            self.deliver(permit, item);
+        }
+    }
+
+    /// Delivers as much of the backlog as the channel has room for without waiting. Returns
+    /// `false` once the receiver is dropped.
+    fn flush(&mut self) -> bool {
+        while !self.backlog.is_empty() {
+            match self.tx.try_reserve() {
+                Ok(permit) => {
+                    if let Some(item) = self.backlog.pop_front() {
+                        self.deliver(permit, item);
+                    }
+                }
+                Err(mpsc::error::TrySendError::Full(())) => break,
+                Err(mpsc::error::TrySendError::Closed(())) => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Resolves `start` to a block number.
+async fn start_block<F: BlockSource>(fetcher: &F, start: WatcherStart) -> Result<u64, F::Error> {
+    match start {
+        WatcherStart::Genesis => Ok(0),
+        WatcherStart::Block(block_number) => Ok(block_number),
+        WatcherStart::Latest => fetcher.latest_block_number().await,
+        WatcherStart::Timestamp(timestamp) => first_block_at(fetcher, timestamp).await,
+    }
+}
+
+/// Binary-searches for the first block with a timestamp at or after `timestamp`, returning the
+/// block after the latest one if none is yet.
+async fn first_block_at<F: BlockSource>(fetcher: &F, timestamp: u64) -> Result<u64, F::Error> {
+    let mut low = 0;
+    let mut high = fetcher.latest_block_number().await? + 1;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if fetcher.block_timestamp(mid).await? < timestamp {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Returns the current Unix time in seconds, or `0` with a warning if the system clock is set
+/// before the epoch.
+pub fn current_unix_secs() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs(),
+        Err(e) => {
+            warn!("System clock is before the Unix epoch: {}", e);
+            0
+        }
+    }
+}
+
+/// Fetches block `block_number` while it is known to exist, i.e. while `behind` the last seen
+/// head, and otherwise reads the head again.
+async fn poll<F>(
+    fetcher: &F,
+    block_number: u64,
+    behind: bool,
+    hasher: &dyn Hasher,
+) -> Result<Polled, F::Error>
+where
+    F: BlockSource<Query = DataQuery>,
+    F::DecompressedType: AsRef<[u8]>,
+{
+    if behind {
+        fetch_proposal(fetcher, block_number, hasher)
+            .await
+            .map(Polled::Block)
+    } else {
+        fetcher.latest_block_number().await.map(Polled::Head)
+    }
+}
+
+/// Runs block `block_number` through the fetcher's fetch, decode and decompress stages,
+/// returning its proposal if it carries a payload. The manifest is stamped with the L1 block's
+/// timestamp so every node derives the same L2 block from it.
+#[instrument(name = "watch_block", skip_all, fields(block_number))]
+async fn fetch_proposal<F>(
+    fetcher: &F,
+    block_number: u64,
+    hasher: &dyn Hasher,
+) -> Result<Option<ProposalManifest>, F::Error>
+where
+    F: BlockSource<Query = DataQuery>,
+    F::DecompressedType: AsRef<[u8]>,
+{
+    let query = DataQuery {
+        from_block: block_number,
+        to_block: block_number,
+    };
+    let raw = fetcher.fetch(&query).await?;
+    let decoded = fetcher.decode(raw).await?;
+    let payload = fetcher.decompress(decoded).await?;
+    let payload = payload.as_ref();
+    if payload.is_empty() {
+        return Ok(None);
+    }
+
+    let timestamp = fetcher.block_timestamp(block_number).await?;
+    Ok(Some(ProposalManifest::with_hasher(
+        block_number,
+        timestamp,
+        payload,
+        hasher,
+    )))
+}
+
+/// Shifts `delay` by a random amount within `±jitter` of it, never below one millisecond.
+fn jittered(delay: Duration, jitter: f64) -> Duration {
+    if jitter == 0.0 {
+        return delay;
+    }
+    let factor = 1.0 + rand::thread_rng().gen_range(-jitter..=jitter);
+    delay.mul_f64(factor).max(Duration::from_millis(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::{common::FetcherError, mock_fetcher::MockDataSourceFetcher};
+
+    const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+    fn block(block_number: u64) -> DataQuery {
+        DataQuery {
+            from_block: block_number,
+            to_block: block_number,
+        }
+    }
+
+    /// Blocks `0..=head` where only the listed ones carry a payload.
+    fn fetcher(head: u64, proposals: &[u64]) -> MockDataSourceFetcher {
+        (0..=head).fold(MockDataSourceFetcher::new(), |fetcher, block_number| {
+            let data = if proposals.contains(&block_number) {
+                format!("batch {}", block_number).into_bytes()
+            } else {
+                Vec::new()
+            };
+            fetcher.with_response(block(block_number), data)
+        })
+    }
+
+    async fn next_block(rx: &mut Receiver<WatchItem>) -> u64 {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("watcher stalled")
+            .expect("channel closed")
+            .expect("watcher error")
+            .block_number
+    }
+
+    #[tokio::test]
+    async fn catches_up_to_head_without_polling() {
+        let watcher = DAWatcher::new(
+            fetcher(50, &[3, 17, 50]),
+            POLL_INTERVAL,
+            8,
+            0,
+            WatcherStart::Genesis,
+        );
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 3);
+        assert_eq!(next_block(&mut rx).await, 17);
+        assert_eq!(next_block(&mut rx).await, 50);
+        // Block 51 is not registered, so fetching past the head would surface an error.
+        assert!(tokio::time::timeout(Duration::from_millis(200), rx.recv())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn emits_block_timestamp() {
+        let fetcher = fetcher(2, &[2]).with_block_timestamp(2, 1_700_000_000);
+        let watcher = DAWatcher::new(fetcher, POLL_INTERVAL, 8, 0, WatcherStart::Genesis);
+        let mut rx = watcher.watch().await.unwrap();
+
+        let proposal = rx.recv().await.unwrap().unwrap();
+        assert_eq!(proposal.timestamp, 1_700_000_000);
+        assert_eq!(proposal.data_hash, keccak(b"batch 2"));
+    }
+
+    #[tokio::test]
+    async fn starts_at_block() {
+        let watcher = DAWatcher::new(
+            fetcher(10, &[2, 8]),
+            POLL_INTERVAL,
+            8,
+            0,
+            WatcherStart::Block(5),
+        );
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 8);
+    }
+
+    #[tokio::test]
+    async fn starts_at_latest() {
+        let watcher = DAWatcher::new(
+            fetcher(10, &[2, 10]),
+            POLL_INTERVAL,
+            8,
+            0,
+            WatcherStart::Latest,
+        );
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 10);
+    }
+
+    #[tokio::test]
+    async fn starts_at_timestamp() {
+        let fetcher = (0..=10).fold(fetcher(10, &[3, 6]), |fetcher, block_number| {
+            fetcher.with_block_timestamp(block_number, 1000 + block_number * 12)
+        });
+        let watcher = DAWatcher::new(
+            fetcher,
+            POLL_INTERVAL,
+            8,
+            0,
+            WatcherStart::Timestamp(1000 + 4 * 12),
+        );
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 6);
+    }
+
+    #[tokio::test]
+    async fn stops_on_unrecoverable_error() {
+        let fetcher = fetcher(3, &[1])
+            .with_error(block(2), FetcherError::DecodeError("bad blob".to_string()));
+        let watcher = DAWatcher::new(fetcher, POLL_INTERVAL, 8, 0, WatcherStart::Genesis);
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 1);
+        assert!(matches!(
+            rx.recv().await,
+            Some(Err(WatcherError::FetchError(_)))
+        ));
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancellation_closes_receiver() {
+        let cancel = CancellationToken::new();
+        let watcher = DAWatcher::new(fetcher(3, &[1]), POLL_INTERVAL, 8, 0, WatcherStart::Genesis)
+            .with_cancellation(cancel.clone());
+        let progress = watcher.progress();
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 1);
+        assert!(progress.is_running());
+        cancel.cancel();
+        assert!(tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("watcher did not stop")
+            .is_none());
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while progress.is_running() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("watcher still reported running");
+    }
+
+    #[tokio::test]
+    async fn skips_already_emitted_proposals() {
+        let watcher = DAWatcher::new(
+            fetcher(3, &[1, 3]),
+            POLL_INTERVAL,
+            8,
+            16,
+            WatcherStart::Genesis,
+        );
+
+        let mut first = watcher.watch().await.unwrap();
+        assert_eq!(next_block(&mut first).await, 1);
+        assert_eq!(next_block(&mut first).await, 3);
+
+        // Watching the same range again finds the same proposals, which were all delivered.
+        let mut second = watcher.watch().await.unwrap();
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), second.recv())
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn current_unix_secs_reads_the_clock() {
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let now = current_unix_secs();
+
+        // 2023-11-14, well before any clock this runs on.
+        assert!(now >= 1_700_000_000);
+        assert!((before..=before + 1).contains(&now));
+    }
+
+    fn keccak(data: &[u8]) -> B256 {
+        Keccak256Hasher.hash(data)
+    }
+
+    #[test]
+    fn jitter_stays_within_band() {
+        let delay = Duration::from_secs(10);
+        for _ in 0..1_000 {
+            let jittered = jittered(delay, 0.1);
+            assert!(jittered >= Duration::from_secs(9) && jittered <= Duration::from_secs(11));
+        }
+        assert_eq!(jittered(delay, 0.0), delay);
+        // Full jitter can shrink the delay to nothing, which is kept at a millisecond.
+        for _ in 0..1_000 {
+            assert!(jittered(Duration::from_millis(1), 1.0) >= Duration::from_millis(1));
+        }
+    }
+
+    #[tokio::test]
+    async fn surfaces_transient_errors_until_retries_run_out() {
+        let fetcher = fetcher(3, &[1]).with_error(
+            block(2),
+            FetcherError::NetworkError("timed out".to_string()),
+        );
+        let watcher = DAWatcher::new(fetcher, POLL_INTERVAL, 8, 0, WatcherStart::Genesis)
+            .with_retry_policy(RetryPolicy {
+                max_retries: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+                jitter: Duration::ZERO,
+            });
+        let progress = watcher.progress();
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 1);
+        // Every failed attempt reaches the consumer, the last one just before the channel closes.
+        for _ in 0..3 {
+            let item = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("watcher stalled");
+            assert!(
+                matches!(item, Some(Err(WatcherError::FetchError(e))) if e.contains("block 2"))
+            );
+        }
+        assert!(rx.recv().await.is_none());
+        assert_eq!(progress.errors(), 3);
+    }
+
+    async fn wait_for_drops(watcher: &DAWatcher<MockDataSourceFetcher>, dropped: u64) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while watcher.dropped_proposals() < dropped {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("proposals were not dropped");
+    }
+
+    #[tokio::test]
+    async fn send_timeout_gives_slow_consumer_time_to_catch_up() {
+        let watcher = DAWatcher::new(
+            fetcher(4, &[1, 2, 3, 4]),
+            POLL_INTERVAL,
+            1,
+            0,
+            WatcherStart::Genesis,
+        )
+        .with_backpressure(BackpressurePolicy::DropNewest)
+        .with_send_timeout(Duration::from_secs(1));
+        let mut rx = watcher.watch().await.unwrap();
+
+        for block_number in 1..=4 {
+            sleep(Duration::from_millis(20)).await;
+            assert_eq!(next_block(&mut rx).await, block_number);
+        }
+        assert_eq!(watcher.dropped_proposals(), 0);
+    }
+
+    #[tokio::test]
+    async fn send_timeout_drops_for_stalled_consumer() {
+        let watcher = DAWatcher::new(
+            fetcher(4, &[1, 2, 3, 4]),
+            POLL_INTERVAL,
+            1,
+            0,
+            WatcherStart::Genesis,
+        )
+        .with_backpressure(BackpressurePolicy::DropNewest)
+        .with_send_timeout(Duration::from_millis(20));
+        let mut rx = watcher.watch().await.unwrap();
+
+        wait_for_drops(&watcher, 3).await;
+        assert_eq!(next_block(&mut rx).await, 1);
+    }
+
+    /// A watcher over blocks `1..=4`, each with a proposal, and a single-slot channel.
+    fn backpressured(policy: BackpressurePolicy) -> DAWatcher<MockDataSourceFetcher> {
+        DAWatcher::new(
+            fetcher(4, &[1, 2, 3, 4]),
+            Duration::from_millis(10),
+            1,
+            0,
+            WatcherStart::Genesis,
+        )
+        .with_backpressure(policy)
+    }
+
+    #[tokio::test]
+    async fn block_policy_waits_for_the_consumer() {
+        let watcher = backpressured(BackpressurePolicy::Block);
+        let mut rx = watcher.watch().await.unwrap();
+
+        // Plenty of time to poll every block, were the watcher not held up.
+        sleep(Duration::from_millis(100)).await;
+        for block_number in 1..=4 {
+            assert_eq!(next_block(&mut rx).await, block_number);
+        }
+        assert_eq!(watcher.dropped_proposals(), 0);
+    }
+
+    #[tokio::test]
+    async fn drop_newest_policy_keeps_the_first_proposals() {
+        let watcher = backpressured(BackpressurePolicy::DropNewest);
+        let mut rx = watcher.watch().await.unwrap();
+
+        wait_for_drops(&watcher, 3).await;
+        assert_eq!(next_block(&mut rx).await, 1);
+        assert!(timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .is_err());
+        assert_eq!(watcher.dropped_proposals(), 3);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_policy_keeps_the_latest_proposals() {
+        let watcher = backpressured(BackpressurePolicy::DropOldest);
+        let mut rx = watcher.watch().await.unwrap();
+
+        wait_for_drops(&watcher, 2).await;
+        // The channel's slot and a backlog of one, holding the latest proposal.
+        assert_eq!(next_block(&mut rx).await, 1);
+        assert_eq!(next_block(&mut rx).await, 4);
+        assert_eq!(watcher.dropped_proposals(), 2);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_spaces_out_a_burst() {
+        let watcher = DAWatcher::new(
+            fetcher(10, &[1, 2, 3, 4, 5, 6]),
+            POLL_INTERVAL,
+            8,
+            0,
+            WatcherStart::Genesis,
+        )
+        .with_rate_limit(RateLimiter::new(50.0, 1), RateLimitMode::Delay);
+        let mut rx = watcher.watch().await.unwrap();
+
+        let started = tokio::time::Instant::now();
+        for expected in 1..=6 {
+            assert_eq!(next_block(&mut rx).await, expected);
+        }
+        // One proposal from the burst, then 20ms between each of the other five.
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(watcher.dropped_proposals(), 0);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_drops_the_excess_of_a_burst() {
+        let watcher = DAWatcher::new(
+            fetcher(10, &[1, 2, 3, 4, 5, 6]),
+            POLL_INTERVAL,
+            8,
+            0,
+            WatcherStart::Genesis,
+        )
+        .with_rate_limit(RateLimiter::new(0.1, 2), RateLimitMode::Drop);
+        let mut rx = watcher.watch().await.unwrap();
+
+        assert_eq!(next_block(&mut rx).await, 1);
+        assert_eq!(next_block(&mut rx).await, 2);
+        assert!(timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .is_err());
+        assert_eq!(watcher.dropped_proposals(), 4);
+    }
+
+    #[tokio::test]
+    async fn proposals_dropped_before_delivery_are_not_marked_emitted() {
+        let watcher = DAWatcher::new(
+            fetcher(4, &[1, 2, 3, 4]),
+            POLL_INTERVAL,
+            1,
+            16,
+            WatcherStart::Genesis,
+        )
+        .with_backpressure(BackpressurePolicy::DropNewest)
+        .with_send_timeout(Duration::from_millis(200));
+
+        let mut first = watcher.watch().await.unwrap();
+        wait_for_drops(&watcher, 3).await;
+        assert_eq!(next_block(&mut first).await, 1);
+        drop(first);
+
+        // Only block 1 reached a consumer, so watching again delivers the dropped ones.
+        let mut second = watcher.watch().await.unwrap();
+        for block_number in 2..=4 {
+            assert_eq!(next_block(&mut second).await, block_number);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod common;
+#[allow(clippy::module_inception)]
+pub mod da_watcher;
+pub mod replay_watcher;
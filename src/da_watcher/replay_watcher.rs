@@ -0,0 +1,152 @@
+use std::{fs, marker::PhantomData, path::Path};
+
+use async_trait::async_trait;
+use tokio::{
+    sync::mpsc::{self, Receiver},
+    time::sleep,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::{
+    da_watcher::common::{ProposalManifest, ReplaySpeed, WatcherError},
+    traits::{DataAvailabilityWatcher, DataSourceFetcher},
+};
+
+/// Emits a recorded sequence of proposals instead of watching the DA layer, e.g. to re-run a
+/// derivation regression or to exercise the driver with realistic timing.
+///
+/// Proposals are emitted in the recorded order, spaced by the gaps between their timestamps at
+/// the configured [`ReplaySpeed`]; the delays depend only on the recording, so every replay is
+/// paced the same. The channel closes once the last proposal is emitted. `F` is the DA source
+/// the recorded proposals' payloads are fetched from by the pipeline.
+pub struct ReplayWatcher<F> {
+    manifests: Vec<ProposalManifest>,
+    speed: ReplaySpeed,
+    buffer_size: usize,
+    cancel: CancellationToken,
+    _fetcher: PhantomData<fn() -> F>,
+}
+
+impl<F> ReplayWatcher<F> {
+    /// Replays `manifests` in real time through a channel of `buffer_size` proposals.
+    pub fn new(manifests: Vec<ProposalManifest>, buffer_size: usize) -> Self {
+        Self {
+            manifests,
+            speed: ReplaySpeed::default(),
+            buffer_size,
+            cancel: CancellationToken::new(),
+            _fetcher: PhantomData,
+        }
+    }
+
+    /// Reads the proposals to replay from `path`, one JSON manifest per line.
+    pub fn load(path: impl AsRef<Path>, buffer_size: usize) -> Result<Self, WatcherError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| WatcherError::Other(format!("{}: {}", path.display(), e)))?;
+        let manifests = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|e| WatcherError::Other(format!("{}: {}", path.display(), e)))?;
+        Ok(Self::new(manifests, buffer_size))
+    }
+
+    /// Replays at `speed`; real time by default.
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Stops the replay, closing the channel, once `cancel` fires.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
+#[async_trait]
+impl<F: DataSourceFetcher> DataAvailabilityWatcher for ReplayWatcher<F> {
+    type ProposalManifest = ProposalManifest;
+    type DataSourceFetcher = F;
+    type Error = WatcherError;
+
+    async fn watch(
+        &self,
+    ) -> Result<Receiver<Result<ProposalManifest, WatcherError>>, WatcherError> {
+        info!(
+            "Replaying {} proposals at {:?}",
+            self.manifests.len(),
+            self.speed
+        );
+        let (tx, rx) = mpsc::channel(self.buffer_size);
+        let manifests = self.manifests.clone();
+        let speed = self.speed;
+        let cancel = self.cancel.clone();
+
+        tokio::spawn(async move {
+            let mut previous = None;
+            for manifest in manifests {
+                if let Some(previous) = previous {
+                    tokio::select! {
+                        _ = cancel.cancelled() => return,
+                        _ = sleep(speed.delay(previous, manifest.timestamp)) => {}
+                    }
+                }
+                previous = Some(manifest.timestamp);
+                if tx.send(Ok(manifest)).await.is_err() {
+                    return;
+                }
+            }
+            info!("Replay finished");
+        });
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::datasource::mock_fetcher::MockDataSourceFetcher;
+
+    /// Replays three proposals 1 second apart at `speed`, returning when each was received.
+    async fn replay(speed: ReplaySpeed) -> Vec<Duration> {
+        let manifests = (0..3)
+            .map(|i| ProposalManifest::new(10 + i, 1_700_000_000 + i, b"batch"))
+            .collect();
+        let watcher = ReplayWatcher::<MockDataSourceFetcher>::new(manifests, 8).with_speed(speed);
+
+        let started = Instant::now();
+        let mut proposals = watcher.watch().await.unwrap();
+        let mut received = Vec::new();
+        while let Some(proposal) = proposals.recv().await {
+            proposal.unwrap();
+            received.push(started.elapsed());
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn multiplier_paces_emissions_by_the_scaled_timestamps() {
+        let fast = replay(ReplaySpeed::Multiplier(10.0)).await;
+        let faster = replay(ReplaySpeed::Multiplier(20.0)).await;
+
+        assert_eq!(fast.len(), 3);
+        assert!(fast[2] >= Duration::from_millis(200), "{:?}", fast);
+        assert!(faster[2] >= Duration::from_millis(100), "{:?}", faster);
+        // Doubling the multiplier halves the gaps, give or take scheduling.
+        assert!(
+            faster[2] < fast[2] - Duration::from_millis(50),
+            "{:?} {:?}",
+            fast,
+            faster
+        );
+
+        let unbounded = replay(ReplaySpeed::Unbounded).await;
+        assert!(unbounded[2] < Duration::from_millis(50), "{:?}", unbounded);
+    }
+}
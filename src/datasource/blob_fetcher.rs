@@ -1 +1,373 @@
-pub struct BlobDataSourceFetcher {}
+use std::marker::PhantomData;
+
+use alloy::{
+    consensus::Transaction,
+    eips::eip4844::{
+        builder::{SidecarCoder, SimpleCoder},
+        Blob, BlobTransactionSidecarItem, Bytes48, FIELD_ELEMENTS_PER_BLOB,
+    },
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
+    transports::{
+        http::reqwest::{Client, Url},
+        BoxTransport, Transport,
+    },
+};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+use tokio::sync::OnceCell;
+use tracing::instrument;
+
+use crate::{
+    datasource::{
+        common::{DataQuery, FetcherError},
+        compression::{decompress, DEFAULT_MAX_DECOMPRESSED_SIZE},
+        CompressionType,
+    },
+    traits::{BlockSource, DataSourceFetcher},
+};
+
+#[derive(Deserialize)]
+struct BeaconResponse<D> {
+    data: D,
+}
+
+#[derive(Deserialize)]
+struct BeaconGenesis {
+    genesis_time: String,
+}
+
+#[derive(Deserialize)]
+struct BeaconBlobSidecar {
+    index: String,
+    blob: Bytes,
+    kzg_commitment: Bytes48,
+    kzg_proof: Bytes48,
+}
+
+/// Fetches batches posted as EIP-4844 blobs.
+///
+/// For each L1 block, the blob versioned hashes of transactions sent to `inbox` are read from
+/// the execution node and the matching sidecars are fetched from the beacon node. Every blob is
+/// checked against its versioned hash and KZG proof before it is decoded.
+#[derive(Debug)]
+pub struct BlobDataSourceFetcher<P, T = BoxTransport> {
+    provider: P,
+    client: Client,
+    beacon_url: Url,
+    inbox: Address,
+    seconds_per_slot: u64,
+    genesis_time: OnceCell<u64>,
+    compression: CompressionType,
+    _transport: PhantomData<T>,
+}
+
+impl<P, T> BlobDataSourceFetcher<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    pub fn new(provider: P, beacon_url: Url, inbox: Address) -> Self {
+        Self {
+            provider,
+            client: Client::new(),
+            beacon_url,
+            inbox,
+            seconds_per_slot: 12,
+            genesis_time: OnceCell::new(),
+            compression: CompressionType::None,
+            _transport: PhantomData,
+        }
+    }
+
+    pub fn with_seconds_per_slot(mut self, seconds_per_slot: u64) -> Self {
+        self.seconds_per_slot = seconds_per_slot;
+        self
+    }
+
+    /// Compression applied to the batch before it was encoded into blobs.
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    async fn beacon_get<D: DeserializeOwned>(&self, path: &str) -> Result<D, FetcherError> {
+        let url = self
+            .beacon_url
+            .join(path)
+            .map_err(|e| FetcherError::Other(e.to_string()))?;
+
+        let response: BeaconResponse<D> = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?;
+
+        Ok(response.data)
+    }
+
+    async fn slot_at(&self, timestamp: u64) -> Result<u64, FetcherError> {
+        let genesis_time = *self
+            .genesis_time
+            .get_or_try_init(|| async {
+                let genesis: BeaconGenesis = self.beacon_get("eth/v1/beacon/genesis").await?;
+                genesis
+                    .genesis_time
+                    .parse::<u64>()
+                    .map_err(|e| FetcherError::DecodeError(format!("genesis time: {}", e)))
+            })
+            .await?;
+
+        Ok(timestamp.saturating_sub(genesis_time) / self.seconds_per_slot)
+    }
+
+    /// Returns the verified blobs posted to the inbox in `block_number`, in transaction order.
+    async fn fetch_block_blobs(&self, block_number: u64) -> Result<Vec<Blob>, FetcherError> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Full)
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+            .ok_or_else(|| FetcherError::Other(format!("block {} not found", block_number)))?;
+
+        let mut versioned_hashes: Vec<B256> = Vec::new();
+        for tx in block.transactions.txns() {
+            if tx.to() == Some(self.inbox) {
+                versioned_hashes.extend(tx.blob_versioned_hashes().unwrap_or_default());
+            }
+        }
+        if versioned_hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let slot = self.slot_at(block.header.timestamp).await?;
+        let sidecars: Vec<BeaconBlobSidecar> = self
+            .beacon_get(&format!("eth/v1/beacon/blob_sidecars/{}", slot))
+            .await?;
+        let sidecars = sidecars
+            .into_iter()
+            .map(sidecar_item)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        verified_blobs(&versioned_hashes, &sidecars, block_number)
+    }
+}
+
+#[async_trait]
+impl<P, T> DataSourceFetcher for BlobDataSourceFetcher<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    type Query = DataQuery;
+    type Compression = CompressionType;
+    type RawDataType = Vec<Blob>;
+    type DecodedType = Vec<u8>;
+    type DecompressedType = Vec<u8>;
+    type Error = FetcherError;
+
+    #[instrument(skip_all, fields(from_block = query.from_block, to_block = query.to_block))]
+    async fn fetch(&self, query: &DataQuery) -> Result<Vec<Blob>, FetcherError> {
+        let mut blobs = Vec::new();
+        for block_number in query.from_block..=query.to_block {
+            blobs.extend(self.fetch_block_blobs(block_number).await?);
+        }
+        Ok(blobs)
+    }
+
+    /// Strips the field-element padding and length prefixes, concatenating the payload of every
+    /// blob.
+    #[instrument(skip_all, fields(chunks = raw.len()))]
+    async fn decode(&self, raw: Vec<Blob>) -> Result<Vec<u8>, FetcherError> {
+        decode_blobs(&raw)
+            .ok_or_else(|| FetcherError::DecodeError("malformed blob encoding".to_string()))
+    }
+
+    #[instrument(skip_all, fields(bytes = data.len()))]
+    async fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+        decompress(&self.compression, &data, DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    fn compression_type(&self) -> CompressionType {
+        self.compression.clone()
+    }
+}
+
+#[async_trait]
+impl<P, T> BlockSource for BlobDataSourceFetcher<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    async fn latest_block_number(&self) -> Result<u64, FetcherError> {
+        self.provider
+            .get_block_number()
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, FetcherError> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+            .ok_or_else(|| FetcherError::Other(format!("block {} not found", block_number)))?;
+        Ok(block.header.timestamp)
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, FetcherError> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+            .ok_or_else(|| FetcherError::Other(format!("block {} not found", block_number)))?;
+        Ok(block.header.hash)
+    }
+}
+
+/// Returns the blob of each versioned hash, in order, after checking it against its KZG proof.
+///
+/// Blobs are 128 KiB, so this avoids iterator adaptors that would each hold one on the stack.
+fn verified_blobs(
+    versioned_hashes: &[B256],
+    sidecars: &[BlobTransactionSidecarItem],
+    block_number: u64,
+) -> Result<Vec<Blob>, FetcherError> {
+    let mut blobs = Vec::with_capacity(versioned_hashes.len());
+    for hash in versioned_hashes {
+        let sidecar = sidecars
+            .iter()
+            .find(|sidecar| sidecar.to_kzg_versioned_hash() == hash.0)
+            .ok_or_else(|| {
+                FetcherError::DecodeError(format!(
+                    "no sidecar matches versioned hash {} in block {}",
+                    hash, block_number
+                ))
+            })?;
+        sidecar.verify_blob_kzg_proof().map_err(|e| {
+            FetcherError::DecodeError(format!("KZG proof check failed for blob {}: {}", hash, e))
+        })?;
+        blobs.push(*sidecar.blob);
+    }
+    Ok(blobs)
+}
+
+/// Decodes the blobs of every transaction in a block. The coder stops at the zero padding that
+/// ends each transaction's data, so decoding resumes at the blob after it.
+fn decode_blobs(blobs: &[Blob]) -> Option<Vec<u8>> {
+    let mut payload = Vec::new();
+    let mut next = 0;
+    while next < blobs.len() {
+        let chunks = SimpleCoder::default().decode_all(&blobs[next..])?;
+        // Each chunk is a length prefix followed by 31 bytes per field element.
+        let field_elements: usize = chunks
+            .iter()
+            .map(|chunk| 1 + chunk.len().div_ceil(31))
+            .sum();
+        next += field_elements
+            .div_ceil(FIELD_ELEMENTS_PER_BLOB as usize)
+            .max(1);
+        payload.extend(chunks.concat());
+    }
+    Some(payload)
+}
+
+fn sidecar_item(sidecar: BeaconBlobSidecar) -> Result<BlobTransactionSidecarItem, FetcherError> {
+    let index = sidecar
+        .index
+        .parse()
+        .map_err(|e| FetcherError::DecodeError(format!("sidecar index: {}", e)))?;
+    let blob = Blob::try_from(sidecar.blob.as_ref())
+        .map_err(|e| FetcherError::DecodeError(format!("sidecar {} blob: {}", index, e)))?;
+
+    Ok(BlobTransactionSidecarItem {
+        index,
+        blob: Box::new(blob),
+        kzg_commitment: sidecar.kzg_commitment,
+        kzg_proof: sidecar.kzg_proof,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{eips::eip4844::builder::SidecarBuilder, providers::ProviderBuilder};
+    use serde_json::json;
+
+    use super::*;
+
+    /// Two payloads posted in one blob each, with the versioned hashes of their blob
+    /// transactions and their sidecars as served by the beacon node's `blob_sidecars` endpoint.
+    fn fixture() -> (Vec<Vec<u8>>, Vec<B256>, Vec<BeaconBlobSidecar>) {
+        let payloads: Vec<Vec<u8>> = vec![
+            (0..1_000u32).map(|i| (i % 251) as u8).collect(),
+            b"second batch".to_vec(),
+        ];
+        let mut versioned_hashes = Vec::new();
+        let mut sidecars = Vec::new();
+        for (index, payload) in payloads.iter().enumerate() {
+            let sidecar = SidecarBuilder::<SimpleCoder>::from_slice(payload)
+                .build()
+                .unwrap();
+            versioned_hashes.extend(sidecar.versioned_hashes());
+            let sidecar = json!({
+                "index": index.to_string(),
+                "blob": Bytes::copy_from_slice(sidecar.blobs[0].as_slice()),
+                "kzg_commitment": sidecar.commitments[0],
+                "kzg_proof": sidecar.proofs[0],
+            });
+            sidecars.push(serde_json::from_value(sidecar).unwrap());
+        }
+        (payloads, versioned_hashes, sidecars)
+    }
+
+    fn items(sidecars: Vec<BeaconBlobSidecar>) -> Vec<BlobTransactionSidecarItem> {
+        sidecars
+            .into_iter()
+            .map(|s| sidecar_item(s).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn decodes_verified_sidecars() {
+        let (payloads, versioned_hashes, sidecars) = fixture();
+        let fetcher = BlobDataSourceFetcher::new(
+            ProviderBuilder::new().on_http("http://127.0.0.1:1".parse().unwrap()),
+            "http://127.0.0.1:1".parse().unwrap(),
+            Address::ZERO,
+        );
+
+        let blobs = verified_blobs(&versioned_hashes, &items(sidecars), 1).unwrap();
+        let decoded = fetcher.decode(blobs).await.unwrap();
+
+        assert_eq!(decoded, payloads.concat());
+    }
+
+    #[test]
+    fn rejects_mismatched_proof() {
+        let (_, versioned_hashes, mut sidecars) = fixture();
+        let proof = sidecars[0].kzg_proof;
+        sidecars[0].kzg_proof = sidecars[1].kzg_proof;
+        sidecars[1].kzg_proof = proof;
+
+        let err = verified_blobs(&versioned_hashes, &items(sidecars), 1).unwrap_err();
+
+        assert!(matches!(err, FetcherError::DecodeError(_)));
+    }
+
+    #[test]
+    fn rejects_unknown_versioned_hash() {
+        let (_, _, sidecars) = fixture();
+
+        let err = verified_blobs(&[B256::repeat_byte(0x01)], &items(sidecars), 1).unwrap_err();
+
+        assert!(matches!(err, FetcherError::DecodeError(_)));
+    }
+}
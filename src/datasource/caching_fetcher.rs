@@ -0,0 +1,251 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use alloy::primitives::{keccak256, B256};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::fs;
+use tracing::{info, warn};
+
+use crate::traits::{BlockSource, DataSourceFetcher};
+
+/// Caches another fetcher's raw responses on disk, so fetching the same query again (e.g. when
+/// re-deriving after a restart) skips the network.
+///
+/// Each entry is a JSON file named after the keccak256 hash of the serialized query. Entries
+/// never expire unless a [TTL](CachingFetcher::with_ttl) is set, so only cache data that cannot
+/// change, such as blocks that are already finalized. Use one directory per wrapped fetcher.
+///
+/// Cache reads and writes are best effort: a failure is logged and the inner fetcher is used.
+#[derive(Clone, Debug)]
+pub struct CachingFetcher<F> {
+    inner: F,
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl<F> CachingFetcher<F> {
+    pub fn new(inner: F, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+            ttl: None,
+        }
+    }
+
+    /// Refetches entries older than `ttl`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    fn entry_path(&self, query: &impl Serialize) -> Option<PathBuf> {
+        let key = serde_json::to_vec(query)
+            .map_err(|e| warn!("Not caching unserializable query: {}", e))
+            .ok()?;
+        Some(self.dir.join(format!("{:x}.json", keccak256(key))))
+    }
+
+    async fn read<R: DeserializeOwned>(&self, path: &Path) -> Option<R> {
+        let metadata = fs::metadata(path).await.ok()?;
+        if let Some(ttl) = self.ttl {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or(Duration::MAX);
+            if age > ttl {
+                return None;
+            }
+        }
+
+        let contents = fs::read(path).await.ok()?;
+        serde_json::from_slice(&contents)
+            .map_err(|e| warn!("Ignoring corrupt cache entry {}: {}", path.display(), e))
+            .ok()
+    }
+
+    /// Writes to a sibling temp file and renames it into place, so readers never see a partial
+    /// entry.
+    async fn write(&self, path: &Path, contents: Vec<u8>) {
+        let result = async {
+            fs::create_dir_all(&self.dir).await?;
+            let tmp_path = path.with_extension("tmp");
+            fs::write(&tmp_path, contents).await?;
+            fs::rename(&tmp_path, path).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            warn!("Failed to write cache entry {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl<F> DataSourceFetcher for CachingFetcher<F>
+where
+    F: DataSourceFetcher + Send + Sync,
+    F::Query: Serialize + Sync,
+    F::RawDataType: Serialize + DeserializeOwned + Send,
+    F::DecodedType: Send,
+{
+    type Query = F::Query;
+    type Compression = F::Compression;
+    type RawDataType = F::RawDataType;
+    type DecodedType = F::DecodedType;
+    type DecompressedType = F::DecompressedType;
+    type Error = F::Error;
+
+    async fn fetch(&self, query: &F::Query) -> Result<F::RawDataType, F::Error> {
+        let Some(path) = self.entry_path(query) else {
+            return self.inner.fetch(query).await;
+        };
+        if let Some(raw) = self.read(&path).await {
+            info!("Serving fetch from cache entry {}", path.display());
+            return Ok(raw);
+        }
+
+        let raw = self.inner.fetch(query).await?;
+        match serde_json::to_vec(&raw) {
+            Ok(contents) => self.write(&path, contents).await,
+            Err(e) => warn!("Not caching unserializable response: {}", e),
+        }
+        Ok(raw)
+    }
+
+    async fn decode(&self, raw: F::RawDataType) -> Result<F::DecodedType, F::Error> {
+        self.inner.decode(raw).await
+    }
+
+    async fn decompress(&self, data: F::DecodedType) -> Result<F::DecompressedType, F::Error> {
+        self.inner.decompress(data).await
+    }
+
+    fn compression_type(&self) -> F::Compression {
+        self.inner.compression_type()
+    }
+}
+
+/// Heights and timestamps always come from the inner fetcher, uncached.
+#[async_trait]
+impl<F> BlockSource for CachingFetcher<F>
+where
+    F: BlockSource + Send + Sync,
+    F::Query: Serialize + Sync,
+    F::RawDataType: Serialize + DeserializeOwned + Send,
+    F::DecodedType: Send,
+{
+    async fn latest_block_number(&self) -> Result<u64, F::Error> {
+        self.inner.latest_block_number().await
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, F::Error> {
+        self.inner.block_timestamp(block_number).await
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, F::Error> {
+        self.inner.block_hash(block_number).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::datasource::{
+        common::{DataQuery, FetcherError},
+        CompressionType,
+    };
+
+    /// Serves the query's block range as bytes, counting fetches.
+    #[derive(Default)]
+    struct CountingFetcher {
+        fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DataSourceFetcher for CountingFetcher {
+        type Query = DataQuery;
+        type Compression = CompressionType;
+        type RawDataType = Vec<u8>;
+        type DecodedType = Vec<u8>;
+        type DecompressedType = Vec<u8>;
+        type Error = FetcherError;
+
+        async fn fetch(&self, query: &DataQuery) -> Result<Vec<u8>, FetcherError> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![query.from_block as u8, query.to_block as u8])
+        }
+
+        async fn decode(&self, raw: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+            Ok(raw)
+        }
+
+        async fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+            Ok(data)
+        }
+
+        fn compression_type(&self) -> CompressionType {
+            CompressionType::None
+        }
+    }
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "based-rollup-cache-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn second_fetch_is_served_from_disk() {
+        let dir = cache_dir("hit");
+        let fetcher = CachingFetcher::new(CountingFetcher::default(), &dir);
+        let query = DataQuery {
+            from_block: 3,
+            to_block: 4,
+        };
+
+        assert_eq!(fetcher.fetch(&query).await.unwrap(), vec![3, 4]);
+        assert_eq!(fetcher.fetch(&query).await.unwrap(), vec![3, 4]);
+        assert_eq!(fetcher.inner().fetches.load(Ordering::Relaxed), 1);
+
+        // Other queries have entries of their own.
+        let other = DataQuery {
+            from_block: 5,
+            to_block: 5,
+        };
+        assert_eq!(fetcher.fetch(&other).await.unwrap(), vec![5, 5]);
+        assert_eq!(fetcher.inner().fetches.load(Ordering::Relaxed), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_refetched() {
+        let dir = cache_dir("ttl");
+        let fetcher =
+            CachingFetcher::new(CountingFetcher::default(), &dir).with_ttl(Duration::ZERO);
+        let query = DataQuery {
+            from_block: 3,
+            to_block: 4,
+        };
+
+        fetcher.fetch(&query).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        fetcher.fetch(&query).await.unwrap();
+
+        assert_eq!(fetcher.inner().fetches.load(Ordering::Relaxed), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,240 @@
+use std::marker::PhantomData;
+
+use alloy::{
+    consensus::Transaction,
+    primitives::{Address, Bytes, B256},
+    providers::Provider,
+    rpc::types::BlockTransactionsKind,
+    transports::{BoxTransport, Transport},
+};
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    datasource::{
+        common::{DataQuery, FetcherError},
+        compression::{decompress, DEFAULT_MAX_DECOMPRESSED_SIZE},
+        CompressionType,
+    },
+    traits::{BlockSource, DataSourceFetcher},
+};
+
+/// Fetches batches posted as the calldata of L1 transactions sent to `inbox`.
+///
+/// Calldata of every inbox transaction in the queried range is concatenated in block and
+/// transaction order; transactions to any other address are ignored.
+#[derive(Debug)]
+pub struct CalldataDataSourceFetcher<P, T = BoxTransport> {
+    provider: P,
+    inbox: Address,
+    compression: CompressionType,
+    _transport: PhantomData<T>,
+}
+
+impl<P, T> CalldataDataSourceFetcher<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    pub fn new(provider: P, inbox: Address) -> Self {
+        Self {
+            provider,
+            inbox,
+            compression: CompressionType::None,
+            _transport: PhantomData,
+        }
+    }
+
+    /// Compression applied to the batch before it was posted.
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+#[async_trait]
+impl<P, T> DataSourceFetcher for CalldataDataSourceFetcher<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    type Query = DataQuery;
+    type Compression = CompressionType;
+    type RawDataType = Vec<Bytes>;
+    type DecodedType = Vec<u8>;
+    type DecompressedType = Vec<u8>;
+    type Error = FetcherError;
+
+    #[instrument(skip_all, fields(from_block = query.from_block, to_block = query.to_block))]
+    async fn fetch(&self, query: &DataQuery) -> Result<Vec<Bytes>, FetcherError> {
+        let mut calldata = Vec::new();
+        for block_number in query.from_block..=query.to_block {
+            let block = self
+                .provider
+                .get_block_by_number(block_number.into(), BlockTransactionsKind::Full)
+                .await
+                .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+                .ok_or_else(|| FetcherError::Other(format!("block {} not found", block_number)))?;
+
+            for tx in block.transactions.txns() {
+                if tx.to() == Some(self.inbox) {
+                    calldata.push(tx.input().clone());
+                }
+            }
+        }
+        Ok(calldata)
+    }
+
+    #[instrument(skip_all, fields(chunks = raw.len()))]
+    async fn decode(&self, raw: Vec<Bytes>) -> Result<Vec<u8>, FetcherError> {
+        Ok(raw.concat())
+    }
+
+    #[instrument(skip_all, fields(bytes = data.len()))]
+    async fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+        decompress(&self.compression, &data, DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    fn compression_type(&self) -> CompressionType {
+        self.compression.clone()
+    }
+}
+
+#[async_trait]
+impl<P, T> BlockSource for CalldataDataSourceFetcher<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    async fn latest_block_number(&self) -> Result<u64, FetcherError> {
+        self.provider
+            .get_block_number()
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, FetcherError> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+            .ok_or_else(|| FetcherError::Other(format!("block {} not found", block_number)))?;
+        Ok(block.header.timestamp)
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, FetcherError> {
+        let block = self
+            .provider
+            .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|e| FetcherError::NetworkError(e.to_string()))?
+            .ok_or_else(|| FetcherError::Other(format!("block {} not found", block_number)))?;
+        Ok(block.header.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use alloy::{
+        consensus::{Signed, TxEnvelope, TxLegacy},
+        eips::BlockNumberOrTag,
+        primitives::{PrimitiveSignature, TxKind, B256},
+        providers::{ProviderBuilder, RootProvider},
+        rpc::types::{Block, BlockTransactions, Transaction},
+        transports::{
+            http::{Client, Http},
+            TransportResult,
+        },
+    };
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    const INBOX: Address = Address::repeat_byte(0x11);
+
+    /// Serves `blocks`, the first being block 1.
+    struct MockChain {
+        root: RootProvider<Http<Client>>,
+        blocks: Vec<Vec<Transaction>>,
+    }
+
+    #[async_trait]
+    impl Provider<Http<Client>> for MockChain {
+        fn root(&self) -> &RootProvider<Http<Client>> {
+            &self.root
+        }
+
+        async fn get_block_by_number(
+            &self,
+            number: BlockNumberOrTag,
+            _kind: BlockTransactionsKind,
+        ) -> TransportResult<Option<Block>> {
+            let index = number.as_number().and_then(|number| number.checked_sub(1));
+            let Some(transactions) = index.and_then(|index| self.blocks.get(index as usize)) else {
+                return Ok(None);
+            };
+            Ok(Some(Block {
+                transactions: BlockTransactions::Full(transactions.clone()),
+                ..Default::default()
+            }))
+        }
+    }
+
+    fn tx(to: Address, input: &[u8]) -> Transaction {
+        let tx = TxLegacy {
+            to: TxKind::Call(to),
+            input: Bytes::copy_from_slice(input),
+            ..Default::default()
+        };
+        Transaction {
+            inner: TxEnvelope::Legacy(Signed::new_unchecked(
+                tx,
+                PrimitiveSignature::test_signature(),
+                B256::ZERO,
+            )),
+            block_hash: None,
+            block_number: None,
+            transaction_index: None,
+            effective_gas_price: None,
+            from: Address::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn reassembles_batch_from_inbox_calldata() {
+        let batch =
+            b"a batch of L2 transactions, compressed and split across L1 calldata".repeat(4);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&batch).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let (first, rest) = compressed.split_at(10);
+        let (second, third) = rest.split_at(rest.len() / 2);
+
+        let other = Address::repeat_byte(0x22);
+        let chain = MockChain {
+            root: ProviderBuilder::new().on_http("http://127.0.0.1:1".parse().unwrap()),
+            blocks: vec![
+                vec![tx(INBOX, first), tx(other, b"unrelated")],
+                vec![tx(other, b"also unrelated")],
+                vec![tx(INBOX, second), tx(INBOX, third)],
+            ],
+        };
+        let fetcher =
+            CalldataDataSourceFetcher::new(chain, INBOX).with_compression(CompressionType::Gzip);
+
+        let raw = fetcher
+            .fetch(&DataQuery {
+                from_block: 1,
+                to_block: 3,
+            })
+            .await
+            .unwrap();
+        assert_eq!(raw.len(), 3);
+        let decoded = fetcher.decode(raw).await.unwrap();
+        assert_eq!(decoded, compressed);
+        assert_eq!(fetcher.decompress(decoded).await.unwrap(), batch);
+    }
+}
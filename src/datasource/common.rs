@@ -0,0 +1,75 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    common::{hasher::HashScheme, traits::ActorError},
+    datasource::CompressionType,
+};
+
+#[derive(Debug, Error)]
+pub enum FetcherError {
+    #[error("Network error: {0}")]
+    NetworkError(String),
+    #[error("Decode error: {0}")]
+    DecodeError(String),
+    #[error("Decompression error: {0}")]
+    DecompressionError(String),
+    #[error("Other error: {0}")]
+    Other(String),
+}
+
+impl ActorError for FetcherError {
+    /// Data that failed to decode or decompress fails the same way when fetched again.
+    fn is_unrecoverable(&self) -> bool {
+        matches!(
+            self,
+            FetcherError::DecodeError(_) | FetcherError::DecompressionError(_)
+        )
+    }
+}
+
+/// An inclusive range of L1 blocks to fetch DA payloads for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DataQuery {
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+/// A DA layer batches can be read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSourceKind {
+    /// Calldata of transactions sent to the inbox.
+    Calldata,
+    /// EIP-4844 blobs of transactions sent to the inbox, read from a beacon node.
+    Blob,
+}
+
+/// Where batches are read from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DataSourceConfig {
+    /// Tried in order for every block until one has data.
+    pub sources: Vec<DataSourceKind>,
+    /// Address batches are posted to; the indexed contract when unset.
+    pub inbox: Option<Address>,
+    /// Beacon node serving blob sidecars, required by the `blob` source.
+    pub beacon_url: Option<String>,
+    /// Compression applied to batches before they were posted.
+    pub compression: CompressionType,
+    /// Scheme of the manifests' data hashes; `auto` accepts whichever one matches.
+    pub hash_scheme: HashScheme,
+}
+
+impl Default for DataSourceConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec![DataSourceKind::Calldata],
+            inbox: None,
+            beacon_url: None,
+            compression: CompressionType::None,
+            hash_scheme: HashScheme::default(),
+        }
+    }
+}
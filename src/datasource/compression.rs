@@ -0,0 +1,198 @@
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::datasource::{common::FetcherError, CompressionType};
+
+/// Default cap on the inflated size of a single payload.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Inflates `data` according to `compression`, so `DataSourceFetcher::decompress`
+/// implementations can pass their `compression_type()` instead of branching themselves.
+///
+/// [`CompressionType::None`] passes `data` through untouched. With [`CompressionType::Auto`]
+/// the format is sniffed from the magic bytes instead (see [`detect_compression`]), so a source
+/// that starts compressing is still read correctly; an uncompressed payload that happens to
+/// start with a magic number is then misread, so this is opt-in.
+///
+/// Decoding streams and stops as soon as the output would exceed `max_size` bytes, so a small
+/// crafted input cannot expand into an unbounded allocation.
+pub fn decompress(
+    compression: &CompressionType,
+    data: &[u8],
+    max_size: usize,
+) -> Result<Vec<u8>, FetcherError> {
+    match compression {
+        CompressionType::None => Ok(data.to_vec()),
+        CompressionType::Auto => match detect_compression(data) {
+            CompressionType::None => Ok(data.to_vec()),
+            detected => decompress(&detected, data, max_size),
+        },
+        CompressionType::Zlib => read_bounded(ZlibDecoder::new(data), max_size),
+        CompressionType::Gzip => read_bounded(GzDecoder::new(data), max_size),
+        CompressionType::Zstd => {
+            let decoder = zstd::stream::read::Decoder::new(data)
+                .map_err(|e| FetcherError::DecompressionError(e.to_string()))?;
+            read_bounded(decoder, max_size)
+        }
+        CompressionType::Brotli => read_bounded(brotli::Decompressor::new(data, 4096), max_size),
+    }
+}
+
+/// Identifies gzip and zstd payloads by their magic bytes, returning
+/// [`CompressionType::None`] for anything else.
+pub fn detect_compression(data: &[u8]) -> CompressionType {
+    if data.starts_with(&GZIP_MAGIC) {
+        CompressionType::Gzip
+    } else if data.starts_with(&ZSTD_MAGIC) {
+        CompressionType::Zstd
+    } else {
+        CompressionType::None
+    }
+}
+
+fn read_bounded(decoder: impl Read, max_size: usize) -> Result<Vec<u8>, FetcherError> {
+    let mut out = Vec::new();
+    // Read one byte past the limit to tell "exactly at the limit" from "over it".
+    decoder
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| FetcherError::DecompressionError(e.to_string()))?;
+
+    if out.len() > max_size {
+        return Err(FetcherError::DecompressionError(format!(
+            "decompressed size exceeds limit of {} bytes",
+            max_size
+        )));
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    const PAYLOAD: &[u8] = b"rollup batch payload";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn detects_gzip_and_zstd() {
+        assert!(matches!(
+            detect_compression(&gzip(PAYLOAD)),
+            CompressionType::Gzip
+        ));
+        let zstd = zstd::encode_all(PAYLOAD, 0).unwrap();
+        assert!(matches!(detect_compression(&zstd), CompressionType::Zstd));
+    }
+
+    #[test]
+    fn short_or_unknown_input_is_uncompressed() {
+        assert!(matches!(detect_compression(&[]), CompressionType::None));
+        assert!(matches!(detect_compression(&[0x1f]), CompressionType::None));
+        assert!(matches!(
+            detect_compression(&[0x28, 0xb5, 0x2f]),
+            CompressionType::None
+        ));
+        assert!(matches!(detect_compression(PAYLOAD), CompressionType::None));
+    }
+
+    #[test]
+    fn auto_decompresses_detected_formats() {
+        let max = DEFAULT_MAX_DECOMPRESSED_SIZE;
+        assert_eq!(
+            decompress(&CompressionType::Auto, &gzip(PAYLOAD), max).unwrap(),
+            PAYLOAD
+        );
+        let zstd = zstd::encode_all(PAYLOAD, 0).unwrap();
+        assert_eq!(
+            decompress(&CompressionType::Auto, &zstd, max).unwrap(),
+            PAYLOAD
+        );
+        assert_eq!(
+            decompress(&CompressionType::Auto, PAYLOAD, max).unwrap(),
+            PAYLOAD
+        );
+    }
+
+    #[test]
+    fn none_passes_magic_bytes_through() {
+        let data = [0x1f, 0x8b, 0x00, 0x01];
+        assert_eq!(
+            decompress(&CompressionType::None, &data, DEFAULT_MAX_DECOMPRESSED_SIZE).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn rejects_output_over_limit() {
+        assert!(matches!(
+            decompress(&CompressionType::Gzip, &gzip(PAYLOAD), PAYLOAD.len() - 1),
+            Err(FetcherError::DecompressionError(_))
+        ));
+        assert_eq!(
+            decompress(&CompressionType::Gzip, &gzip(PAYLOAD), PAYLOAD.len()).unwrap(),
+            PAYLOAD
+        );
+    }
+
+    #[test]
+    fn gzip_round_trip_and_truncation() {
+        let compressed = gzip(PAYLOAD);
+        let max = DEFAULT_MAX_DECOMPRESSED_SIZE;
+        assert_eq!(
+            decompress(&CompressionType::Gzip, &compressed, max).unwrap(),
+            PAYLOAD
+        );
+
+        let truncated = &compressed[..compressed.len() / 2];
+        assert!(matches!(
+            decompress(&CompressionType::Gzip, truncated, max),
+            Err(FetcherError::DecompressionError(_))
+        ));
+    }
+
+    #[test]
+    fn zstd_round_trip_and_bomb() {
+        let max = DEFAULT_MAX_DECOMPRESSED_SIZE;
+        let compressed = zstd::encode_all(PAYLOAD, 0).unwrap();
+        assert_eq!(
+            decompress(&CompressionType::Zstd, &compressed, max).unwrap(),
+            PAYLOAD
+        );
+
+        // A megabyte of zeros compresses to a few bytes but must not inflate past the limit.
+        let bomb = zstd::encode_all(&vec![0u8; 1 << 20][..], 19).unwrap();
+        assert!(bomb.len() < 1024);
+        assert!(matches!(
+            decompress(&CompressionType::Zstd, &bomb, 64 * 1024),
+            Err(FetcherError::DecompressionError(_))
+        ));
+    }
+
+    #[test]
+    fn brotli_bomb_is_rejected() {
+        let mut bomb = Vec::new();
+        let mut encoder = brotli::CompressorWriter::new(&mut bomb, 4096, 11, 22);
+        encoder.write_all(&vec![0u8; 1 << 20]).unwrap();
+        drop(encoder);
+
+        assert!(bomb.len() < 1024);
+        assert!(matches!(
+            decompress(&CompressionType::Brotli, &bomb, 64 * 1024),
+            Err(FetcherError::DecompressionError(_))
+        ));
+    }
+}
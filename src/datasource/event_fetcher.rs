@@ -0,0 +1,144 @@
+use alloy::primitives::{Bytes, B256};
+use async_trait::async_trait;
+use tracing::instrument;
+
+use crate::{
+    datasource::{
+        common::{DataQuery, FetcherError},
+        compression::{decompress, DEFAULT_MAX_DECOMPRESSED_SIZE},
+        CompressionType,
+    },
+    event_indexer::common::IndexedEvent,
+    traits::{BlockSource, DataSourceFetcher},
+};
+
+/// Serves batches from the data of already indexed events, e.g. read back from an event
+/// store, so derivation can be replayed without an L1 node.
+///
+/// The data of every event in the queried range is concatenated in block and log order.
+#[derive(Clone, Debug)]
+pub struct EventDataSourceFetcher {
+    events: Vec<IndexedEvent>,
+    compression: CompressionType,
+}
+
+impl EventDataSourceFetcher {
+    pub fn new(events: Vec<IndexedEvent>) -> Self {
+        Self {
+            events,
+            compression: CompressionType::None,
+        }
+    }
+
+    /// Compression applied to the batch before it was posted.
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+#[async_trait]
+impl DataSourceFetcher for EventDataSourceFetcher {
+    type Query = DataQuery;
+    type Compression = CompressionType;
+    type RawDataType = Vec<Bytes>;
+    type DecodedType = Vec<u8>;
+    type DecompressedType = Vec<u8>;
+    type Error = FetcherError;
+
+    #[instrument(skip_all, fields(from_block = query.from_block, to_block = query.to_block))]
+    async fn fetch(&self, query: &DataQuery) -> Result<Vec<Bytes>, FetcherError> {
+        Ok(self
+            .events
+            .iter()
+            .filter(|event| (query.from_block..=query.to_block).contains(&event.block_number))
+            .map(|event| event.data.clone())
+            .collect())
+    }
+
+    #[instrument(skip_all, fields(chunks = raw.len()))]
+    async fn decode(&self, raw: Vec<Bytes>) -> Result<Vec<u8>, FetcherError> {
+        Ok(raw.concat())
+    }
+
+    #[instrument(skip_all, fields(bytes = data.len()))]
+    async fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+        decompress(&self.compression, &data, DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    fn compression_type(&self) -> CompressionType {
+        self.compression.clone()
+    }
+}
+
+/// Heights and timestamps come from the events themselves, so only blocks with an event are
+/// known.
+#[async_trait]
+impl BlockSource for EventDataSourceFetcher {
+    async fn latest_block_number(&self) -> Result<u64, FetcherError> {
+        Ok(self
+            .events
+            .iter()
+            .map(|event| event.block_number)
+            .max()
+            .unwrap_or_default())
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, FetcherError> {
+        self.events
+            .iter()
+            .find(|event| event.block_number == block_number)
+            .map(|event| event.block_timestamp)
+            .ok_or_else(|| FetcherError::Other(format!("no event in block {}", block_number)))
+    }
+
+    /// Stored events do not record their block's hash.
+    async fn block_hash(&self, block_number: u64) -> Result<B256, FetcherError> {
+        Err(FetcherError::Other(format!(
+            "no hash stored for block {}",
+            block_number
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn event(block_number: u64, log_index: u64, data: &[u8]) -> IndexedEvent {
+        IndexedEvent {
+            block_number,
+            block_timestamp: block_number * 12,
+            transaction_hash: Default::default(),
+            log_index,
+            address: Default::default(),
+            topics: Vec::new(),
+            data: Bytes::copy_from_slice(data),
+        }
+    }
+
+    #[tokio::test]
+    async fn decompresses_brotli_batch() {
+        let batch = b"a batch of L2 transactions compressed with brotli".repeat(8);
+        let mut compressed = Vec::new();
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        encoder.write_all(&batch).unwrap();
+        drop(encoder);
+        let (first, second) = compressed.split_at(compressed.len() / 2);
+        let fetcher = EventDataSourceFetcher::new(vec![event(10, 0, first), event(10, 1, second)])
+            .with_compression(CompressionType::Brotli);
+
+        let raw = fetcher
+            .fetch(&DataQuery {
+                from_block: 10,
+                to_block: 10,
+            })
+            .await
+            .unwrap();
+        let decoded = fetcher.decode(raw).await.unwrap();
+
+        assert_eq!(fetcher.decompress(decoded).await.unwrap(), batch);
+    }
+}
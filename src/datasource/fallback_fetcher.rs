@@ -0,0 +1,253 @@
+use std::sync::Mutex;
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use crate::{
+    datasource::{
+        common::{DataQuery, FetcherError},
+        CompressionType,
+    },
+    traits::{BlockSource, DataSourceFetcher},
+};
+
+/// A DA source erased to its final payload, so fetchers with different raw types can share one
+/// priority list.
+#[async_trait]
+trait PayloadSource: Send + Sync {
+    async fn payload(&self, query: &DataQuery) -> Result<Vec<u8>, FetcherError>;
+
+    async fn head(&self) -> Result<u64, FetcherError>;
+
+    async fn timestamp(&self, block_number: u64) -> Result<u64, FetcherError>;
+
+    async fn hash(&self, block_number: u64) -> Result<B256, FetcherError>;
+}
+
+#[async_trait]
+impl<F> PayloadSource for F
+where
+    F: BlockSource<Query = DataQuery, Error = FetcherError> + Send + Sync,
+    F::RawDataType: Send,
+    F::DecodedType: Send,
+    F::DecompressedType: Into<Vec<u8>>,
+{
+    async fn payload(&self, query: &DataQuery) -> Result<Vec<u8>, FetcherError> {
+        let raw = self.fetch(query).await?;
+        let decoded = self.decode(raw).await?;
+        Ok(self.decompress(decoded).await?.into())
+    }
+
+    async fn head(&self) -> Result<u64, FetcherError> {
+        self.latest_block_number().await
+    }
+
+    async fn timestamp(&self, block_number: u64) -> Result<u64, FetcherError> {
+        self.block_timestamp(block_number).await
+    }
+
+    async fn hash(&self, block_number: u64) -> Result<B256, FetcherError> {
+        self.block_hash(block_number).await
+    }
+}
+
+/// Fetches from an ordered list of DA sources, e.g. blobs first with calldata as fallback.
+///
+/// Each [`fetch`](DataSourceFetcher::fetch) tries the sources in the order they were added
+/// until one returns a non-empty payload. If none has data the last error is returned when any
+/// source failed, so a failed source is never mistaken for a block without a proposal; the
+/// result is empty only when every source succeeded without data. Sources run their own decode and
+/// decompress stages inside `fetch`, so [`decode`](DataSourceFetcher::decode) and
+/// [`decompress`](DataSourceFetcher::decompress) pass the payload through unchanged.
+#[derive(Default)]
+pub struct FallbackFetcher {
+    sources: Vec<(String, Box<dyn PayloadSource>)>,
+    /// Name of the source that served the last fetch with data.
+    served_by: Mutex<Option<String>>,
+}
+
+impl FallbackFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `fetcher` below every source added so far in priority.
+    pub fn with_source<F>(mut self, name: impl Into<String>, fetcher: F) -> Self
+    where
+        F: BlockSource<Query = DataQuery, Error = FetcherError> + Send + Sync + 'static,
+        F::RawDataType: Send,
+        F::DecodedType: Send,
+        F::DecompressedType: Into<Vec<u8>>,
+    {
+        self.sources.push((name.into(), Box::new(fetcher)));
+        self
+    }
+
+    /// Returns the name of the source that served the most recent fetch with data.
+    pub fn last_served_by(&self) -> Option<String> {
+        self.served_by
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn no_sources() -> FetcherError {
+        FetcherError::Other("no DA sources configured".to_string())
+    }
+}
+
+#[async_trait]
+impl DataSourceFetcher for FallbackFetcher {
+    type Query = DataQuery;
+    type Compression = CompressionType;
+    type RawDataType = Vec<u8>;
+    type DecodedType = Vec<u8>;
+    type DecompressedType = Vec<u8>;
+    type Error = FetcherError;
+
+    async fn fetch(&self, query: &DataQuery) -> Result<Vec<u8>, FetcherError> {
+        let mut last_error = None;
+
+        for (name, source) in &self.sources {
+            match source.payload(query).await {
+                Ok(payload) if payload.is_empty() => {}
+                Ok(payload) => {
+                    info!(
+                        "Blocks {}-{} served by DA source {}",
+                        query.from_block, query.to_block, name
+                    );
+                    *self.served_by.lock().unwrap_or_else(|e| e.into_inner()) = Some(name.clone());
+                    return Ok(payload);
+                }
+                Err(e) => {
+                    warn!(
+                        "DA source {} failed for blocks {}-{}: {}",
+                        name, query.from_block, query.to_block, e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None if self.sources.is_empty() => Err(Self::no_sources()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn decode(&self, raw: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+        Ok(raw)
+    }
+
+    async fn decompress(&self, data: Vec<u8>) -> Result<Vec<u8>, FetcherError> {
+        Ok(data)
+    }
+
+    fn compression_type(&self) -> CompressionType {
+        CompressionType::None
+    }
+}
+
+/// Heights, timestamps and hashes come from the first source that answers.
+#[async_trait]
+impl BlockSource for FallbackFetcher {
+    async fn latest_block_number(&self) -> Result<u64, FetcherError> {
+        let mut last_error = Self::no_sources();
+        for (_, source) in &self.sources {
+            match source.head().await {
+                Ok(block_number) => return Ok(block_number),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, FetcherError> {
+        let mut last_error = Self::no_sources();
+        for (_, source) in &self.sources {
+            match source.timestamp(block_number).await {
+                Ok(timestamp) => return Ok(timestamp),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, FetcherError> {
+        let mut last_error = Self::no_sources();
+        for (_, source) in &self.sources {
+            match source.hash(block_number).await {
+                Ok(hash) => return Ok(hash),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::mock_fetcher::MockDataSourceFetcher;
+
+    fn block(block_number: u64) -> DataQuery {
+        DataQuery {
+            from_block: block_number,
+            to_block: block_number,
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_when_primary_fails() {
+        let fetcher = FallbackFetcher::new()
+            .with_source(
+                "blob",
+                MockDataSourceFetcher::new()
+                    .with_error(block(1), FetcherError::NetworkError("timeout".to_string())),
+            )
+            .with_source(
+                "calldata",
+                MockDataSourceFetcher::new().with_response(block(1), b"batch".to_vec()),
+            );
+
+        assert_eq!(fetcher.fetch(&block(1)).await.unwrap(), b"batch");
+        assert_eq!(fetcher.last_served_by().as_deref(), Some("calldata"));
+    }
+
+    #[tokio::test]
+    async fn reports_failure_when_other_source_is_empty() {
+        let fetcher = FallbackFetcher::new()
+            .with_source(
+                "blob",
+                MockDataSourceFetcher::new()
+                    .with_error(block(1), FetcherError::NetworkError("timeout".to_string())),
+            )
+            .with_source(
+                "calldata",
+                MockDataSourceFetcher::new().with_response(block(1), Vec::new()),
+            );
+
+        assert!(matches!(
+            fetcher.fetch(&block(1)).await,
+            Err(FetcherError::NetworkError(_))
+        ));
+        assert_eq!(fetcher.last_served_by(), None);
+    }
+
+    #[tokio::test]
+    async fn empty_only_when_every_source_is_empty() {
+        let fetcher = FallbackFetcher::new()
+            .with_source(
+                "blob",
+                MockDataSourceFetcher::new().with_response(block(1), Vec::new()),
+            )
+            .with_source(
+                "calldata",
+                MockDataSourceFetcher::new().with_response(block(1), Vec::new()),
+            );
+
+        assert!(fetcher.fetch(&block(1)).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Instant;
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use prometheus::{Histogram, HistogramOpts, IntCounter, Registry};
+
+use crate::traits::{BlockSource, DataSourceFetcher};
+
+/// Prometheus metrics recorded by an [`InstrumentedFetcher`].
+#[derive(Clone, Debug)]
+pub struct FetcherMetrics {
+    fetch_duration: Histogram,
+    decode_duration: Histogram,
+    decompress_duration: Histogram,
+    fetched_bytes: IntCounter,
+    decompressed_bytes: IntCounter,
+}
+
+impl FetcherMetrics {
+    /// Creates the fetcher metrics and registers them with `registry`.
+    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let metrics = Self {
+            fetch_duration: Histogram::with_opts(HistogramOpts::new(
+                "fetcher_fetch_duration_seconds",
+                "Duration of DA fetches",
+            ))?,
+            decode_duration: Histogram::with_opts(HistogramOpts::new(
+                "fetcher_decode_duration_seconds",
+                "Duration of decoding fetched DA data",
+            ))?,
+            decompress_duration: Histogram::with_opts(HistogramOpts::new(
+                "fetcher_decompress_duration_seconds",
+                "Duration of decompressing decoded DA data",
+            ))?,
+            fetched_bytes: IntCounter::new(
+                "fetcher_fetched_bytes_total",
+                "Bytes of (compressed) payload fetched from the DA layer",
+            )?,
+            decompressed_bytes: IntCounter::new(
+                "fetcher_decompressed_bytes_total",
+                "Bytes of payload after decompression",
+            )?,
+        };
+
+        registry.register(Box::new(metrics.fetch_duration.clone()))?;
+        registry.register(Box::new(metrics.decode_duration.clone()))?;
+        registry.register(Box::new(metrics.decompress_duration.clone()))?;
+        registry.register(Box::new(metrics.fetched_bytes.clone()))?;
+        registry.register(Box::new(metrics.decompressed_bytes.clone()))?;
+        Ok(metrics)
+    }
+}
+
+/// Times each stage of another fetcher and counts the bytes passing through it.
+///
+/// Fetched bytes are counted on the decoded payload, since raw responses (blobs, calldata) come
+/// in fetcher-specific shapes; dividing decompressed by fetched bytes gives the compression
+/// ratio. Durations are recorded for failed calls too.
+#[derive(Clone, Debug)]
+pub struct InstrumentedFetcher<F> {
+    inner: F,
+    metrics: FetcherMetrics,
+}
+
+impl<F> InstrumentedFetcher<F> {
+    pub fn new(inner: F, metrics: FetcherMetrics) -> Self {
+        Self { inner, metrics }
+    }
+
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<F> DataSourceFetcher for InstrumentedFetcher<F>
+where
+    F: DataSourceFetcher + Send + Sync,
+    F::Query: Sync,
+    F::RawDataType: Send,
+    F::DecodedType: AsRef<[u8]> + Send,
+    F::DecompressedType: AsRef<[u8]>,
+{
+    type Query = F::Query;
+    type Compression = F::Compression;
+    type RawDataType = F::RawDataType;
+    type DecodedType = F::DecodedType;
+    type DecompressedType = F::DecompressedType;
+    type Error = F::Error;
+
+    async fn fetch(&self, query: &F::Query) -> Result<F::RawDataType, F::Error> {
+        let start = Instant::now();
+        let result = self.inner.fetch(query).await;
+        observe(&self.metrics.fetch_duration, start);
+        result
+    }
+
+    async fn decode(&self, raw: F::RawDataType) -> Result<F::DecodedType, F::Error> {
+        let start = Instant::now();
+        let result = self.inner.decode(raw).await;
+        observe(&self.metrics.decode_duration, start);
+        if let Ok(decoded) = &result {
+            self.metrics
+                .fetched_bytes
+                .inc_by(decoded.as_ref().len() as u64);
+        }
+        result
+    }
+
+    async fn decompress(&self, data: F::DecodedType) -> Result<F::DecompressedType, F::Error> {
+        let start = Instant::now();
+        let result = self.inner.decompress(data).await;
+        observe(&self.metrics.decompress_duration, start);
+        if let Ok(decompressed) = &result {
+            self.metrics
+                .decompressed_bytes
+                .inc_by(decompressed.as_ref().len() as u64);
+        }
+        result
+    }
+
+    fn compression_type(&self) -> F::Compression {
+        self.inner.compression_type()
+    }
+}
+
+/// Height and timestamp lookups are passed through untimed.
+#[async_trait]
+impl<F> BlockSource for InstrumentedFetcher<F>
+where
+    F: BlockSource + Send + Sync,
+    F::Query: Sync,
+    F::RawDataType: Send,
+    F::DecodedType: AsRef<[u8]> + Send,
+    F::DecompressedType: AsRef<[u8]>,
+{
+    async fn latest_block_number(&self) -> Result<u64, F::Error> {
+        self.inner.latest_block_number().await
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, F::Error> {
+        self.inner.block_timestamp(block_number).await
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, F::Error> {
+        self.inner.block_hash(block_number).await
+    }
+}
+
+fn observe(histogram: &Histogram, start: Instant) {
+    histogram.observe(start.elapsed().as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+    use crate::datasource::{
+        common::DataQuery, mock_fetcher::MockDataSourceFetcher, CompressionType,
+    };
+
+    #[tokio::test]
+    async fn records_each_stage() {
+        let payload = vec![0xab; 4096];
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&payload).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let query = DataQuery {
+            from_block: 1,
+            to_block: 1,
+        };
+        let registry = Registry::new();
+        let metrics = FetcherMetrics::register(&registry).unwrap();
+        let fetcher = InstrumentedFetcher::new(
+            MockDataSourceFetcher::new().with_compressed_response(
+                query.clone(),
+                compressed.clone(),
+                CompressionType::Gzip,
+            ),
+            metrics.clone(),
+        );
+
+        let raw = fetcher.fetch(&query).await.unwrap();
+        let decoded = fetcher.decode(raw).await.unwrap();
+        assert_eq!(fetcher.decompress(decoded).await.unwrap(), payload);
+
+        for histogram in [
+            &metrics.fetch_duration,
+            &metrics.decode_duration,
+            &metrics.decompress_duration,
+        ] {
+            assert_eq!(histogram.get_sample_count(), 1);
+        }
+        assert_eq!(metrics.fetched_bytes.get(), compressed.len() as u64);
+        assert_eq!(metrics.decompressed_bytes.get(), 4096);
+        assert_eq!(registry.gather().len(), 5);
+    }
+}
@@ -0,0 +1,246 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use alloy::primitives::B256;
+use async_trait::async_trait;
+use tokio::time::sleep;
+
+use crate::{
+    datasource::{
+        common::{DataQuery, FetcherError},
+        compression::{self, DEFAULT_MAX_DECOMPRESSED_SIZE},
+        CompressionType,
+    },
+    traits::{BlockSource, DataSourceFetcher},
+};
+
+/// A scripted payload as stored on the mock DA layer, with the compression it was stored in.
+#[derive(Clone, Debug)]
+pub struct MockPayload {
+    pub data: Vec<u8>,
+    pub compression: CompressionType,
+}
+
+impl AsRef<[u8]> for MockPayload {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Serves registered payloads and errors per query from memory, for exercising the watcher,
+/// pipeline and driver without a network.
+///
+/// A query with nothing registered fails with [`FetcherError::Other`]. Every fetched query is
+/// recorded in order, see [`MockDataSourceFetcher::requested_queries`]. The head is the highest
+/// registered block unless [set](MockDataSourceFetcher::with_head), and each block's timestamp
+/// is its number unless [registered](MockDataSourceFetcher::with_block_timestamp), as is its
+/// hash unless [registered](MockDataSourceFetcher::with_block_hash).
+#[derive(Debug, Default)]
+pub struct MockDataSourceFetcher {
+    responses: HashMap<DataQuery, Result<MockPayload, FetcherError>>,
+    timestamps: HashMap<u64, u64>,
+    hashes: HashMap<u64, B256>,
+    head: Option<u64>,
+    latency: Duration,
+    requested: Mutex<Vec<DataQuery>>,
+}
+
+impl MockDataSourceFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `data`, uncompressed, for `query`.
+    pub fn with_response(self, query: DataQuery, data: impl Into<Vec<u8>>) -> Self {
+        self.with_compressed_response(query, data, CompressionType::None)
+    }
+
+    /// Serves `data` for `query`, decompressing it with `compression`.
+    pub fn with_compressed_response(
+        mut self,
+        query: DataQuery,
+        data: impl Into<Vec<u8>>,
+        compression: CompressionType,
+    ) -> Self {
+        let payload = MockPayload {
+            data: data.into(),
+            compression,
+        };
+        self.responses.insert(query, Ok(payload));
+        self
+    }
+
+    /// Fails every fetch of `query` with `error`.
+    pub fn with_error(mut self, query: DataQuery, error: FetcherError) -> Self {
+        self.responses.insert(query, Err(error));
+        self
+    }
+
+    pub fn with_block_timestamp(mut self, block_number: u64, timestamp: u64) -> Self {
+        self.timestamps.insert(block_number, timestamp);
+        self
+    }
+
+    pub fn with_block_hash(mut self, block_number: u64, hash: B256) -> Self {
+        self.hashes.insert(block_number, hash);
+        self
+    }
+
+    pub fn with_head(mut self, head: u64) -> Self {
+        self.head = Some(head);
+        self
+    }
+
+    /// Delays every fetch by `latency`, e.g. to stall a stage past its timeout.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Returns every query fetched so far, in order.
+    pub fn requested_queries(&self) -> Vec<DataQuery> {
+        self.requested
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn was_requested(&self, query: &DataQuery) -> bool {
+        self.requested
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(query)
+    }
+}
+
+#[async_trait]
+impl DataSourceFetcher for MockDataSourceFetcher {
+    type Query = DataQuery;
+    type Compression = CompressionType;
+    type RawDataType = MockPayload;
+    type DecodedType = MockPayload;
+    type DecompressedType = Vec<u8>;
+    type Error = FetcherError;
+
+    async fn fetch(&self, query: &DataQuery) -> Result<MockPayload, FetcherError> {
+        self.requested
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(query.clone());
+        if !self.latency.is_zero() {
+            sleep(self.latency).await;
+        }
+        match self.responses.get(query) {
+            Some(Ok(payload)) => Ok(payload.clone()),
+            Some(Err(e)) => Err(clone_error(e)),
+            None => Err(FetcherError::Other(format!(
+                "no response registered for blocks {}-{}",
+                query.from_block, query.to_block
+            ))),
+        }
+    }
+
+    async fn decode(&self, raw: MockPayload) -> Result<MockPayload, FetcherError> {
+        Ok(raw)
+    }
+
+    async fn decompress(&self, data: MockPayload) -> Result<Vec<u8>, FetcherError> {
+        compression::decompress(&data.compression, &data.data, DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Compression is set per response; see [`MockPayload::compression`].
+    fn compression_type(&self) -> CompressionType {
+        CompressionType::None
+    }
+}
+
+#[async_trait]
+impl BlockSource for MockDataSourceFetcher {
+    async fn latest_block_number(&self) -> Result<u64, FetcherError> {
+        Ok(self.head.unwrap_or_else(|| {
+            self.responses
+                .keys()
+                .map(|query| query.to_block)
+                .max()
+                .unwrap_or_default()
+        }))
+    }
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, FetcherError> {
+        Ok(self
+            .timestamps
+            .get(&block_number)
+            .copied()
+            .unwrap_or(block_number))
+    }
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, FetcherError> {
+        Ok(self
+            .hashes
+            .get(&block_number)
+            .copied()
+            .unwrap_or_else(|| B256::left_padding_from(&block_number.to_be_bytes())))
+    }
+}
+
+/// Rebuilds a registered error, which is served on every fetch of its query.
+fn clone_error(error: &FetcherError) -> FetcherError {
+    match error {
+        FetcherError::NetworkError(e) => FetcherError::NetworkError(e.clone()),
+        FetcherError::DecodeError(e) => FetcherError::DecodeError(e.clone()),
+        FetcherError::DecompressionError(e) => FetcherError::DecompressionError(e.clone()),
+        FetcherError::Other(e) => FetcherError::Other(e.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_number: u64) -> DataQuery {
+        DataQuery {
+            from_block: block_number,
+            to_block: block_number,
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_registered_responses_and_records_queries() {
+        let compressed = zstd::encode_all(&b"batch"[..], 0).unwrap();
+        let fetcher = MockDataSourceFetcher::new()
+            .with_compressed_response(block(1), compressed, CompressionType::Zstd)
+            .with_error(block(2), FetcherError::NetworkError("down".to_string()));
+
+        let raw = fetcher.fetch(&block(1)).await.unwrap();
+        assert_eq!(fetcher.decompress(raw).await.unwrap(), b"batch");
+        assert!(matches!(
+            fetcher.fetch(&block(2)).await,
+            Err(FetcherError::NetworkError(_))
+        ));
+        assert!(matches!(
+            fetcher.fetch(&block(3)).await,
+            Err(FetcherError::Other(_))
+        ));
+
+        assert_eq!(
+            fetcher.requested_queries(),
+            vec![block(1), block(2), block(3)]
+        );
+        assert!(fetcher.was_requested(&block(2)));
+        assert!(!fetcher.was_requested(&block(4)));
+    }
+
+    #[tokio::test]
+    async fn head_and_timestamps_default_to_registered_blocks() {
+        let fetcher = MockDataSourceFetcher::new()
+            .with_response(block(9), b"batch".to_vec())
+            .with_block_timestamp(9, 1_700_000_000);
+
+        assert_eq!(fetcher.latest_block_number().await.unwrap(), 9);
+        assert_eq!(fetcher.block_timestamp(9).await.unwrap(), 1_700_000_000);
+        assert_eq!(fetcher.block_timestamp(4).await.unwrap(), 4);
+        assert_eq!(
+            fetcher.with_head(20).latest_block_number().await.unwrap(),
+            20
+        );
+    }
+}
@@ -1,7 +1,26 @@
 pub mod blob_fetcher;
+pub mod caching_fetcher;
+pub mod calldata_fetcher;
+pub mod common;
+pub mod compression;
+pub mod event_fetcher;
+pub mod fallback_fetcher;
+#[cfg(feature = "metrics")]
+pub mod instrumented_fetcher;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock_fetcher;
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum CompressionType {
+    #[default]
     None,
+    /// Detected per payload from its magic bytes, see [`compression::detect_compression`].
+    Auto,
     Zlib,
+    Gzip,
+    Zstd,
+    Brotli,
 }
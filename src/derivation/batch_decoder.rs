@@ -0,0 +1,28 @@
+use alloy::{primitives::Bytes, rlp};
+
+use crate::derivation::common::DerivationError;
+
+/// Splits a decompressed batch into its transactions.
+pub trait BatchDecoder: Send + Sync {
+    fn decode_batch(&self, data: &[u8]) -> Result<Vec<Bytes>, DerivationError>;
+}
+
+/// Decodes a batch encoded as an RLP list of opaque transaction byte strings.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RlpBatchDecoder;
+
+impl BatchDecoder for RlpBatchDecoder {
+    fn decode_batch(&self, data: &[u8]) -> Result<Vec<Bytes>, DerivationError> {
+        let transactions: Vec<Bytes> =
+            rlp::decode_exact(data).map_err(|e| DerivationError::MalformedBatch(e.to_string()))?;
+
+        if let Some(index) = transactions.iter().position(|tx| tx.is_empty()) {
+            return Err(DerivationError::MalformedBatch(format!(
+                "empty transaction at index {}",
+                index
+            )));
+        }
+
+        Ok(transactions)
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+use alloy::{
+    eips::eip1559::BaseFeeParams,
+    primitives::{Address, Bytes, B256},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::common::traits::ActorError;
+
+/// Gas limit of derived blocks unless configured.
+pub const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+
+/// Smallest gas limit a block may have under EIP-1559.
+pub const MIN_GAS_LIMIT: u64 = 5_000;
+
+/// Largest gas limit execution clients accept (`2^63 - 1`).
+pub const MAX_GAS_LIMIT: u64 = i64::MAX as u64;
+
+/// Rollup parameters every derived block is built with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PayloadConfig {
+    pub fee_recipient: Address,
+    pub gas_limit: u64,
+    /// EIP-1559 parameters; both must be non-zero and fit in 32 bits.
+    pub base_fee_params: BaseFeeParams,
+}
+
+impl PayloadConfig {
+    pub fn validate(&self) -> Result<(), DerivationError> {
+        if !(MIN_GAS_LIMIT..=MAX_GAS_LIMIT).contains(&self.gas_limit) {
+            return Err(DerivationError::InvalidConfig(format!(
+                "gas limit {} is outside {}-{}",
+                self.gas_limit, MIN_GAS_LIMIT, MAX_GAS_LIMIT
+            )));
+        }
+
+        let BaseFeeParams {
+            max_change_denominator,
+            elasticity_multiplier,
+        } = self.base_fee_params;
+        let in_range = |value: u128| value != 0 && value <= u32::MAX as u128;
+        if !in_range(max_change_denominator) || !in_range(elasticity_multiplier) {
+            return Err(DerivationError::InvalidConfig(format!(
+                "base fee params {}/{} must be non-zero 32-bit values",
+                max_change_denominator, elasticity_multiplier
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for PayloadConfig {
+    fn default() -> Self {
+        Self {
+            fee_recipient: Address::ZERO,
+            gas_limit: DEFAULT_GAS_LIMIT,
+            base_fee_params: BaseFeeParams::ethereum(),
+        }
+    }
+}
+
+/// Known state derivation starts from: the first derived L2 block builds on `l2_genesis_hash`,
+/// from proposals included after `l1_origin_block`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnchorConfig {
+    pub l2_genesis_hash: B256,
+    pub l1_origin_block: u64,
+    pub l1_origin_hash: B256,
+}
+
+/// Attributes the execution engine builds an L2 block from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockPayloadAttributes {
+    /// L1 block the proposal was included in.
+    pub l1_block_number: u64,
+    pub timestamp: u64,
+    pub prev_randao: B256,
+    pub suggested_fee_recipient: Address,
+    pub gas_limit: u64,
+    pub base_fee_params: BaseFeeParams,
+    /// Encoded transactions, in execution order.
+    pub transactions: Vec<Bytes>,
+    /// Whether the block was derived empty on purpose, rather than its batch having no
+    /// transactions; see `empty_epoch_reason`.
+    #[serde(default)]
+    pub is_empty_epoch: bool,
+    #[serde(default)]
+    pub empty_epoch_reason: Option<EmptyEpochReason>,
+}
+
+/// Why a block was derived without its batch's transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum EmptyEpochReason {
+    /// The batch was included `delay_secs` after it was proposed, past the sequencing window.
+    SequencingWindowExpired { window_secs: u64, delay_secs: u64 },
+}
+
+impl fmt::Display for EmptyEpochReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmptyEpochReason::SequencingWindowExpired {
+                window_secs,
+                delay_secs,
+            } => write!(
+                f,
+                "included {}s after it was proposed, past the {}s sequencing window",
+                delay_secs, window_secs
+            ),
+        }
+    }
+}
+
+/// Every intermediate a proposal passes through on its way to payload attributes, for
+/// debugging a single proposal, see [`DefaultDerivationPipeline::trace`].
+///
+/// [`DefaultDerivationPipeline::trace`]: crate::derivation::derivation::DefaultDerivationPipeline::trace
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivationTrace {
+    /// Batch data as fetched from the DA layer.
+    pub raw: Bytes,
+    /// Batch data after the source's decoding, still compressed.
+    pub decoded: Bytes,
+    /// The canonical payload the manifest's data hash commits to.
+    pub decompressed: Bytes,
+    pub transactions: Vec<Bytes>,
+    pub attributes: BlockPayloadAttributes,
+}
+
+#[derive(Debug, Error)]
+pub enum DerivationError {
+    #[error("Fetch error: {0}")]
+    FetchError(String),
+    /// Fetching the proposal's block or payload took longer than the fetch timeout.
+    #[error("Fetch timeout: {0}")]
+    FetchTimeout(String),
+    #[error("Data hash mismatch: expected {expected}, got {actual}")]
+    HashMismatch { expected: B256, actual: B256 },
+    #[error("Malformed batch: {0}")]
+    MalformedBatch(String),
+    #[error("Out-of-order proposal: block {received} is not after block {previous}")]
+    OutOfOrder { previous: u64, received: u64 },
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Proposal does not build on the anchor: {0}")]
+    AnchorMismatch(String),
+    /// The proposal is older than the pipeline's maximum proposal age.
+    #[error("Stale proposal: {0}")]
+    StaleProposal(String),
+}
+
+impl ActorError for DerivationError {
+    /// Only a failed or stalled fetch can succeed on retry; anything wrong with the proposal or
+    /// its batch fails the same way on every node and every attempt.
+    fn is_unrecoverable(&self) -> bool {
+        !matches!(
+            self,
+            DerivationError::FetchError(_) | DerivationError::FetchTimeout(_)
+        )
+    }
+
+    fn is_timeout(&self) -> bool {
+        matches!(self, DerivationError::FetchTimeout(_))
+    }
+}
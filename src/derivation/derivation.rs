@@ -0,0 +1,773 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use alloy::primitives::Bytes;
+use async_trait::async_trait;
+use tokio::time::timeout;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    common::hasher::{HashScheme, Hasher, Keccak256Hasher},
+    da_watcher::{common::ProposalManifest, da_watcher::current_unix_secs},
+    datasource::common::DataQuery,
+    derivation::{
+        batch_decoder::{BatchDecoder, RlpBatchDecoder},
+        common::{
+            AnchorConfig, BlockPayloadAttributes, DerivationError, DerivationTrace,
+            EmptyEpochReason, PayloadConfig,
+        },
+        metrics::DerivationMetrics,
+    },
+    traits::{BlockSource, DataSourceFetcher, DerivationPipeline},
+};
+
+/// Derives payload attributes by fetching each proposal's batch from the DA layer, checking it
+/// against the manifest's data hash and splitting it into transactions. Every block gets the
+/// fee recipient, gas limit and base fee parameters of its [`PayloadConfig`], and the timestamp
+/// of the L1 block that included its batch.
+///
+/// Proposals must arrive in increasing L1 block order. With an [anchor](Self::with_anchor), the
+/// first one must also build on it. With a [maximum age](Self::with_max_proposal_age), proposals
+/// older than it are discarded without being fetched.
+pub struct DefaultDerivationPipeline<F, B = RlpBatchDecoder> {
+    fetcher: F,
+    batch_decoder: B,
+    payload_config: PayloadConfig,
+    hasher: Arc<dyn Hasher>,
+    /// Scheme the last data hash matched under, with hash auto-detection on.
+    detected_scheme: Option<Mutex<Option<HashScheme>>>,
+    anchor: Option<AnchorConfig>,
+    fetch_timeout: Option<Duration>,
+    max_proposal_age: Option<Duration>,
+    sequencing_window: Option<Duration>,
+    /// Proposals discarded for their age so far.
+    stale: AtomicU64,
+    metrics: Option<DerivationMetrics>,
+    last_block: Mutex<Option<u64>>,
+}
+
+impl<F> DefaultDerivationPipeline<F> {
+    /// Fails if `payload_config` is out of bounds, see [`PayloadConfig::validate`].
+    pub fn new(fetcher: F, payload_config: PayloadConfig) -> Result<Self, DerivationError> {
+        Self::with_batch_decoder(fetcher, RlpBatchDecoder, payload_config)
+    }
+}
+
+impl<F, B> DefaultDerivationPipeline<F, B> {
+    pub fn with_batch_decoder(
+        fetcher: F,
+        batch_decoder: B,
+        payload_config: PayloadConfig,
+    ) -> Result<Self, DerivationError> {
+        payload_config.validate()?;
+        Ok(Self {
+            fetcher,
+            batch_decoder,
+            payload_config,
+            hasher: Arc::new(Keccak256Hasher),
+            detected_scheme: None,
+            anchor: None,
+            fetch_timeout: None,
+            max_proposal_age: None,
+            sequencing_window: None,
+            stale: AtomicU64::new(0),
+            metrics: None,
+            last_block: Mutex::new(None),
+        })
+    }
+
+    /// Commitment scheme the manifests' data hashes were computed with; `keccak256` by default.
+    pub fn with_hasher(mut self, hasher: Arc<dyn Hasher>) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Accepts a data hash under whichever [detectable](HashScheme::DETECTABLE) scheme it
+    /// matches instead of only under the [hasher](Self::with_hasher), for DA sources that do
+    /// not agree on one. The matching scheme is recorded, see [`Self::detected_hash_scheme`],
+    /// and a warning is logged whenever it changes. Off by default.
+    pub fn with_hash_auto_detection(mut self) -> Self {
+        self.detected_scheme = Some(Mutex::new(None));
+        self
+    }
+
+    /// Returns the scheme the last verified data hash matched under, with hash auto-detection
+    /// on.
+    pub fn detected_hash_scheme(&self) -> Option<HashScheme> {
+        let detected = self.detected_scheme.as_ref()?;
+        *detected.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Validates the first derived proposal against `anchor`; see [`Self::check_anchor`].
+    pub fn with_anchor(mut self, anchor: AnchorConfig) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Fails a proposal with [`DerivationError::FetchTimeout`] when fetching its L1 block and
+    /// payload takes longer than `fetch_timeout`. Fetches may take as long as they take by
+    /// default.
+    pub fn with_fetch_timeout(mut self, fetch_timeout: Duration) -> Self {
+        self.fetch_timeout = Some(fetch_timeout);
+        self
+    }
+
+    /// Fails proposals whose L1 timestamp is more than `max_proposal_age` behind the system
+    /// clock with [`DerivationError::StaleProposal`], so a driver recovering from a long outage
+    /// does not grind through a backlog that was finalized elsewhere. Every proposal is derived
+    /// by default.
+    pub fn with_max_proposal_age(mut self, max_proposal_age: Duration) -> Self {
+        self.max_proposal_age = Some(max_proposal_age);
+        self
+    }
+
+    /// Derives a proposal whose batch was included more than `sequencing_window` after the
+    /// proposal's own timestamp as an empty block, flagged with
+    /// [`EmptyEpochReason::SequencingWindowExpired`], instead of executing a batch that came too
+    /// late. The window never expires by default.
+    pub fn with_sequencing_window(mut self, sequencing_window: Duration) -> Self {
+        self.sequencing_window = Some(sequencing_window);
+        self
+    }
+
+    /// Why `proposal`, included at `timestamp`, must derive to an empty block, if it must.
+    fn empty_epoch_reason(
+        &self,
+        proposal: &ProposalManifest,
+        timestamp: u64,
+    ) -> Option<EmptyEpochReason> {
+        let window_secs = self.sequencing_window?.as_secs();
+        let delay_secs = timestamp.saturating_sub(proposal.timestamp);
+        (delay_secs > window_secs).then_some(EmptyEpochReason::SequencingWindowExpired {
+            window_secs,
+            delay_secs,
+        })
+    }
+
+    /// Counts discarded stale proposals in `metrics`.
+    pub fn with_metrics(mut self, metrics: DerivationMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Returns how many proposals [`DerivationPipeline::derive`] discarded for their age.
+    pub fn stale_proposals(&self) -> u64 {
+        self.stale.load(Ordering::Relaxed)
+    }
+
+    /// Rejects `proposal` if it is older than the maximum proposal age.
+    fn check_age(&self, proposal: &ProposalManifest) -> Result<(), DerivationError> {
+        let Some(max_age) = self.max_proposal_age else {
+            return Ok(());
+        };
+        let age = current_unix_secs().saturating_sub(proposal.timestamp);
+        if age <= max_age.as_secs() {
+            return Ok(());
+        }
+        Err(DerivationError::StaleProposal(format!(
+            "block {} is {}s old, more than {:?}",
+            proposal.block_number, age, max_age
+        )))
+    }
+
+    fn last_block(&self) -> Option<u64> {
+        *self.last_block.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_last_block(&self, block_number: u64) {
+        *self.last_block.lock().unwrap_or_else(|e| e.into_inner()) = Some(block_number);
+    }
+}
+
+impl<F, B> DefaultDerivationPipeline<F, B>
+where
+    F: BlockSource<Query = DataQuery> + Send + Sync,
+    F::DecompressedType: AsRef<[u8]>,
+    B: BatchDecoder,
+{
+    /// Derives `proposal` as if `previous` was the last derived block, without recording it.
+    #[instrument(name = "derive", skip_all, fields(block_number = proposal.block_number))]
+    async fn derive_after(
+        &self,
+        proposal: ProposalManifest,
+        previous: Option<u64>,
+    ) -> Result<BlockPayloadAttributes, DerivationError> {
+        self.check_age(&proposal)?;
+        match previous {
+            Some(previous) if proposal.block_number <= previous => {
+                return Err(DerivationError::OutOfOrder {
+                    previous,
+                    received: proposal.block_number,
+                });
+            }
+            Some(_) => {}
+            None => self.check_anchor(&proposal).await?,
+        }
+
+        let fetched = async {
+            // The manifest's own timestamp is not part of the commitment, so take the L1 block's.
+            let timestamp = self
+                .fetcher
+                .block_timestamp(proposal.block_number)
+                .await
+                .map_err(|e| DerivationError::FetchError(e.to_string()))?;
+            let query = DataQuery {
+                from_block: proposal.block_number,
+                to_block: proposal.block_number,
+            };
+            let payload = fetch_payload(&self.fetcher, &query)
+                .await
+                .map_err(DerivationError::FetchError)?;
+            Ok((timestamp, payload))
+        };
+        let (timestamp, payload) = match self.fetch_timeout {
+            None => fetched.await?,
+            Some(limit) => timeout(limit, fetched).await.unwrap_or_else(|_| {
+                warn!(
+                    "Fetching block {} timed out after {:?}",
+                    proposal.block_number, limit
+                );
+                Err(DerivationError::FetchTimeout(format!(
+                    "block {} not fetched within {:?}",
+                    proposal.block_number, limit
+                )))
+            })?,
+        };
+        self.attributes(&proposal, timestamp, payload.as_ref())
+    }
+
+    /// Rejects a first proposal that does not continue from the anchor: it must be included
+    /// after the anchor's L1 origin, on a chain whose block at the origin's height is still the
+    /// origin.
+    async fn check_anchor(&self, proposal: &ProposalManifest) -> Result<(), DerivationError> {
+        let Some(anchor) = &self.anchor else {
+            return Ok(());
+        };
+        if proposal.block_number <= anchor.l1_origin_block {
+            return Err(DerivationError::AnchorMismatch(format!(
+                "block {} is not after L1 origin block {}",
+                proposal.block_number, anchor.l1_origin_block
+            )));
+        }
+
+        let origin_hash = self
+            .fetcher
+            .block_hash(anchor.l1_origin_block)
+            .await
+            .map_err(|e| DerivationError::FetchError(e.to_string()))?;
+        if origin_hash != anchor.l1_origin_hash {
+            return Err(DerivationError::AnchorMismatch(format!(
+                "L1 block {} is {}, expected {}",
+                anchor.l1_origin_block, origin_hash, anchor.l1_origin_hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks `payload` against `proposal`'s data hash and builds the block it derives to.
+    fn attributes(
+        &self,
+        proposal: &ProposalManifest,
+        timestamp: u64,
+        payload: &[u8],
+    ) -> Result<BlockPayloadAttributes, DerivationError> {
+        let verified = match &self.detected_scheme {
+            None => proposal.verify(self.hasher.as_ref(), payload),
+            Some(detected) => detect_scheme(detected, proposal, payload),
+        };
+        if !verified {
+            return Err(DerivationError::HashMismatch {
+                expected: proposal.data_hash,
+                actual: self.hasher.hash(payload),
+            });
+        }
+
+        let empty_epoch_reason = self.empty_epoch_reason(proposal, timestamp);
+        let transactions = match empty_epoch_reason {
+            Some(reason) => {
+                info!("Deriving block {} empty: {}", proposal.block_number, reason);
+                Vec::new()
+            }
+            None => self.batch_decoder.decode_batch(payload)?,
+        };
+
+        Ok(BlockPayloadAttributes {
+            l1_block_number: proposal.block_number,
+            timestamp,
+            // Every node derives the same value from the batch commitment.
+            prev_randao: proposal.data_hash,
+            suggested_fee_recipient: self.payload_config.fee_recipient,
+            gas_limit: self.payload_config.gas_limit,
+            base_fee_params: self.payload_config.base_fee_params,
+            transactions,
+            is_empty_epoch: empty_epoch_reason.is_some(),
+            empty_epoch_reason,
+        })
+    }
+}
+
+impl<F, B> DefaultDerivationPipeline<F, B>
+where
+    F: BlockSource<Query = DataQuery> + Send + Sync,
+    F::RawDataType: AsRef<[u8]> + Clone,
+    F::DecodedType: AsRef<[u8]> + Clone,
+    F::DecompressedType: AsRef<[u8]>,
+    B: BatchDecoder,
+{
+    /// Derives `proposal` keeping every intermediate stage. Neither checks nor records the
+    /// proposal's order, so it can look at any proposal without affecting later derives.
+    pub async fn trace(
+        &self,
+        proposal: ProposalManifest,
+    ) -> Result<DerivationTrace, DerivationError> {
+        let fetch_error = |e: F::Error| DerivationError::FetchError(e.to_string());
+        let timestamp = self
+            .fetcher
+            .block_timestamp(proposal.block_number)
+            .await
+            .map_err(fetch_error)?;
+        let query = DataQuery {
+            from_block: proposal.block_number,
+            to_block: proposal.block_number,
+        };
+        let raw = self.fetcher.fetch(&query).await.map_err(fetch_error)?;
+        let decoded = self
+            .fetcher
+            .decode(raw.clone())
+            .await
+            .map_err(fetch_error)?;
+        let decompressed = self
+            .fetcher
+            .decompress(decoded.clone())
+            .await
+            .map_err(fetch_error)?;
+
+        let attributes = self.attributes(&proposal, timestamp, decompressed.as_ref())?;
+        Ok(DerivationTrace {
+            raw: Bytes::copy_from_slice(raw.as_ref()),
+            decoded: Bytes::copy_from_slice(decoded.as_ref()),
+            decompressed: Bytes::copy_from_slice(decompressed.as_ref()),
+            transactions: attributes.transactions.clone(),
+            attributes,
+        })
+    }
+}
+
+#[async_trait]
+impl<F, B> DerivationPipeline for DefaultDerivationPipeline<F, B>
+where
+    F: BlockSource<Query = DataQuery> + Send + Sync,
+    F::RawDataType: Send,
+    F::DecodedType: Send,
+    F::DecompressedType: AsRef<[u8]>,
+    B: BatchDecoder,
+{
+    type ProposalManifest = ProposalManifest;
+    type BlockPayloadAttributes = BlockPayloadAttributes;
+    type Error = DerivationError;
+
+    async fn derive(
+        &self,
+        proposal: ProposalManifest,
+    ) -> Result<BlockPayloadAttributes, DerivationError> {
+        let block_number = proposal.block_number;
+        let payload = match self.derive_after(proposal, self.last_block()).await {
+            Ok(payload) => payload,
+            Err(e @ DerivationError::StaleProposal(_)) => {
+                warn!("Discarding {}", e);
+                self.stale.fetch_add(1, Ordering::Relaxed);
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_stale_proposal();
+                }
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
+        self.set_last_block(block_number);
+        Ok(payload)
+    }
+
+    /// Only records the batch's last block once every proposal has derived, so a failed batch
+    /// can be retried proposal by proposal. A stale proposal fails the batch like an invalid
+    /// one, and is only counted as discarded once derived on its own.
+    #[instrument(skip_all, fields(proposals = proposals.len()))]
+    async fn derive_batch(
+        &self,
+        proposals: Vec<ProposalManifest>,
+    ) -> Result<Vec<BlockPayloadAttributes>, DerivationError> {
+        let mut previous = self.last_block();
+        let mut payloads = Vec::with_capacity(proposals.len());
+        for proposal in proposals {
+            let block_number = proposal.block_number;
+            payloads.push(self.derive_after(proposal, previous).await?);
+            previous = Some(block_number);
+        }
+
+        if let Some(block_number) = previous {
+            self.set_last_block(block_number);
+        }
+        Ok(payloads)
+    }
+}
+
+/// Verifies `payload` under whichever scheme its data hash matches, recording the scheme in
+/// `detected` and warning when it differs from the last one.
+fn detect_scheme(
+    detected: &Mutex<Option<HashScheme>>,
+    proposal: &ProposalManifest,
+    payload: &[u8],
+) -> bool {
+    let Some(scheme) = HashScheme::detect(proposal.data_hash, payload) else {
+        return false;
+    };
+    let mut detected = detected.lock().unwrap_or_else(|e| e.into_inner());
+    if *detected != Some(scheme) {
+        warn!(
+            "Guessing the data hash scheme: block {} matches {}",
+            proposal.block_number, scheme
+        );
+        *detected = Some(scheme);
+    }
+    true
+}
+
+/// Runs `query` through the fetcher's fetch, decode and decompress stages.
+async fn fetch_payload<F>(fetcher: &F, query: &DataQuery) -> Result<F::DecompressedType, String>
+where
+    F: DataSourceFetcher<Query = DataQuery>,
+{
+    let raw = fetcher.fetch(query).await.map_err(|e| e.to_string())?;
+    let decoded = fetcher.decode(raw).await.map_err(|e| e.to_string())?;
+    fetcher.decompress(decoded).await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alloy::{
+        eips::eip1559::BaseFeeParams,
+        primitives::{Address, Bytes, B256},
+        rlp,
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id},
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        Layer, Registry,
+    };
+
+    use super::*;
+    use crate::{
+        common::hasher::Sha256Hasher,
+        datasource::{mock_fetcher::MockDataSourceFetcher, CompressionType},
+    };
+
+    fn block(block_number: u64) -> DataQuery {
+        DataQuery {
+            from_block: block_number,
+            to_block: block_number,
+        }
+    }
+
+    fn batch(transactions: &[&[u8]]) -> Vec<u8> {
+        let transactions: Vec<Bytes> = transactions
+            .iter()
+            .map(|tx| Bytes::copy_from_slice(tx))
+            .collect();
+        rlp::encode(transactions)
+    }
+
+    #[tokio::test]
+    async fn derives_valid_batch() {
+        let data = batch(&[b"tx1", b"tx2"]);
+        let fetcher = MockDataSourceFetcher::new()
+            .with_response(block(7), data.clone())
+            .with_block_timestamp(7, 1_700_000_000);
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default()).unwrap();
+
+        // A wall-clock timestamp on the manifest must not leak into the block.
+        let payload = pipeline
+            .derive(ProposalManifest::new(7, 42, &data))
+            .await
+            .unwrap();
+
+        assert_eq!(payload.l1_block_number, 7);
+        assert_eq!(payload.timestamp, 1_700_000_000);
+        assert_eq!(
+            payload.transactions,
+            vec![Bytes::from_static(b"tx1"), Bytes::from_static(b"tx2")]
+        );
+    }
+
+    #[tokio::test]
+    async fn applies_payload_config() {
+        let data = batch(&[b"tx1"]);
+        let fetcher = MockDataSourceFetcher::new().with_response(block(7), data.clone());
+        let payload_config = PayloadConfig {
+            fee_recipient: Address::repeat_byte(0xfe),
+            gas_limit: 12_000_000,
+            base_fee_params: BaseFeeParams::new(50, 4),
+        };
+        let pipeline = DefaultDerivationPipeline::new(fetcher, payload_config).unwrap();
+
+        let payload = pipeline
+            .derive(ProposalManifest::new(7, 0, &data))
+            .await
+            .unwrap();
+
+        assert_eq!(payload.suggested_fee_recipient, Address::repeat_byte(0xfe));
+        assert_eq!(payload.gas_limit, 12_000_000);
+        assert_eq!(payload.base_fee_params, BaseFeeParams::new(50, 4));
+    }
+
+    #[test]
+    fn rejects_invalid_payload_config() {
+        for payload_config in [
+            PayloadConfig {
+                gas_limit: 0,
+                ..PayloadConfig::default()
+            },
+            PayloadConfig {
+                base_fee_params: BaseFeeParams::new(0, 2),
+                ..PayloadConfig::default()
+            },
+        ] {
+            assert!(matches!(
+                DefaultDerivationPipeline::new(MockDataSourceFetcher::new(), payload_config),
+                Err(DerivationError::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_corrupt_batch() {
+        let data = b"not rlp".to_vec();
+        let fetcher = MockDataSourceFetcher::new().with_response(block(7), data.clone());
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default()).unwrap();
+
+        assert!(matches!(
+            pipeline.derive(ProposalManifest::new(7, 0, &data)).await,
+            Err(DerivationError::MalformedBatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_hash_mismatch() {
+        let data = batch(&[b"tx1"]);
+        let fetcher = MockDataSourceFetcher::new().with_response(block(7), data);
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default()).unwrap();
+
+        assert!(matches!(
+            pipeline
+                .derive(ProposalManifest::new(7, 0, &batch(&[b"tx2"])))
+                .await,
+            Err(DerivationError::HashMismatch { .. })
+        ));
+    }
+
+    /// A span's name and its fields, formatted with `Debug`.
+    type RecordedSpan = (String, Vec<(String, String)>);
+
+    /// Records every span opened while it is the default subscriber.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: Arc<Mutex<Vec<RecordedSpan>>>,
+    }
+
+    impl<S: tracing::Subscriber> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            struct Fields(Vec<(String, String)>);
+
+            impl Visit for Fields {
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    self.0
+                        .push((field.name().to_string(), format!("{:?}", value)));
+                }
+            }
+
+            let mut fields = Fields(Vec::new());
+            attrs.record(&mut fields);
+            self.spans
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push((attrs.metadata().name().to_string(), fields.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn derive_span_carries_block_number() {
+        let recorder = SpanRecorder::default();
+        let _guard = tracing::subscriber::set_default(Registry::default().with(recorder.clone()));
+        let data = batch(&[b"tx1"]);
+        let fetcher = MockDataSourceFetcher::new().with_response(block(7), data.clone());
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default()).unwrap();
+
+        pipeline
+            .derive(ProposalManifest::new(7, 0, &data))
+            .await
+            .unwrap();
+
+        let spans = recorder.spans.lock().unwrap_or_else(|e| e.into_inner());
+        assert!(spans.contains(&(
+            "derive".to_string(),
+            vec![("block_number".to_string(), "7".to_string())]
+        )));
+    }
+
+    #[tokio::test]
+    async fn trace_reports_every_stage() {
+        use std::io::Write;
+
+        use flate2::{write::GzEncoder, Compression};
+
+        let data = batch(&[b"tx1", b"tx2"]);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let fetcher = MockDataSourceFetcher::new()
+            .with_compressed_response(block(7), compressed.clone(), CompressionType::Gzip)
+            .with_block_timestamp(7, 1_700_000_000);
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default()).unwrap();
+        let manifest = ProposalManifest::new(7, 0, &data);
+
+        let trace = pipeline.trace(manifest.clone()).await.unwrap();
+
+        assert_eq!(trace.raw, Bytes::from(compressed.clone()));
+        assert_eq!(trace.decoded, Bytes::from(compressed));
+        assert_eq!(trace.decompressed, Bytes::from(data));
+        assert_eq!(
+            trace.transactions,
+            vec![Bytes::from_static(b"tx1"), Bytes::from_static(b"tx2")]
+        );
+        assert_eq!(trace.attributes.timestamp, 1_700_000_000);
+        // Tracing leaves the pipeline free to derive the same proposal afterwards.
+        assert_eq!(pipeline.derive(manifest).await.unwrap(), trace.attributes);
+    }
+
+    #[tokio::test]
+    async fn first_proposal_must_build_on_the_anchor() {
+        let data = batch(&[b"tx1"]);
+        let origin_hash = B256::repeat_byte(0x0a);
+        let anchor = AnchorConfig {
+            l2_genesis_hash: B256::repeat_byte(0x02),
+            l1_origin_block: 6,
+            l1_origin_hash: origin_hash,
+        };
+        let pipeline = |origin: B256| {
+            let fetcher = MockDataSourceFetcher::new()
+                .with_response(block(7), data.clone())
+                .with_block_hash(6, origin);
+            DefaultDerivationPipeline::new(fetcher, PayloadConfig::default())
+                .unwrap()
+                .with_anchor(anchor)
+        };
+
+        let matching = pipeline(origin_hash);
+        assert!(matching
+            .derive(ProposalManifest::new(7, 0, &data))
+            .await
+            .is_ok());
+
+        let forked = pipeline(B256::repeat_byte(0x0b));
+        let result = forked.derive(ProposalManifest::new(7, 0, &data)).await;
+        assert!(matches!(result, Err(DerivationError::AnchorMismatch(_))));
+
+        // A proposal at or before the origin cannot build on it either.
+        let early = pipeline(origin_hash);
+        let result = early.derive(ProposalManifest::new(6, 0, &data)).await;
+        assert!(matches!(result, Err(DerivationError::AnchorMismatch(_))));
+    }
+
+    #[tokio::test]
+    async fn discards_proposals_older_than_the_max_age() {
+        let data = batch(&[b"tx1"]);
+        let fetcher = MockDataSourceFetcher::new()
+            .with_response(block(7), data.clone())
+            .with_response(block(8), data.clone());
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default())
+            .unwrap()
+            .with_max_proposal_age(Duration::from_secs(600));
+
+        let stale = pipeline
+            .derive(ProposalManifest::new(7, 1_000, &data))
+            .await;
+        let fresh = pipeline
+            .derive(ProposalManifest::new(8, current_unix_secs(), &data))
+            .await;
+
+        assert!(matches!(stale, Err(DerivationError::StaleProposal(_))));
+        assert_eq!(pipeline.stale_proposals(), 1);
+        assert!(!pipeline.fetcher.was_requested(&block(7)));
+        assert_eq!(fresh.unwrap().l1_block_number, 8);
+    }
+
+    #[tokio::test]
+    async fn auto_detection_verifies_sha256_commitments() {
+        let data = batch(&[b"tx1"]);
+        let fetcher = MockDataSourceFetcher::new()
+            .with_response(block(7), data.clone())
+            .with_response(block(8), data.clone());
+        let strict = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default()).unwrap();
+        let manifest = ProposalManifest::with_hasher(7, 0, &data, &Sha256Hasher);
+
+        assert!(matches!(
+            strict.derive(manifest.clone()).await,
+            Err(DerivationError::HashMismatch { .. })
+        ));
+
+        let auto = DefaultDerivationPipeline::new(strict.fetcher, PayloadConfig::default())
+            .unwrap()
+            .with_hash_auto_detection();
+        assert_eq!(auto.detected_hash_scheme(), None);
+        assert_eq!(auto.derive(manifest).await.unwrap().l1_block_number, 7);
+        assert_eq!(auto.detected_hash_scheme(), Some(HashScheme::Sha256));
+
+        auto.derive(ProposalManifest::new(8, 0, &data))
+            .await
+            .unwrap();
+        assert_eq!(auto.detected_hash_scheme(), Some(HashScheme::Keccak256));
+    }
+
+    #[tokio::test]
+    async fn batch_past_the_sequencing_window_derives_an_empty_epoch() {
+        let data = batch(&[b"tx1"]);
+        let fetcher = MockDataSourceFetcher::new()
+            .with_response(block(7), data.clone())
+            .with_block_timestamp(7, 1_700_000_000)
+            .with_response(block(8), data.clone())
+            .with_block_timestamp(8, 1_700_000_000);
+        let pipeline = DefaultDerivationPipeline::new(fetcher, PayloadConfig::default())
+            .unwrap()
+            .with_sequencing_window(Duration::from_secs(600));
+
+        let late = pipeline
+            .derive(ProposalManifest::new(7, 1_700_000_000 - 601, &data))
+            .await
+            .unwrap();
+        let on_time = pipeline
+            .derive(ProposalManifest::new(8, 1_700_000_000 - 600, &data))
+            .await
+            .unwrap();
+
+        assert!(late.is_empty_epoch);
+        assert!(late.transactions.is_empty());
+        assert_eq!(
+            late.empty_epoch_reason,
+            Some(EmptyEpochReason::SequencingWindowExpired {
+                window_secs: 600,
+                delay_secs: 601,
+            })
+        );
+        assert!(!on_time.is_empty_epoch);
+        assert_eq!(on_time.empty_epoch_reason, None);
+        assert_eq!(on_time.transactions, vec![Bytes::from_static(b"tx1")]);
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(feature = "metrics")]
+use prometheus::{IntCounter, Registry};
+
+/// Prometheus metrics recorded by the derivation pipeline.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug)]
+pub struct DerivationMetrics {
+    stale_proposals: IntCounter,
+}
+
+#[cfg(feature = "metrics")]
+impl DerivationMetrics {
+    /// Creates the derivation metrics and registers them with `registry`.
+    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let metrics = Self {
+            stale_proposals: IntCounter::new(
+                "stale_proposals_discarded_total",
+                "Proposals discarded for being older than max_proposal_age",
+            )?,
+        };
+
+        registry.register(Box::new(metrics.stale_proposals.clone()))?;
+        Ok(metrics)
+    }
+
+    pub(crate) fn record_stale_proposal(&self) {
+        self.stale_proposals.inc();
+    }
+}
+
+/// Stand-in for the Prometheus metrics when the `metrics` feature is disabled; it cannot be
+/// constructed, so the pipeline never records anything.
+#[cfg(not(feature = "metrics"))]
+#[derive(Clone, Debug)]
+pub enum DerivationMetrics {}
+
+#[cfg(not(feature = "metrics"))]
+impl DerivationMetrics {
+    pub(crate) fn record_stale_proposal(&self) {}
+}
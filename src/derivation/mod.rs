@@ -0,0 +1,5 @@
+pub mod batch_decoder;
+pub mod common;
+#[allow(clippy::module_inception)]
+pub mod derivation;
+pub mod metrics;
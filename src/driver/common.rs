@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Pings one integration before the driver starts, see
+/// [`BasedDriver::with_warmup_check`](crate::driver::driver::BasedDriver::with_warmup_check).
+pub type WarmupCheck = Box<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+/// Called with every non-fatal error, see
+/// [`BasedDriver::with_on_error`](crate::driver::driver::BasedDriver::with_on_error).
+pub type ErrorHook = Arc<dyn Fn(&DriverError) + Send + Sync>;
+
+#[derive(Debug, Error)]
+pub enum DriverError {
+    #[error("Watcher error: {0}")]
+    WatcherError(String),
+    /// A proposal kept failing to derive for a reason that may be transient.
+    #[error("Derivation error: {0}")]
+    DerivationError(String),
+    #[error("Execution error: {0}")]
+    ExecutionError(String),
+    /// The executor rejected a payload in a way retrying cannot fix.
+    #[error("Fatal execution error: {0}")]
+    FatalExecutionError(String),
+    #[error("Out-of-order proposal: {0}")]
+    OutOfOrder(String),
+    /// One or more integrations failed their warmup check; lists every failure.
+    #[error("Warmup failed: {0}")]
+    WarmupFailed(String),
+    /// A proposal's L1 block or payload kept taking longer than the fetch timeout.
+    #[error("Fetch timeout: {0}")]
+    FetchTimeout(String),
+    /// A proposal kept taking longer than the derive timeout to derive.
+    #[error("Derive timeout: {0}")]
+    DeriveTimeout(String),
+    /// A payload kept taking longer than the execute timeout to execute.
+    #[error("Execute timeout: {0}")]
+    ExecuteTimeout(String),
+    #[error("Other error: {0}")]
+    Other(String),
+}
+
+/// How long each stage may take per attempt before it counts as stalled; unset stages may take
+/// as long as they take. A stalled attempt is retried like any transient failure, and the
+/// driver stops with the stage's timeout error once retries run out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StageTimeouts {
+    /// Fetching a proposal's L1 block and payload, surfacing as [`DriverError::FetchTimeout`].
+    pub fetch_ms: Option<u64>,
+    /// Deriving a proposal, fetch included, surfacing as [`DriverError::DeriveTimeout`].
+    pub derive_ms: Option<u64>,
+    /// Executing a payload, surfacing as [`DriverError::ExecuteTimeout`].
+    pub execute_ms: Option<u64>,
+}
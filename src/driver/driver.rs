@@ -0,0 +1,1167 @@
+use std::{
+    fmt::{self, Debug, Display},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::{future::join_all, FutureExt};
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinSet,
+    time::{sleep, timeout, timeout_at, Instant},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    common::{
+        retry::{retry_with_backoff, RetryPolicy},
+        traits::ActorError,
+    },
+    driver::{
+        common::{DriverError, ErrorHook, WarmupCheck},
+        reorder::ReorderBuffer,
+    },
+    traits::{BlockOrdered, DataAvailabilityWatcher, DerivationPipeline, Driver, EngineExecutor},
+};
+
+/// Delay before the first execution retry; each further retry doubles it.
+const EXECUTION_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Derived payloads buffered between the derivation and execution tasks.
+const PAYLOAD_BUFFER: usize = 16;
+
+/// How long in-flight work may take to finish once the driver is cancelled, by default.
+const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Stand-in for an unbounded grace period; about 30 years, like tokio's own far-future deadline.
+const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 30);
+
+/// Delay before the first retry of a proposal that failed to derive, by default.
+const DERIVATION_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound on the delay between derivation retries, by default.
+const MAX_DERIVATION_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long each warmup check may take before it counts as failed.
+const WARMUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most queued proposals handed to [`DerivationPipeline::derive_batch`] at once.
+const MAX_DERIVATION_BATCH: usize = 64;
+
+/// Feeds every proposal from the watcher through the derivation pipeline and executes the
+/// resulting payload.
+///
+/// Derivation and execution run as separate tasks. While catching up, proposals already queued
+/// by the watcher are derived together with [`DerivationPipeline::derive_batch`]; if the batch
+/// fails, each of its proposals is derived on its own. A proposal that is
+/// [invalid](ActorError::is_unrecoverable), e.g. a malformed batch or a hash mismatch, derives
+/// the same way on every node, so it is logged, counted and skipped. Any other derivation error
+/// is retried under the [derivation retry policy](BasedDriver::with_derivation_retry_policy),
+/// and stops the driver once exhausted. Watcher errors are logged and skipped; if the watcher
+/// stops right after an error, that error is returned. A failed execution is retried unless the error is
+/// [unrecoverable](ActorError::is_unrecoverable); an unrecoverable error, or exhausting
+/// `max_execution_retries`, stops both tasks and is returned from [`Driver::run`], since
+/// skipping a payload would leave the L2 chain diverged.
+///
+/// With a [derive](BasedDriver::with_derive_timeout) or
+/// [execute](BasedDriver::with_execute_timeout) timeout, an attempt that stalls past it fails
+/// and is retried; once retries run out the driver stops with [`DriverError::DeriveTimeout`] or
+/// [`DriverError::ExecuteTimeout`], or [`DriverError::FetchTimeout`] when the pipeline's own
+/// [fetch timeout](ActorError::is_timeout) was what kept firing.
+///
+/// An [error hook](BasedDriver::with_on_error) sees every watcher error and every failed
+/// derivation or execution attempt as it happens, before the driver decides whether to skip,
+/// retry or stop; only a fatal execution error bypasses it.
+///
+/// With a [reorder window](BasedDriver::with_reorder_window), proposals are held back and
+/// derived in ascending block order instead of arrival order.
+///
+/// Before watching, every [warmup check](BasedDriver::with_warmup_check) runs, and the run
+/// fails with [`DriverError::WarmupFailed`] if any of them does, instead of failing later
+/// mid-run.
+///
+/// Once cancelled, no new proposal is derived or executed, but a payload already being executed
+/// is allowed to finish so the execution client is not left mid-import. Tasks still running
+/// after the shutdown grace period are aborted.
+pub struct BasedDriver<W, P, E> {
+    watcher: W,
+    pipeline: Arc<P>,
+    executor: Arc<E>,
+    max_execution_retries: u32,
+    derivation_retry: RetryPolicy,
+    /// Invalid proposals skipped, across every `run` call.
+    skipped: Arc<AtomicU64>,
+    reorder_window: Option<u64>,
+    shutdown_grace_period: Duration,
+    derive_timeout: Option<Duration>,
+    execute_timeout: Option<Duration>,
+    on_error: Option<ErrorHook>,
+    warmup_checks: Vec<(String, WarmupCheck)>,
+    warmup: bool,
+    cancel: CancellationToken,
+}
+
+impl<W, P, E> BasedDriver<W, P, E> {
+    pub fn new(watcher: W, pipeline: P, executor: E) -> Self {
+        Self {
+            watcher,
+            pipeline: Arc::new(pipeline),
+            executor: Arc::new(executor),
+            max_execution_retries: 3,
+            derivation_retry: RetryPolicy {
+                max_retries: 10,
+                base_delay: DERIVATION_RETRY_DELAY,
+                max_delay: MAX_DERIVATION_RETRY_DELAY,
+                jitter: Duration::ZERO,
+            },
+            skipped: Arc::new(AtomicU64::new(0)),
+            reorder_window: None,
+            shutdown_grace_period: DEFAULT_SHUTDOWN_GRACE_PERIOD,
+            derive_timeout: None,
+            execute_timeout: None,
+            on_error: None,
+            warmup_checks: Vec::new(),
+            warmup: true,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_max_execution_retries(mut self, max_execution_retries: u32) -> Self {
+        self.max_execution_retries = max_execution_retries;
+        self
+    }
+
+    /// Backoff for proposals that fail to derive for a transient reason, e.g. the DA source
+    /// being unreachable. By default the delay doubles from half a second up to 30 seconds, for
+    /// up to 10 retries.
+    pub fn with_derivation_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.derivation_retry = retry;
+        self
+    }
+
+    /// Returns how many invalid proposals were skipped so far.
+    pub fn skipped_proposals(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Holds each proposal until one at least `window` blocks later has arrived, then derives
+    /// the held proposals in block order. A proposal arriving after a later one was derived
+    /// stops the driver with [`DriverError::OutOfOrder`]. Proposals are derived as they arrive
+    /// by default.
+    pub fn with_reorder_window(mut self, window: u64) -> Self {
+        self.reorder_window = Some(window);
+        self
+    }
+
+    /// How long to wait for in-flight work after cancellation before aborting it; 30 seconds
+    /// by default.
+    pub fn with_shutdown_grace_period(mut self, grace_period: Duration) -> Self {
+        self.shutdown_grace_period = grace_period;
+        self
+    }
+
+    /// How long deriving one proposal may take per attempt; unbounded by default.
+    pub fn with_derive_timeout(mut self, derive_timeout: Duration) -> Self {
+        self.derive_timeout = Some(derive_timeout);
+        self
+    }
+
+    /// How long executing one payload may take per attempt; unbounded by default.
+    pub fn with_execute_timeout(mut self, execute_timeout: Duration) -> Self {
+        self.execute_timeout = Some(execute_timeout);
+        self
+    }
+
+    /// Calls `on_error` with every non-fatal error, e.g. to page someone. It runs on the
+    /// driver's own tasks, so it must return quickly and hand any slow work off elsewhere.
+    pub fn with_on_error<F>(mut self, on_error: F) -> Self
+    where
+        F: Fn(&DriverError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(on_error));
+        self
+    }
+
+    /// Adds a check that `name`, e.g. the L1 RPC or the execution client, answers, run before
+    /// every [`Driver::run`]. A check failing or taking longer than 10 seconds aborts the run.
+    pub fn with_warmup_check<F, Fut>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.warmup_checks
+            .push((name.into(), Box::new(move || check().boxed())));
+        self
+    }
+
+    /// Whether to run the warmup checks; on by default.
+    pub fn with_warmup(mut self, warmup: bool) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Runs every warmup check concurrently, failing with all of the failures at once.
+    async fn warmup(&self) -> Result<(), DriverError> {
+        if !self.warmup {
+            return Ok(());
+        }
+        let results = join_all(self.warmup_checks.iter().map(|(name, check)| async move {
+            let result = timeout(WARMUP_TIMEOUT, check())
+                .await
+                .unwrap_or_else(|_| Err(format!("timed out after {:?}", WARMUP_TIMEOUT)));
+            (name, result)
+        }))
+        .await;
+
+        let mut failures = Vec::new();
+        for (name, result) in results {
+            match result {
+                Ok(()) => info!("Warmup check {} passed", name),
+                Err(e) => {
+                    error!("Warmup check {} failed: {}", name, e);
+                    failures.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(DriverError::WarmupFailed(failures.join("; ")))
+        }
+    }
+
+    /// Returns `Ok(())` from [`Driver::run`] once `cancel` fires.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+}
+
+#[async_trait]
+impl<W, P, E> Driver for BasedDriver<W, P, E>
+where
+    W: DataAvailabilityWatcher + Send + Sync,
+    W::ProposalManifest: BlockOrdered + Clone + Send + Sync + 'static,
+    W::Error: Send + 'static,
+    P: DerivationPipeline<ProposalManifest = W::ProposalManifest> + Send + Sync + 'static,
+    P::Error: ActorError + Send,
+    E: EngineExecutor<BlockPayloadAttributes = P::BlockPayloadAttributes> + Send + Sync + 'static,
+    E::BlockPayloadAttributes: Clone + Send + 'static,
+    E::ExecutionResult: Debug + Send,
+    E::Error: ActorError + Send,
+{
+    type DataAvailabilityWatcher = W;
+    type DerivationPipeline = P;
+    type EngineExecutor = E;
+    type Error = DriverError;
+
+    /// Runs until the proposal channel closes, `cancel` fires, or a task fails; in every case
+    /// both tasks have stopped, or been aborted after the grace period, by the time it returns.
+    async fn run(&self) -> Result<(), DriverError> {
+        self.warmup().await?;
+        let proposals = self
+            .watcher
+            .watch()
+            .await
+            .map_err(|e| DriverError::WatcherError(e.to_string()))?;
+
+        // A failure stops this run's tasks without cancelling the caller's token.
+        let cancel = self.cancel.child_token();
+        let (payloads_tx, payloads_rx) = mpsc::channel(PAYLOAD_BUFFER);
+
+        let mut tasks = JoinSet::new();
+        tasks.spawn(derive_proposals(
+            Deriver {
+                pipeline: self.pipeline.clone(),
+                retry: self.derivation_retry.clone(),
+                skipped: self.skipped.clone(),
+                timeout: self.derive_timeout,
+                on_error: self.on_error.clone(),
+                cancel: cancel.clone(),
+            },
+            proposals,
+            self.reorder_window,
+            payloads_tx,
+            cancel.clone(),
+        ));
+        tasks.spawn(execute_payloads(
+            self.executor.clone(),
+            payloads_rx,
+            self.max_execution_retries,
+            self.execute_timeout,
+            self.on_error.clone(),
+            cancel.clone(),
+        ));
+
+        let mut result = Ok(());
+        let mut deadline = None;
+        loop {
+            let joined = match deadline {
+                None => tokio::select! {
+                    joined = tasks.join_next() => joined,
+                    _ = cancel.cancelled() => {
+                        info!(
+                            "Draining in-flight work for up to {:?}",
+                            self.shutdown_grace_period
+                        );
+                        // An unrepresentable deadline means waiting for as long as it takes.
+                        deadline = Some(
+                            Instant::now()
+                                .checked_add(self.shutdown_grace_period)
+                                .unwrap_or_else(|| Instant::now() + FAR_FUTURE),
+                        );
+                        continue;
+                    }
+                },
+                Some(deadline) => match timeout_at(deadline, tasks.join_next()).await {
+                    Ok(joined) => joined,
+                    Err(_) => {
+                        warn!(
+                            "{} driver tasks still running after {:?}, aborting them",
+                            tasks.len(),
+                            self.shutdown_grace_period
+                        );
+                        tasks.shutdown().await;
+                        break;
+                    }
+                },
+            };
+            let Some(joined) = joined else {
+                break;
+            };
+
+            let outcome = joined
+                .unwrap_or_else(|e| Err(DriverError::Other(format!("driver task failed: {}", e))));
+            if let Err(e) = outcome {
+                if result.is_ok() {
+                    error!("Stopping driver: {}", e);
+                    cancel.cancel();
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+}
+
+async fn derive_proposals<P, WE>(
+    deriver: Deriver<P>,
+    mut proposals: Receiver<Result<P::ProposalManifest, WE>>,
+    reorder_window: Option<u64>,
+    payloads: Sender<P::BlockPayloadAttributes>,
+    cancel: CancellationToken,
+) -> Result<(), DriverError>
+where
+    P: DerivationPipeline + Send + Sync,
+    P::ProposalManifest: BlockOrdered + Clone + Send + Sync,
+    P::BlockPayloadAttributes: Send,
+    P::Error: ActorError + Send,
+    WE: Display,
+{
+    let mut reorder = reorder_window.map(ReorderBuffer::new);
+    // The watcher's most recent error, cleared by the next proposal.
+    let mut last_error = None;
+    loop {
+        let proposal = tokio::select! {
+            // Checked first, so nothing new starts once cancelled.
+            biased;
+            _ = cancel.cancelled() => break,
+            proposal = proposals.recv() => proposal,
+        };
+        let proposal = match proposal {
+            Some(Ok(proposal)) => proposal,
+            Some(Err(e)) => {
+                warn!("Watcher error: {}", e);
+                report(&deriver.on_error, &DriverError::WatcherError(e.to_string()));
+                last_error = Some(e.to_string());
+                continue;
+            }
+            None => {
+                info!("Proposal channel closed");
+                // No earlier proposal can arrive any more.
+                if let Some(held) = reorder.as_mut().map(ReorderBuffer::flush) {
+                    if !held.is_empty() && !deriver.forward(held, &payloads).await? {
+                        return Ok(());
+                    }
+                }
+                return match last_error {
+                    Some(e) => Err(DriverError::WatcherError(e)),
+                    None => Ok(()),
+                };
+            }
+        };
+        last_error = None;
+
+        let mut batch = vec![proposal];
+        while batch.len() < MAX_DERIVATION_BATCH {
+            match proposals.try_recv() {
+                Ok(Ok(proposal)) => batch.push(proposal),
+                Ok(Err(e)) => {
+                    warn!("Watcher error: {}", e);
+                    report(&deriver.on_error, &DriverError::WatcherError(e.to_string()));
+                    last_error = Some(e.to_string());
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+
+        let batch = match reorder.as_mut() {
+            None => batch,
+            Some(reorder) => {
+                for proposal in batch {
+                    reorder.push(proposal)?;
+                }
+                let ready = reorder.pop_ready();
+                if ready.is_empty() {
+                    continue;
+                }
+                ready
+            }
+        };
+
+        if !deriver.forward(batch, &payloads).await? {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Derivation state shared by the proposals of one [`Driver::run`].
+struct Deriver<P> {
+    pipeline: Arc<P>,
+    retry: RetryPolicy,
+    skipped: Arc<AtomicU64>,
+    timeout: Option<Duration>,
+    on_error: Option<ErrorHook>,
+    cancel: CancellationToken,
+}
+
+impl<P> Deriver<P>
+where
+    P: DerivationPipeline + Sync,
+    P::ProposalManifest: BlockOrdered + Clone + Send + Sync,
+    P::BlockPayloadAttributes: Send,
+    P::Error: ActorError + Send,
+{
+    /// Derives `batch` and hands the payloads to the execution task. Returns `false` once that
+    /// task has hung up, which it only does after failing or being cancelled.
+    async fn forward(
+        &self,
+        batch: Vec<P::ProposalManifest>,
+        payloads: &Sender<P::BlockPayloadAttributes>,
+    ) -> Result<bool, DriverError> {
+        for payload in self.derive(batch).await? {
+            if payloads.send(payload).await.is_err() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Derives `batch`, skipping invalid proposals and retrying the others until they derive.
+    /// Once cancelled, returns the payloads derived so far.
+    async fn derive(
+        &self,
+        batch: Vec<P::ProposalManifest>,
+    ) -> Result<Vec<P::BlockPayloadAttributes>, DriverError> {
+        if batch.len() > 1 {
+            let len = batch.len();
+            match within(self.timeout, self.pipeline.derive_batch(batch.clone())).await {
+                Ok(payloads) => return Ok(payloads),
+                Err(e) => warn!(
+                    "Batch derivation of {} proposals failed, deriving them one by one: {}",
+                    len, e
+                ),
+            }
+        }
+
+        let mut payloads = Vec::with_capacity(batch.len());
+        for proposal in batch {
+            let derived = tokio::select! {
+                _ = self.cancel.cancelled() => break,
+                derived = retry_with_backoff(&self.retry, || async {
+                    let derived = within(self.timeout, self.pipeline.derive(proposal.clone())).await;
+                    if let Err(e) = &derived {
+                        report(&self.on_error, &derivation_error(e, proposal.block_number()));
+                    }
+                    derived
+                }) => derived,
+            };
+            match derived {
+                Ok(payload) => payloads.push(payload),
+                Err(e) if e.is_unrecoverable() => {
+                    self.skipped.fetch_add(1, Ordering::Relaxed);
+                    warn!("Skipping invalid proposal: {}", e);
+                }
+                Err(e) => return Err(derivation_error(&e, proposal.block_number())),
+            }
+        }
+        Ok(payloads)
+    }
+}
+
+async fn execute_payloads<E>(
+    executor: Arc<E>,
+    mut payloads: Receiver<E::BlockPayloadAttributes>,
+    max_retries: u32,
+    attempt_timeout: Option<Duration>,
+    on_error: Option<ErrorHook>,
+    cancel: CancellationToken,
+) -> Result<(), DriverError>
+where
+    E: EngineExecutor + Send + Sync,
+    E::BlockPayloadAttributes: Clone + Send,
+    E::ExecutionResult: Debug,
+    E::Error: ActorError,
+{
+    let mut processed: u64 = 0;
+    loop {
+        let payload = tokio::select! {
+            // Checked first, so nothing new starts once cancelled.
+            biased;
+            _ = cancel.cancelled() => break,
+            payload = payloads.recv() => payload,
+        };
+        let Some(payload) = payload else {
+            break;
+        };
+
+        let Some(result) = execute_with_retry(
+            executor.as_ref(),
+            payload,
+            max_retries,
+            attempt_timeout,
+            &on_error,
+            &cancel,
+        )
+        .await?
+        else {
+            break;
+        };
+        processed += 1;
+        info!("Executed proposal {}: {:?}", processed, result);
+    }
+
+    info!("Driver stopped after {} proposals", processed);
+    Ok(())
+}
+
+/// Returns `None` if cancelled while waiting to retry.
+async fn execute_with_retry<E>(
+    executor: &E,
+    payload: E::BlockPayloadAttributes,
+    max_retries: u32,
+    attempt_timeout: Option<Duration>,
+    on_error: &Option<ErrorHook>,
+    cancel: &CancellationToken,
+) -> Result<Option<E::ExecutionResult>, DriverError>
+where
+    E: EngineExecutor + Sync,
+    E::BlockPayloadAttributes: Clone + Send,
+    E::Error: ActorError,
+{
+    let mut retries = 0;
+    loop {
+        let err = match within(attempt_timeout, executor.execute(payload.clone())).await {
+            Ok(result) => return Ok(Some(result)),
+            Err(e) => e,
+        };
+        if err.is_unrecoverable() {
+            return Err(DriverError::FatalExecutionError(err.to_string()));
+        }
+        let error = match err {
+            Attempt::Stalled(limit) => {
+                DriverError::ExecuteTimeout(format!("payload not executed within {:?}", limit))
+            }
+            Attempt::Failed(ref e) => DriverError::ExecutionError(e.to_string()),
+        };
+        report(on_error, &error);
+        if retries >= max_retries {
+            return Err(error);
+        }
+
+        retries += 1;
+        let delay = EXECUTION_RETRY_DELAY.saturating_mul(1 << (retries - 1).min(16));
+        warn!(
+            "Execution failed (attempt {}), retrying in {:?}: {}",
+            retries, delay, err
+        );
+        tokio::select! {
+            _ = cancel.cancelled() => return Ok(None),
+            _ = sleep(delay) => {}
+        }
+    }
+}
+
+/// The driver error a failed derivation attempt of `block_number` stands for.
+fn derivation_error<E: ActorError + Display>(e: &Attempt<E>, block_number: u64) -> DriverError {
+    match e {
+        Attempt::Stalled(limit) => DriverError::DeriveTimeout(format!(
+            "block {} not derived within {:?}",
+            block_number, limit
+        )),
+        Attempt::Failed(e) if e.is_timeout() => DriverError::FetchTimeout(e.to_string()),
+        Attempt::Failed(e) => DriverError::DerivationError(e.to_string()),
+    }
+}
+
+fn report(on_error: &Option<ErrorHook>, error: &DriverError) {
+    if let Some(on_error) = on_error {
+        on_error(error);
+    }
+}
+
+/// One attempt at a stage: either the stage's own error, or the attempt outliving its timeout.
+#[derive(Debug)]
+enum Attempt<E> {
+    Failed(E),
+    Stalled(Duration),
+}
+
+impl<E: Display> Display for Attempt<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Attempt::Failed(e) => e.fmt(f),
+            Attempt::Stalled(limit) => write!(f, "timed out after {:?}", limit),
+        }
+    }
+}
+
+impl<E: ActorError> ActorError for Attempt<E> {
+    /// A stalled attempt may well finish in time on retry.
+    fn is_unrecoverable(&self) -> bool {
+        match self {
+            Attempt::Failed(e) => e.is_unrecoverable(),
+            Attempt::Stalled(_) => false,
+        }
+    }
+
+    fn is_timeout(&self) -> bool {
+        match self {
+            Attempt::Failed(e) => e.is_timeout(),
+            Attempt::Stalled(_) => true,
+        }
+    }
+}
+
+/// Runs `attempt`, failing it with [`Attempt::Stalled`] once it outlives `limit`, if set.
+async fn within<T, E>(
+    limit: Option<Duration>,
+    attempt: impl Future<Output = Result<T, E>>,
+) -> Result<T, Attempt<E>> {
+    let Some(limit) = limit else {
+        return attempt.await.map_err(Attempt::Failed);
+    };
+    match timeout(limit, attempt).await {
+        Ok(result) => result.map_err(Attempt::Failed),
+        Err(_) => Err(Attempt::Stalled(limit)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    };
+
+    use alloy::{primitives::Bytes, rlp};
+
+    use super::*;
+    use crate::{
+        da_watcher::{
+            common::{ProposalManifest, WatcherStart},
+            da_watcher::DAWatcher,
+        },
+        datasource::{
+            common::{DataQuery, FetcherError},
+            mock_fetcher::MockDataSourceFetcher,
+        },
+        derivation::{
+            common::{BlockPayloadAttributes, DerivationError, PayloadConfig},
+            derivation::DefaultDerivationPipeline,
+        },
+        execution_engine::common::ExecutionError,
+    };
+
+    /// Records the L1 block of every executed payload.
+    #[derive(Clone, Default)]
+    struct RecordingExecutor {
+        executed: Arc<Mutex<Vec<u64>>>,
+    }
+
+    impl RecordingExecutor {
+        fn executed(&self) -> Vec<u64> {
+            self.executed
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        }
+    }
+
+    #[async_trait]
+    impl EngineExecutor for RecordingExecutor {
+        type BlockPayloadAttributes = BlockPayloadAttributes;
+        type ExecutionResult = ();
+        type Error = ExecutionError;
+
+        async fn execute(&self, payload: BlockPayloadAttributes) -> Result<(), ExecutionError> {
+            self.executed
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(payload.l1_block_number);
+            Ok(())
+        }
+    }
+
+    /// Fails the first `failures` derivations with a fetch error.
+    struct FlakyPipeline<P> {
+        inner: P,
+        failures: AtomicU32,
+    }
+
+    #[async_trait]
+    impl<P> DerivationPipeline for FlakyPipeline<P>
+    where
+        P: DerivationPipeline<
+                ProposalManifest = ProposalManifest,
+                BlockPayloadAttributes = BlockPayloadAttributes,
+                Error = DerivationError,
+            > + Send
+            + Sync,
+    {
+        type ProposalManifest = ProposalManifest;
+        type BlockPayloadAttributes = BlockPayloadAttributes;
+        type Error = DerivationError;
+
+        async fn derive(
+            &self,
+            proposal: ProposalManifest,
+        ) -> Result<BlockPayloadAttributes, DerivationError> {
+            let remaining = self.failures.load(Ordering::Relaxed);
+            if remaining > 0 {
+                self.failures.store(remaining - 1, Ordering::Relaxed);
+                return Err(DerivationError::FetchError("connection reset".to_string()));
+            }
+            self.inner.derive(proposal).await
+        }
+    }
+
+    fn block(block_number: u64) -> DataQuery {
+        DataQuery {
+            from_block: block_number,
+            to_block: block_number,
+        }
+    }
+
+    fn batch(block_number: u64) -> Vec<u8> {
+        rlp::encode(vec![Bytes::from(
+            format!("tx {}", block_number).into_bytes(),
+        )])
+    }
+
+    /// Blocks `0..=3` with a valid batch in blocks 1 and 3 and `block_2` in block 2.
+    fn fetcher(block_2: Result<Vec<u8>, FetcherError>) -> MockDataSourceFetcher {
+        let fetcher = MockDataSourceFetcher::new()
+            .with_response(block(0), Vec::new())
+            .with_response(block(1), batch(1))
+            .with_response(block(3), batch(3));
+        match block_2 {
+            Ok(data) => fetcher.with_response(block(2), data),
+            Err(e) => fetcher.with_error(block(2), e),
+        }
+    }
+
+    fn watcher(cancel: &CancellationToken) -> DAWatcher<MockDataSourceFetcher> {
+        DAWatcher::new(
+            fetcher(Ok(b"not a batch".to_vec())),
+            Duration::from_secs(60),
+            8,
+            0,
+            WatcherStart::Genesis,
+        )
+        .with_cancellation(cancel.clone())
+    }
+
+    fn fast_retry(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    async fn wait_for(executor: &RecordingExecutor, blocks: &[u64]) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while executor.executed() != blocks {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("executed {:?}, expected {:?}", executor.executed(), blocks));
+    }
+
+    #[tokio::test]
+    async fn skips_invalid_proposal() {
+        let cancel = CancellationToken::new();
+        let executor = RecordingExecutor::default();
+        let pipeline = DefaultDerivationPipeline::new(
+            fetcher(Ok(b"not a batch".to_vec())),
+            PayloadConfig::default(),
+        )
+        .unwrap();
+        let driver = Arc::new(
+            BasedDriver::new(watcher(&cancel), pipeline, executor.clone())
+                .with_cancellation(cancel.clone()),
+        );
+
+        let handle = tokio::spawn({
+            let driver = driver.clone();
+            async move { driver.run().await }
+        });
+        wait_for(&executor, &[1, 3]).await;
+        cancel.cancel();
+
+        handle.await.unwrap().unwrap();
+        assert_eq!(driver.skipped_proposals(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failure() {
+        let cancel = CancellationToken::new();
+        let executor = RecordingExecutor::default();
+        let pipeline = FlakyPipeline {
+            inner: DefaultDerivationPipeline::new(
+                fetcher(Ok(b"not a batch".to_vec())),
+                PayloadConfig::default(),
+            )
+            .unwrap(),
+            failures: AtomicU32::new(2),
+        };
+        let driver = Arc::new(
+            BasedDriver::new(watcher(&cancel), pipeline, executor.clone())
+                .with_derivation_retry_policy(fast_retry(3))
+                .with_cancellation(cancel.clone()),
+        );
+
+        let handle = tokio::spawn({
+            let driver = driver.clone();
+            async move { driver.run().await }
+        });
+        wait_for(&executor, &[1, 3]).await;
+        cancel.cancel();
+
+        handle.await.unwrap().unwrap();
+        assert_eq!(driver.skipped_proposals(), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_instead_of_skipping_unavailable_proposal() {
+        let cancel = CancellationToken::new();
+        let executor = RecordingExecutor::default();
+        let pipeline = DefaultDerivationPipeline::new(
+            fetcher(Err(FetcherError::NetworkError("unreachable".to_string()))),
+            PayloadConfig::default(),
+        )
+        .unwrap();
+        let driver = BasedDriver::new(watcher(&cancel), pipeline, executor.clone())
+            .with_derivation_retry_policy(fast_retry(2));
+
+        let result = tokio::time::timeout(Duration::from_secs(5), driver.run())
+            .await
+            .expect("driver did not stop");
+
+        assert!(matches!(result, Err(DriverError::DerivationError(_))));
+        assert!(!executor.executed().contains(&3));
+        assert_eq!(driver.skipped_proposals(), 0);
+    }
+
+    /// Rejects every payload as invalid.
+    #[derive(Default)]
+    struct RejectingExecutor {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl EngineExecutor for RejectingExecutor {
+        type BlockPayloadAttributes = BlockPayloadAttributes;
+        type ExecutionResult = ();
+        type Error = ExecutionError;
+
+        async fn execute(&self, _payload: BlockPayloadAttributes) -> Result<(), ExecutionError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Err(ExecutionError::InvalidPayload("INVALID".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn fatal_execution_error_stops_driver() {
+        let cancel = CancellationToken::new();
+        let pipeline = DefaultDerivationPipeline::new(
+            fetcher(Ok(b"not a batch".to_vec())),
+            PayloadConfig::default(),
+        )
+        .unwrap();
+        let driver = BasedDriver::new(watcher(&cancel), pipeline, RejectingExecutor::default())
+            .with_cancellation(cancel.clone());
+
+        let result = tokio::time::timeout(Duration::from_secs(5), driver.run())
+            .await
+            .expect("driver did not stop");
+
+        assert!(matches!(result, Err(DriverError::FatalExecutionError(_))));
+        assert_eq!(driver.executor.calls.load(Ordering::Relaxed), 1);
+        // Only this run's tasks are stopped, not the caller's token.
+        assert!(!cancel.is_cancelled());
+        cancel.cancel();
+    }
+
+    /// Takes `delay` to execute each payload, recording when it starts and finishes.
+    struct SlowExecutor {
+        delay: Duration,
+        started: Mutex<Vec<u64>>,
+        finished: Mutex<Vec<u64>>,
+    }
+
+    impl SlowExecutor {
+        fn new(delay: Duration) -> Self {
+            Self {
+                delay,
+                started: Mutex::new(Vec::new()),
+                finished: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EngineExecutor for SlowExecutor {
+        type BlockPayloadAttributes = BlockPayloadAttributes;
+        type ExecutionResult = ();
+        type Error = ExecutionError;
+
+        async fn execute(&self, payload: BlockPayloadAttributes) -> Result<(), ExecutionError> {
+            let block = payload.l1_block_number;
+            self.started
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(block);
+            sleep(self.delay).await;
+            self.finished
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(block);
+            Ok(())
+        }
+    }
+
+    /// Runs `driver` until its first payload starts executing, then cancels it.
+    async fn cancel_mid_execution(
+        driver: &BasedDriver<
+            DAWatcher<MockDataSourceFetcher>,
+            DefaultDerivationPipeline<MockDataSourceFetcher>,
+            SlowExecutor,
+        >,
+        cancel: &CancellationToken,
+    ) -> Result<(), DriverError> {
+        let (result, _) = tokio::join!(driver.run(), async {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                while driver.executor.started.lock().unwrap().is_empty() {
+                    sleep(Duration::from_millis(5)).await;
+                }
+            })
+            .await
+            .expect("nothing was executed");
+            cancel.cancel();
+        });
+        result
+    }
+
+    fn pipeline() -> DefaultDerivationPipeline<MockDataSourceFetcher> {
+        DefaultDerivationPipeline::new(
+            fetcher(Ok(b"not a batch".to_vec())),
+            PayloadConfig::default(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn cancellation_drains_in_flight_payload() {
+        let cancel = CancellationToken::new();
+        let driver = BasedDriver::new(
+            watcher(&cancel),
+            pipeline(),
+            SlowExecutor::new(Duration::from_millis(200)),
+        )
+        .with_cancellation(cancel.clone());
+
+        cancel_mid_execution(&driver, &cancel).await.unwrap();
+
+        // Block 1 was allowed to finish, and block 3 never started.
+        assert_eq!(*driver.executor.started.lock().unwrap(), vec![1]);
+        assert_eq!(*driver.executor.finished.lock().unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn grace_period_bounds_the_drain() {
+        let cancel = CancellationToken::new();
+        let driver = BasedDriver::new(
+            watcher(&cancel),
+            pipeline(),
+            SlowExecutor::new(Duration::from_secs(60)),
+        )
+        .with_shutdown_grace_period(Duration::from_millis(50))
+        .with_cancellation(cancel.clone());
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            cancel_mid_execution(&driver, &cancel),
+        )
+        .await
+        .expect("driver did not abort its tasks")
+        .unwrap();
+
+        assert!(driver.executor.finished.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_warmup_check_aborts_before_watching() {
+        let cancel = CancellationToken::new();
+        let executor = RecordingExecutor::default();
+        let pipeline = DefaultDerivationPipeline::new(
+            fetcher(Ok(b"not a batch".to_vec())),
+            PayloadConfig::default(),
+        )
+        .unwrap();
+        let driver = BasedDriver::new(watcher(&cancel), pipeline, executor.clone())
+            .with_warmup_check("l1 rpc", || async { Ok(()) })
+            .with_warmup_check("engine api", || async {
+                Err("connection refused".to_string())
+            })
+            .with_warmup_check("da source", || async { Err("no head".to_string()) })
+            .with_cancellation(cancel.clone());
+
+        let result = driver.run().await;
+
+        assert!(matches!(
+            result,
+            Err(DriverError::WarmupFailed(report))
+                if report == "engine api: connection refused; da source: no head"
+        ));
+        assert!(executor.executed().is_empty());
+
+        // Skipping the warmup runs the driver despite the failing checks.
+        let driver = Arc::new(driver.with_warmup(false));
+        let handle = tokio::spawn({
+            let driver = driver.clone();
+            async move { driver.run().await }
+        });
+        wait_for(&executor, &[1, 3]).await;
+        cancel.cancel();
+        handle.await.unwrap().unwrap();
+    }
+
+    /// A pipeline whose fetches take a minute.
+    fn stalled_pipeline() -> DefaultDerivationPipeline<MockDataSourceFetcher> {
+        DefaultDerivationPipeline::new(
+            fetcher(Ok(b"not a batch".to_vec())).with_latency(Duration::from_secs(60)),
+            PayloadConfig::default(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn stalled_fetch_surfaces_as_fetch_timeout() {
+        let cancel = CancellationToken::new();
+        let pipeline = stalled_pipeline().with_fetch_timeout(Duration::from_millis(20));
+        let driver = BasedDriver::new(watcher(&cancel), pipeline, RecordingExecutor::default())
+            .with_derivation_retry_policy(fast_retry(1));
+
+        let result = tokio::time::timeout(Duration::from_secs(5), driver.run())
+            .await
+            .expect("driver did not stop");
+
+        assert!(matches!(result, Err(DriverError::FetchTimeout(_))));
+    }
+
+    #[tokio::test]
+    async fn stalled_derivation_surfaces_as_derive_timeout() {
+        let cancel = CancellationToken::new();
+        let driver = BasedDriver::new(
+            watcher(&cancel),
+            stalled_pipeline(),
+            RecordingExecutor::default(),
+        )
+        .with_derive_timeout(Duration::from_millis(20))
+        .with_derivation_retry_policy(fast_retry(1));
+
+        let result = tokio::time::timeout(Duration::from_secs(5), driver.run())
+            .await
+            .expect("driver did not stop");
+
+        assert!(matches!(result, Err(DriverError::DeriveTimeout(_))));
+    }
+
+    #[tokio::test]
+    async fn stalled_execution_surfaces_as_execute_timeout() {
+        let cancel = CancellationToken::new();
+        let driver = BasedDriver::new(
+            watcher(&cancel),
+            pipeline(),
+            SlowExecutor::new(Duration::from_secs(60)),
+        )
+        .with_execute_timeout(Duration::from_millis(20))
+        .with_max_execution_retries(1);
+
+        let result = tokio::time::timeout(Duration::from_secs(5), driver.run())
+            .await
+            .expect("driver did not stop");
+
+        assert!(matches!(result, Err(DriverError::ExecuteTimeout(_))));
+        assert_eq!(*driver.executor.started.lock().unwrap(), vec![1, 1]);
+    }
+
+    #[tokio::test]
+    async fn error_hook_sees_each_failed_attempt_before_its_retry() {
+        let cancel = CancellationToken::new();
+        let executor = RecordingExecutor::default();
+        let pipeline = FlakyPipeline {
+            inner: DefaultDerivationPipeline::new(
+                fetcher(Ok(b"not a batch".to_vec())),
+                PayloadConfig::default(),
+            )
+            .unwrap(),
+            failures: AtomicU32::new(2),
+        };
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let driver = BasedDriver::new(watcher(&cancel), pipeline, executor.clone())
+            .with_derivation_retry_policy(fast_retry(5))
+            .with_on_error({
+                let seen = seen.clone();
+                move |e| seen.lock().unwrap().push(e.to_string())
+            })
+            .with_cancellation(cancel.clone());
+
+        let (result, _) = tokio::join!(driver.run(), async {
+            wait_for(&executor, &[1, 3]).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        let seen = seen.lock().unwrap();
+        // A transient failure is seen before each retry, then the invalid proposal before it
+        // is skipped; a failure inside a batch derivation only triggers the one-by-one retry.
+        assert!(seen.len() >= 2, "{:?}", seen);
+        assert!(seen[..seen.len() - 1]
+            .iter()
+            .all(|e| e == "Derivation error: Fetch error: connection reset"));
+        assert!(seen[seen.len() - 1].starts_with("Derivation error: Malformed batch"));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod common;
+#[allow(clippy::module_inception)]
+pub mod driver;
+pub mod reorder;
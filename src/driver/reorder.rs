@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use crate::{driver::common::DriverError, traits::BlockOrdered};
+
+/// Holds proposals back until they can be released in ascending block order.
+///
+/// Proposals only exist for blocks that carry data, so the next one cannot be known in advance.
+/// Instead, a proposal is released once another at least `window` blocks later has arrived; any
+/// proposal arriving after a later one was released is out of order beyond the window and
+/// rejected.
+#[derive(Debug)]
+pub struct ReorderBuffer<T> {
+    window: u64,
+    pending: BTreeMap<u64, T>,
+    highest: Option<u64>,
+    released: Option<u64>,
+}
+
+impl<T: BlockOrdered> ReorderBuffer<T> {
+    pub fn new(window: u64) -> Self {
+        Self {
+            window,
+            pending: BTreeMap::new(),
+            highest: None,
+            released: None,
+        }
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), DriverError> {
+        let block_number = item.block_number();
+        if let Some(released) = self.released.filter(|released| block_number <= *released) {
+            return Err(DriverError::OutOfOrder(format!(
+                "proposal for block {} arrived after block {} was released (window {})",
+                block_number, released, self.window
+            )));
+        }
+        if self.pending.insert(block_number, item).is_some() {
+            return Err(DriverError::OutOfOrder(format!(
+                "duplicate proposal for block {}",
+                block_number
+            )));
+        }
+        self.highest = self.highest.max(Some(block_number));
+        Ok(())
+    }
+
+    /// Removes the proposals that are outside the window, lowest first.
+    pub fn pop_ready(&mut self) -> Vec<T> {
+        let Some(cutoff) = self
+            .highest
+            .and_then(|highest| highest.checked_sub(self.window))
+        else {
+            return Vec::new();
+        };
+        let held = self.pending.split_off(&cutoff.saturating_add(1));
+        let ready = std::mem::replace(&mut self.pending, held);
+        self.take(ready)
+    }
+
+    /// Removes every pending proposal, lowest first, e.g. once no more can arrive.
+    pub fn flush(&mut self) -> Vec<T> {
+        let pending = std::mem::take(&mut self.pending);
+        self.take(pending)
+    }
+
+    fn take(&mut self, items: BTreeMap<u64, T>) -> Vec<T> {
+        if let Some((block_number, _)) = items.last_key_value() {
+            self.released = Some(*block_number);
+        }
+        items.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Proposal(u64);
+
+    impl BlockOrdered for Proposal {
+        fn block_number(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn blocks(proposals: Vec<Proposal>) -> Vec<u64> {
+        proposals.into_iter().map(|proposal| proposal.0).collect()
+    }
+
+    #[test]
+    fn releases_shuffled_proposals_in_ascending_order() {
+        let mut buffer = ReorderBuffer::new(5);
+        let mut released = Vec::new();
+
+        for block_number in [4, 2, 7, 3, 9, 8, 14, 12, 20] {
+            buffer.push(Proposal(block_number)).unwrap();
+            released.extend(blocks(buffer.pop_ready()));
+        }
+        released.extend(blocks(buffer.flush()));
+
+        assert_eq!(released, vec![2, 3, 4, 7, 8, 9, 12, 14, 20]);
+    }
+
+    #[test]
+    fn holds_proposals_within_the_window() {
+        let mut buffer = ReorderBuffer::new(5);
+
+        buffer.push(Proposal(10)).unwrap();
+        buffer.push(Proposal(14)).unwrap();
+        assert!(buffer.pop_ready().is_empty());
+
+        buffer.push(Proposal(15)).unwrap();
+        assert_eq!(blocks(buffer.pop_ready()), vec![10]);
+    }
+
+    #[test]
+    fn rejects_proposals_beyond_the_window() {
+        let mut buffer = ReorderBuffer::new(2);
+        buffer.push(Proposal(5)).unwrap();
+        buffer.push(Proposal(8)).unwrap();
+        assert_eq!(blocks(buffer.pop_ready()), vec![5]);
+
+        assert!(matches!(
+            buffer.push(Proposal(4)),
+            Err(DriverError::OutOfOrder(_))
+        ));
+        assert!(matches!(
+            buffer.push(Proposal(8)),
+            Err(DriverError::OutOfOrder(_))
+        ));
+        buffer.push(Proposal(6)).unwrap();
+        assert_eq!(blocks(buffer.flush()), vec![6, 8]);
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::B256;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::event_indexer::common::EventIndexerError;
+
+/// The last block whose events were fully processed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub block_number: u64,
+    /// Hash of `block_number` when known, so a resume can tell the block was reorged out.
+    pub block_hash: Option<B256>,
+}
+
+/// Format version [`FileCheckpointStore`] writes. Version 1 is the format from before
+/// checkpoints were versioned, which has no `version` field.
+pub const CHECKPOINT_VERSION: u64 = 2;
+
+/// A checkpoint as [`FileCheckpointStore`] writes it, tagged with its format version.
+#[derive(Serialize)]
+struct VersionedCheckpoint<'a> {
+    version: u64,
+    #[serde(flatten)]
+    checkpoint: &'a Checkpoint,
+}
+
+/// Durable storage for the indexer's progress.
+pub trait CheckpointStore: Debug + Send + Sync {
+    /// Returns the saved checkpoint, or `None` if nothing was saved yet.
+    fn load(&self) -> Result<Option<Checkpoint>, EventIndexerError>;
+
+    fn save(&self, checkpoint: &Checkpoint) -> Result<(), EventIndexerError>;
+}
+
+/// Stores the checkpoint as JSON in a single file.
+///
+/// Checkpoints written by an older version are migrated on load and rewritten in the
+/// current format by the next save. One from a newer version fails to load rather than being
+/// misread.
+#[derive(Clone, Debug)]
+pub struct FileCheckpointStore {
+    path: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    fn load(&self) -> Result<Option<Checkpoint>, EventIndexerError> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(checkpoint_error("read", &self.path, e)),
+        };
+
+        let stored: Value = serde_json::from_str(&contents)
+            .map_err(|e| checkpoint_error("parse", &self.path, e))?;
+        migrate(stored)
+            .map(Some)
+            .map_err(|e| checkpoint_error("load", &self.path, e))
+    }
+
+    /// Writes to a sibling temp file and renames it over the checkpoint, so a crash mid-write
+    /// leaves the previous checkpoint intact.
+    fn save(&self, checkpoint: &Checkpoint) -> Result<(), EventIndexerError> {
+        let contents = serde_json::to_vec(&VersionedCheckpoint {
+            version: CHECKPOINT_VERSION,
+            checkpoint,
+        })
+        .map_err(|e| checkpoint_error("serialize", &self.path, e))?;
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut file =
+            File::create(&tmp_path).map_err(|e| checkpoint_error("create", &tmp_path, e))?;
+        file.write_all(&contents)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| checkpoint_error("write", &tmp_path, e))?;
+
+        fs::rename(&tmp_path, &self.path).map_err(|e| checkpoint_error("rename", &tmp_path, e))
+    }
+}
+
+/// Reads a stored checkpoint of any supported version into the current format.
+fn migrate(mut stored: Value) -> Result<Checkpoint, String> {
+    let version = match stored
+        .as_object_mut()
+        .and_then(|fields| fields.remove("version"))
+    {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("invalid version {}", version))?,
+    };
+    match version {
+        // Version 2 only added the version field.
+        1 | 2 => serde_json::from_value(stored).map_err(|e| e.to_string()),
+        version if version > CHECKPOINT_VERSION => Err(format!(
+            "version {} is newer than the supported version {}; upgrade the driver or remove \
+             the checkpoint",
+            version, CHECKPOINT_VERSION
+        )),
+        version => Err(format!("unknown version {}", version)),
+    }
+}
+
+fn checkpoint_error(action: &str, path: &Path, e: impl std::fmt::Display) -> EventIndexerError {
+    EventIndexerError::CheckpointError(format!("failed to {} {}: {}", action, path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_round_trips() {
+        let path = std::env::temp_dir().join(format!(
+            "based-rollup-checkpoint-{}.json",
+            std::process::id()
+        ));
+        let store = FileCheckpointStore::new(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(store.load().unwrap(), None);
+
+        let checkpoint = Checkpoint {
+            block_number: 42,
+            block_hash: Some(B256::repeat_byte(0x42)),
+        };
+        store.save(&checkpoint).unwrap();
+        assert_eq!(store.load().unwrap(), Some(checkpoint));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn migrates_v1_checkpoint_on_save() {
+        let path = std::env::temp_dir().join(format!(
+            "based-rollup-checkpoint-v1-{}.json",
+            std::process::id()
+        ));
+        let store = FileCheckpointStore::new(&path);
+        fs::write(&path, r#"{"block_number":42,"block_hash":null}"#).unwrap();
+
+        let checkpoint = store.load().unwrap().unwrap();
+        assert_eq!(
+            checkpoint,
+            Checkpoint {
+                block_number: 42,
+                block_hash: None,
+            }
+        );
+
+        store.save(&checkpoint).unwrap();
+        let stored: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(stored["version"], CHECKPOINT_VERSION);
+        assert_eq!(store.load().unwrap(), Some(checkpoint));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_checkpoint_from_a_newer_version() {
+        let path = std::env::temp_dir().join(format!(
+            "based-rollup-checkpoint-v99-{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, r#"{"version":99,"block_number":42}"#).unwrap();
+
+        let result = FileCheckpointStore::new(&path).load();
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(EventIndexerError::CheckpointError(message)) if message.contains("version 99 is newer")
+        ));
+    }
+}
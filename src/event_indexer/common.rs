@@ -1,13 +1,148 @@
-use alloy::transports::TransportError;
+use std::{
+    ops::RangeInclusive,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::{Address, Bytes, B256},
+    rpc::types::Log,
+    transports::{TransportError, TransportErrorKind},
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::{common::traits::ActorError, event_indexer::decoder::DecodeError};
+
+/// How the indexer follows the chain head once the historical backfill is done.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TailMode {
+    /// Subscribe to new blocks over WebSocket/IPC.
+    #[default]
+    Subscribe,
+    /// Never subscribe; poll `eth_getLogs` from the last indexed block to the head on an interval.
+    Poll {
+        #[serde(rename = "interval_ms", with = "crate::common::serde_millis")]
+        interval: Duration,
+    },
+}
+
+/// The newest block the indexer indexes up to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexTarget {
+    /// The head itself.
+    Latest,
+    /// The block the provider reports as `safe`.
+    Safe,
+    /// The block the provider reports as `finalized`, following consensus finality.
+    Finalized,
+    /// The newest block buried under this many blocks.
+    Confirmations(u64),
+}
+
+/// Values required at the indexed topic positions 1-3, e.g. an event's indexed `sender`. A log
+/// matches a position holding any of its values; an empty position matches any log.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TopicFilter {
+    pub topic1: Vec<B256>,
+    pub topic2: Vec<B256>,
+    pub topic3: Vec<B256>,
+}
+
+/// A cap on retries across the whole indexer lifetime, on top of the per-call `max_retries`,
+/// so a flapping endpoint cannot keep the indexer retrying forever.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetryBudget {
+    /// Retries allowed within each `window` before the budget trips.
+    pub max_retries: u32,
+    /// How often the retry count resets.
+    #[serde(rename = "window_ms", with = "crate::common::serde_millis")]
+    pub window: Duration,
+    pub on_exhausted: RetryBudgetAction,
+}
+
+/// What the indexer does once the [`RetryBudget`] is spent.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryBudgetAction {
+    /// Pause for the given duration, then start a fresh window.
+    #[serde(rename = "cooldown_ms")]
+    Cooldown(#[serde(with = "crate::common::serde_millis")] Duration),
+    /// Stop with [`EventIndexerError::RetryBudgetExhausted`].
+    Halt,
+}
+
 /// Configuration for the live event indexer.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EventIndexerConfig {
+    /// Blocks processed, and marked indexed, per step of a backfill; bounds how many logs are
+    /// held in memory at once. Independent of `max_block_range`: a batch wider than it is
+    /// fetched in several requests.
     pub batch_size: u64,
     pub max_retries: u32,
+    /// Delay before the first retry; each further retry doubles it.
     pub retry_delay_ms: u64,
+    /// Upper bound of the random delay added to each retry; `0` disables jitter.
+    pub retry_jitter_ms: u64,
+    /// Largest block span requested from the provider in a single `eth_getLogs` call, i.e. the
+    /// provider's hard range cap. Every fetch is chunked to it, and a span the provider still
+    /// rejects as too large is bisected.
     pub max_block_range: u64,
+    /// Number of recent head hashes kept to detect reorgs and find the common ancestor.
+    pub max_reorg_depth: usize,
+    /// Blocks re-indexed per step after a reorg, each step's events emitted before the next is
+    /// fetched, so consumers take a deep rollback in pieces after its `Reorg` notice; `0`
+    /// re-indexes the whole range at once.
+    pub reorg_batch_size: u64,
+    /// Blocks back from the newest processed log within which logs are remembered by
+    /// `(block_number, log_index)`, so ranges re-indexed after a reconnect or rewind emit each
+    /// log once; `0` disables it. Should be at least `max_reorg_depth`. A log whose block hash
+    /// changed is still emitted.
+    pub dedup_window: u64,
+    /// Blocks a block must be buried under before it is indexed; `0` indexes the head itself.
+    /// Ignored when `index_target` is set.
+    pub confirmations: u64,
+    /// Where indexing stops; unset means [`IndexTarget::Confirmations`] with `confirmations`.
+    pub index_target: Option<IndexTarget>,
+    /// Narrows the indexed events by their indexed parameters.
+    pub topic_filter: TopicFilter,
+    /// Query each `max_block_range` window with a single `eth_getLogs` first, only falling
+    /// back to `batch_size` batches when the provider rejects the wide request.
+    pub wide_query: bool,
+    pub tail_mode: TailMode,
+    /// Interval at which `TailMode::Subscribe` polls instead when the provider cannot
+    /// subscribe, e.g. over plain HTTP.
+    pub fallback_poll_interval_ms: u64,
+    pub retry_budget: Option<RetryBudget>,
+    /// Size of the pool of blocking threads logs are decoded on, e.g. by a
+    /// [`TopicRouter`](crate::event_indexer::decoder::TopicRouter) with an expensive decoder
+    /// per topic; logs are still emitted in order. `1` decodes them one at a time on the
+    /// indexer task.
+    pub process_concurrency: usize,
+    /// Blocks to advance between checkpoint saves, when a checkpoint store is set.
+    pub checkpoint_interval: u64,
+    /// How often a long backfill logs its progress; `0` disables the log.
+    pub progress_log_interval_ms: u64,
+    /// Block timestamps remembered so each block's header is fetched once; `0` disables the
+    /// cache.
+    pub header_cache_size: usize,
+    /// Caps provider requests issued by the indexer (log queries and header fetches); unset
+    /// leaves them unthrottled.
+    pub max_requests_per_second: Option<f64>,
+}
+
+impl EventIndexerConfig {
+    pub fn index_target(&self) -> IndexTarget {
+        self.index_target
+            .unwrap_or(IndexTarget::Confirmations(self.confirmations))
+    }
 }
 
 /// Default configuration values for the live event indexer.
@@ -17,21 +152,350 @@ impl Default for EventIndexerConfig {
             batch_size: 1000,
             max_retries: 3,
             retry_delay_ms: 1000,
+            retry_jitter_ms: 0,
             max_block_range: 10000,
+            max_reorg_depth: 64,
+            reorg_batch_size: 0,
+            dedup_window: 64,
+            confirmations: 0,
+            index_target: None,
+            topic_filter: TopicFilter::default(),
+            wide_query: false,
+            tail_mode: TailMode::default(),
+            fallback_poll_interval_ms: 12000,
+            retry_budget: None,
+            process_concurrency: 1,
+            checkpoint_interval: 100,
+            progress_log_interval_ms: 10000,
+            header_cache_size: 256,
+            max_requests_per_second: None,
+        }
+    }
+}
+
+/// An indexed log, as forwarded to consumers of the indexer's event channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedEvent {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub transaction_hash: B256,
+    pub log_index: u64,
+    /// The contract that emitted the log.
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+}
+
+impl From<&Log> for IndexedEvent {
+    fn from(log: &Log) -> Self {
+        Self {
+            block_number: log.block_number.unwrap_or_default(),
+            block_timestamp: log.block_timestamp.unwrap_or_default(),
+            transaction_hash: log.transaction_hash.unwrap_or_default(),
+            log_index: log.log_index.unwrap_or_default(),
+            address: log.address(),
+            topics: log.topics().to_vec(),
+            data: log.data().data.clone(),
+        }
+    }
+}
+
+/// An item on the indexer's event channel.
+///
+/// Consumers that store logs keyed by `(block_number, log_index)` must treat a
+/// [`Reorg`](IndexerEvent::Reorg) as delete-then-insert: drop everything stored for its
+/// `invalidated` blocks before applying any later event. The canonical chain's logs for those
+/// blocks follow the notice and may reuse the stale entries' keys.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexerEvent<E = IndexedEvent> {
+    /// A matching log, as produced by the indexer's decoder.
+    Log(E),
+    /// The last `depth` indexed blocks were reorged out; everything emitted after
+    /// `common_ancestor` is stale and is about to be re-emitted from the canonical chain.
+    Reorg {
+        depth: u64,
+        common_ancestor: u64,
+        /// The blocks whose emitted logs are stale, `common_ancestor + 1` through
+        /// `common_ancestor + depth`.
+        invalidated: RangeInclusive<u64>,
+    },
+}
+
+impl<E> IndexerEvent<E> {
+    /// A notice that the `depth` blocks after `common_ancestor` were reorged out.
+    pub fn reorg(depth: u64, common_ancestor: u64) -> Self {
+        IndexerEvent::Reorg {
+            depth,
+            common_ancestor,
+            invalidated: common_ancestor + 1..=common_ancestor + depth,
+        }
+    }
+}
+
+/// A runtime instruction for a running indexer, see [`EventIndexer::run_with_control`].
+///
+/// [`EventIndexer::run_with_control`]: crate::event_indexer::event_indexer::EventIndexer::run_with_control
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexerCommand {
+    /// Stop indexing new blocks; heads keep being followed but nothing is fetched.
+    Pause,
+    /// Catch up to the head and continue indexing.
+    Resume,
+    /// Index logs emitted by this contract, replacing the configured addresses, from the next
+    /// indexed block on.
+    SetAddress(Address),
+    /// Rewind `last_indexed_block` to this block and re-index forward from the block after it.
+    RewindTo(u64),
+}
+
+/// Shared view of an indexer's progress, readable from other tasks while it runs.
+#[derive(Clone, Debug, Default)]
+pub struct IndexerProgress {
+    last_indexed_block: Arc<AtomicU64>,
+    /// Newest confirmed head the indexer has seen.
+    head: Arc<AtomicU64>,
+    is_indexing: Arc<AtomicBool>,
+    /// `(from, to, started)` of the range being indexed.
+    backfill: Arc<Mutex<Option<(u64, u64, Instant)>>>,
+}
+
+impl IndexerProgress {
+    pub fn last_indexed_block(&self) -> u64 {
+        self.last_indexed_block.load(Ordering::Relaxed)
+    }
+
+    /// Whether a historical range is being indexed right now.
+    pub fn is_indexing(&self) -> bool {
+        self.is_indexing.load(Ordering::Relaxed)
+    }
+
+    /// Progress through the range being indexed, while [`is_indexing`](Self::is_indexing).
+    pub fn backfill(&self) -> Option<BackfillProgress> {
+        let (from, to, started) = (*self.backfill.lock().unwrap_or_else(|e| e.into_inner()))?;
+        Some(BackfillProgress::new(
+            from,
+            to,
+            self.last_indexed_block(),
+            started.elapsed(),
+        ))
+    }
+
+    /// Compares progress against `head`, the newest block the indexer is expected to reach.
+    pub fn sync_status(&self, head: u64) -> SyncStatus {
+        let last_indexed_block = self.last_indexed_block();
+        SyncStatus {
+            last_indexed_block,
+            head,
+            lag: head.saturating_sub(last_indexed_block),
+        }
+    }
+
+    /// Compares progress against the newest confirmed head the indexer has seen, without
+    /// querying the provider.
+    pub fn status(&self) -> SyncStatus {
+        self.sync_status(self.head.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn set_head(&self, head: u64) {
+        self.head.store(head, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_last_indexed_block(&self, block: u64) {
+        self.last_indexed_block.store(block, Ordering::Relaxed);
+    }
+
+    pub(crate) fn start_backfill(&self, from: u64, to: u64) {
+        *self.backfill.lock().unwrap_or_else(|e| e.into_inner()) = Some((from, to, Instant::now()));
+        self.is_indexing.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn finish_backfill(&self) {
+        self.is_indexing.store(false, Ordering::Relaxed);
+        *self.backfill.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// How far an [`index_events`] run has come through its range.
+///
+/// [`index_events`]: crate::event_indexer::event_indexer::EventIndexer::index_events
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// Blocks of the range indexed so far.
+    pub indexed_blocks: u64,
+    pub percent: f64,
+    pub blocks_per_sec: f64,
+    /// Estimated seconds until the whole range is indexed; unknown until a block is.
+    pub eta_secs: Option<u64>,
+}
+
+impl BackfillProgress {
+    /// Progress through `[from_block, to_block]` with `last_indexed_block` reached `elapsed`
+    /// after the range was started.
+    pub fn new(from_block: u64, to_block: u64, last_indexed_block: u64, elapsed: Duration) -> Self {
+        let total = to_block.saturating_sub(from_block).saturating_add(1);
+        let indexed_blocks = last_indexed_block
+            .saturating_add(1)
+            .saturating_sub(from_block)
+            .min(total);
+
+        let secs = elapsed.as_secs_f64();
+        let blocks_per_sec = if secs > 0.0 {
+            indexed_blocks as f64 / secs
+        } else {
+            0.0
+        };
+        let eta_secs = (blocks_per_sec > 0.0)
+            .then(|| ((total - indexed_blocks) as f64 / blocks_per_sec).ceil() as u64);
+
+        Self {
+            from_block,
+            to_block,
+            indexed_blocks,
+            percent: indexed_blocks as f64 / total as f64 * 100.0,
+            blocks_per_sec,
+            eta_secs,
         }
     }
 }
 
+/// How far the indexer trails the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub last_indexed_block: u64,
+    pub head: u64,
+    /// Blocks between `last_indexed_block` and `head`.
+    pub lag: u64,
+}
+
+impl SyncStatus {
+    pub fn is_synced(&self) -> bool {
+        self.lag == 0
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum EventIndexerError {
     #[error("Provider error: {0}")]
     ProviderError(String),
+    /// The provider rejected `eth_getLogs` for a span that cannot be split any further.
+    #[error("Block range {from}-{to} is too large for the provider")]
+    RangeTooLarge { from: u64, to: u64 },
+    /// The provider's connection, and with it any subscription, went away.
+    #[error("Subscription closed by the provider")]
+    SubscriptionClosed,
+    #[error("Reorg of {depth} blocks")]
+    Reorg { depth: u64 },
+    #[error("Decode error: {0}")]
+    DecodeError(String),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Retry budget exhausted: {retries} retries within {window:?}")]
+    RetryBudgetExhausted { retries: u32, window: Duration },
+    #[error("Checkpoint error: {0}")]
+    CheckpointError(String),
+    #[error("Sink error: {0}")]
+    SinkError(String),
+    #[error("Event channel closed")]
+    ChannelClosed,
     #[error("Other error: {0}")]
     Other(String),
 }
 
 impl From<TransportError> for EventIndexerError {
     fn from(err: TransportError) -> Self {
-        EventIndexerError::ProviderError(err.to_string())
+        match err {
+            TransportError::Transport(TransportErrorKind::BackendGone) => {
+                EventIndexerError::SubscriptionClosed
+            }
+            err => EventIndexerError::ProviderError(err.to_string()),
+        }
+    }
+}
+
+impl ActorError for EventIndexerError {
+    /// Retrying cannot help errors caused by the request itself, its configuration or the
+    /// indexer's consumers.
+    fn is_unrecoverable(&self) -> bool {
+        matches!(
+            self,
+            EventIndexerError::RangeTooLarge { .. }
+                | EventIndexerError::DecodeError(_)
+                | EventIndexerError::InvalidConfig(_)
+                | EventIndexerError::RetryBudgetExhausted { .. }
+                | EventIndexerError::ChannelClosed
+        )
+    }
+}
+
+impl From<DecodeError> for EventIndexerError {
+    fn from(err: DecodeError) -> Self {
+        EventIndexerError::DecodeError(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_display_their_context() {
+        for (err, expected) in [
+            (
+                EventIndexerError::RangeTooLarge { from: 10, to: 20 },
+                "Block range 10-20 is too large for the provider",
+            ),
+            (
+                EventIndexerError::SubscriptionClosed,
+                "Subscription closed by the provider",
+            ),
+            (EventIndexerError::Reorg { depth: 3 }, "Reorg of 3 blocks"),
+            (
+                EventIndexerError::DecodeError("bad topic".to_string()),
+                "Decode error: bad topic",
+            ),
+        ] {
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn dropped_backend_is_a_closed_subscription() {
+        assert!(matches!(
+            EventIndexerError::from(TransportError::Transport(TransportErrorKind::BackendGone)),
+            EventIndexerError::SubscriptionClosed
+        ));
+        assert!(matches!(
+            EventIndexerError::from(TransportErrorKind::custom_str("connection reset")),
+            EventIndexerError::ProviderError(_)
+        ));
+    }
+
+    #[test]
+    fn backfill_eta_follows_the_indexing_rate() {
+        let progress = BackfillProgress::new(1_000, 10_999, 3_499, Duration::from_secs(50));
+
+        assert_eq!(progress.indexed_blocks, 2_500);
+        assert_eq!(progress.percent, 25.0);
+        assert_eq!(progress.blocks_per_sec, 50.0);
+        assert_eq!(progress.eta_secs, Some(150));
+
+        let done = BackfillProgress::new(1_000, 10_999, 10_999, Duration::from_secs(200));
+        assert_eq!(done.percent, 100.0);
+        assert_eq!(done.eta_secs, Some(0));
+    }
+
+    #[test]
+    fn backfill_eta_is_unknown_before_the_first_block() {
+        let progress = BackfillProgress::new(1_000, 10_999, 999, Duration::from_secs(5));
+
+        assert_eq!(progress.indexed_blocks, 0);
+        assert_eq!(progress.percent, 0.0);
+        assert_eq!(progress.eta_secs, None);
+        assert_eq!(
+            BackfillProgress::new(1_000, 10_999, 1_500, Duration::ZERO).eta_secs,
+            None
+        );
     }
 }
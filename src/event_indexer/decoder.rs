@@ -0,0 +1,243 @@
+use std::{collections::HashMap, fmt, marker::PhantomData};
+
+use alloy::{
+    primitives::{Address, B256},
+    rpc::types::Log,
+    sol_types::SolEvent,
+};
+use thiserror::Error;
+
+use crate::event_indexer::common::IndexedEvent;
+
+/// Turns a raw log into the value the indexer emits for it.
+pub trait EventDecoder: Send + Sync {
+    type Event: Send;
+
+    fn decode(&self, log: &Log) -> Result<Self::Event, DecodeError>;
+}
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("Unexpected event signature: {0:?}")]
+    UnexpectedSignature(Option<B256>),
+    #[error("ABI decode error: {0}")]
+    AbiError(String),
+}
+
+/// Emits every log as an undecoded [`IndexedEvent`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RawEventDecoder;
+
+impl EventDecoder for RawEventDecoder {
+    type Event = IndexedEvent;
+
+    fn decode(&self, log: &Log) -> Result<IndexedEvent, DecodeError> {
+        Ok(IndexedEvent::from(log))
+    }
+}
+
+/// A decoded event together with where it was emitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedEvent<E> {
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub transaction_hash: B256,
+    pub log_index: u64,
+    pub address: Address,
+    pub event: E,
+}
+
+/// Decodes logs into an event type generated by alloy's `sol!` macro.
+///
+/// ```no_run
+/// use alloy::{sol, sol_types::SolEvent};
+/// use based_rollup_driver::event_indexer::{
+///     common::{EventIndexerConfig, IndexerEvent},
+///     decoder::SolEventDecoder,
+///     event_indexer::EventIndexer,
+/// };
+///
+/// sol! {
+///     event BatchSubmitted(uint256 indexed batchIndex, bytes32 dataHash);
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let provider = alloy::providers::ProviderBuilder::new().on_http("http://localhost:8545".parse()?);
+/// let (tx, mut rx) = tokio::sync::mpsc::channel(1024);
+/// let mut indexer = EventIndexer::new(
+///     provider,
+///     EventIndexerConfig::default(),
+///     "0x5FbDB2315678afecb367f032d93F642f64180aa3".parse()?,
+///     BatchSubmitted::SIGNATURE_HASH,
+/// )?
+/// .with_decoder(SolEventDecoder::<BatchSubmitted>::new())
+/// .with_event_sender(tx);
+///
+/// tokio::spawn(async move { indexer.run(None).await });
+/// while let Some(event) = rx.recv().await {
+///     if let IndexerEvent::Log(decoded) = event {
+///         println!(
+///             "batch {} submitted at block {}",
+///             decoded.event.batchIndex, decoded.block_number
+///         );
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SolEventDecoder<E> {
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E> SolEventDecoder<E> {
+    pub fn new() -> Self {
+        Self {
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<E> Default for SolEventDecoder<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> Clone for SolEventDecoder<E> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<E: SolEvent + Send> EventDecoder for SolEventDecoder<E> {
+    type Event = DecodedEvent<E>;
+
+    fn decode(&self, log: &Log) -> Result<DecodedEvent<E>, DecodeError> {
+        if log.topic0() != Some(&E::SIGNATURE_HASH) {
+            return Err(DecodeError::UnexpectedSignature(log.topic0().copied()));
+        }
+
+        let decoded =
+            E::decode_log(&log.inner, true).map_err(|e| DecodeError::AbiError(e.to_string()))?;
+
+        Ok(DecodedEvent {
+            block_number: log.block_number.unwrap_or_default(),
+            block_timestamp: log.block_timestamp.unwrap_or_default(),
+            transaction_hash: log.transaction_hash.unwrap_or_default(),
+            log_index: log.log_index.unwrap_or_default(),
+            address: decoded.address,
+            event: decoded.data,
+        })
+    }
+}
+
+/// Routes each log to the decoder registered for its signature (topic0), so a multi-topic
+/// indexer can decode every event with its own decoder into one event type, e.g. an enum over
+/// the contract's events.
+///
+/// The router is itself an [`EventDecoder`], so with
+/// [`process_concurrency`](crate::event_indexer::common::EventIndexerConfig::process_concurrency)
+/// above 1 the routed decoders run on the indexer's pool of decode threads while logs are still
+/// emitted in (block, log index) order. A log whose signature has no route fails with
+/// [`DecodeError::UnexpectedSignature`].
+pub struct TopicRouter<E> {
+    routes: HashMap<B256, Box<dyn EventDecoder<Event = E>>>,
+}
+
+impl<E> TopicRouter<E> {
+    pub fn new() -> Self {
+        Self {
+            routes: HashMap::new(),
+        }
+    }
+
+    /// Decodes logs whose topic0 is `topic` with `decoder`, replacing any earlier route.
+    pub fn with_route<D>(mut self, topic: B256, decoder: D) -> Self
+    where
+        D: EventDecoder<Event = E> + 'static,
+    {
+        self.routes.insert(topic, Box::new(decoder));
+        self
+    }
+}
+
+impl<E> Default for TopicRouter<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> fmt::Debug for TopicRouter<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopicRouter")
+            .field("topics", &self.routes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<E: Send> EventDecoder for TopicRouter<E> {
+    type Event = E;
+
+    fn decode(&self, log: &Log) -> Result<E, DecodeError> {
+        let topic = log.topic0();
+        match topic.and_then(|topic| self.routes.get(topic)) {
+            Some(decoder) => decoder.decode(log),
+            None => Err(DecodeError::UnexpectedSignature(topic.copied())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        primitives::{Bytes, Log as PrimitiveLog, U256},
+        sol,
+    };
+
+    use super::*;
+
+    sol! {
+        #[derive(Debug)]
+        event BatchSubmitted(uint256 indexed batchIndex, bytes32 dataHash);
+    }
+
+    fn log(topics: Vec<B256>, data: Bytes) -> Log {
+        Log {
+            inner: PrimitiveLog::new_unchecked(Address::repeat_byte(0x11), topics, data),
+            block_number: Some(10),
+            log_index: Some(2),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decodes_sol_event() {
+        let data_hash = B256::repeat_byte(0xab);
+        let log = log(
+            vec![BatchSubmitted::SIGNATURE_HASH, B256::from(U256::from(7))],
+            Bytes::copy_from_slice(data_hash.as_slice()),
+        );
+
+        let decoded = SolEventDecoder::<BatchSubmitted>::new()
+            .decode(&log)
+            .unwrap();
+
+        assert_eq!(decoded.event.batchIndex, U256::from(7));
+        assert_eq!(decoded.event.dataHash, data_hash);
+        assert_eq!(decoded.address, Address::repeat_byte(0x11));
+        assert_eq!((decoded.block_number, decoded.log_index), (10, 2));
+    }
+
+    #[test]
+    fn rejects_other_signatures() {
+        let other = B256::repeat_byte(0x22);
+        let log = log(vec![other], Bytes::new());
+
+        let err = SolEventDecoder::<BatchSubmitted>::new()
+            .decode(&log)
+            .unwrap_err();
+
+        assert!(matches!(err, DecodeError::UnexpectedSignature(Some(topic)) if topic == other));
+    }
+}
@@ -1,49 +1,386 @@
-use std::time::Duration;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    marker::PhantomData,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 use alloy::{
     eips::BlockNumberOrTag,
     primitives::{Address, B256},
     providers::Provider,
-    rpc::types::{Filter, Log},
+    rpc::types::{BlockTransactionsKind, Filter, Header, Log},
+    transports::{BoxTransport, Transport, TransportError},
 };
 use futures::StreamExt;
-use tokio::time::sleep;
-use tracing::info;
+use lru::LruCache;
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    task,
+    time::sleep,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    common::{
+        rate_limiter::RateLimiter,
+        retry::{retry_with_backoff, RetryPolicy},
+    },
+    event_indexer::{
+        checkpoint::{Checkpoint, CheckpointStore},
+        common::{
+            EventIndexerConfig, EventIndexerError, IndexTarget, IndexerCommand, IndexerEvent,
+            IndexerProgress, RetryBudgetAction, SyncStatus, TailMode,
+        },
+        decoder::{DecodeError, EventDecoder, RawEventDecoder},
+        metrics::IndexerMetrics,
+        plan::{bisect_range, chunk_range, gap, is_reorg},
+    },
+};
+
+/// Upper bound on the delay before resubscribing after the block subscription drops.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Retries charged against the [`RetryBudget`](crate::event_indexer::common::RetryBudget)
+/// since `start`.
+#[derive(Debug)]
+struct RetryWindow {
+    retries: u32,
+    start: Instant,
+}
+
+impl RetryWindow {
+    fn new() -> Self {
+        Self {
+            retries: 0,
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Logs recently handed to `process_log`, by `(block_number, log_index)`, with their block hash.
+#[derive(Debug, Default)]
+struct SeenLogs {
+    logs: BTreeMap<(u64, u64), Option<B256>>,
+}
+
+impl SeenLogs {
+    /// Records `log`, returning whether it was not seen before. Logs more than `window` blocks
+    /// behind the newest one are forgotten.
+    fn insert(&mut self, log: &Log, window: u64) -> bool {
+        let (Some(number), Some(index)) = (log.block_number, log.log_index) else {
+            return true;
+        };
+        if self.logs.insert((number, index), log.block_hash) == Some(log.block_hash) {
+            return false;
+        }
 
-use crate::event_indexer::common::{EventIndexerConfig, EventIndexerError};
+        if let Some((&(newest, _), _)) = self.logs.last_key_value() {
+            self.logs = self.logs.split_off(&(newest.saturating_sub(window), 0));
+        }
+        true
+    }
+
+    /// Forgets every log above `block`, so a rewound range is emitted again when re-indexed.
+    fn forget_after(&mut self, block: u64) {
+        self.logs.split_off(&(block + 1, 0));
+    }
+}
 
+/// Indexes contract events from L1, first by backfilling historical blocks
+/// and then by following new blocks as they arrive.
+///
+/// The indexer accepts any `P: Provider<T>` over any transport `T`, so the
+/// provider can be wrapped in custom middleware (caching, logging, metrics)
+/// before being handed over. A middleware only needs to forward
+/// [`Provider::root`] to the inner provider and override the calls it wants
+/// to observe:
+///
+/// ```no_run
+/// use std::sync::{
+///     atomic::{AtomicUsize, Ordering},
+///     Arc,
+/// };
+///
+/// use alloy::{
+///     providers::{Provider, ProviderBuilder, RootProvider},
+///     rpc::types::{BlockTransactionsKind, Filter, Header, Log},
+///     transports::{Transport, TransportResult},
+/// };
+/// use based_rollup_driver::event_indexer::{
+///     common::EventIndexerConfig, event_indexer::EventIndexer,
+/// };
+///
+/// /// Counts every `eth_getLogs` request issued through the provider.
+/// struct CountingProvider<P> {
+///     inner: P,
+///     get_logs_calls: Arc<AtomicUsize>,
+/// }
+///
+/// #[async_trait::async_trait]
+/// impl<P, T> Provider<T> for CountingProvider<P>
+/// where
+///     P: Provider<T>,
+///     T: Transport + Clone,
+/// {
+///     fn root(&self) -> &RootProvider<T> {
+///         self.inner.root()
+///     }
+///
+///     async fn get_logs(&self, filter: &Filter) -> TransportResult<Vec<Log>> {
+///         self.get_logs_calls.fetch_add(1, Ordering::Relaxed);
+///         self.inner.get_logs(filter).await
+///     }
+/// }
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let inner = ProviderBuilder::new().on_http("http://localhost:8545".parse()?);
+/// let provider = CountingProvider {
+///     inner,
+///     get_logs_calls: Arc::new(AtomicUsize::new(0)),
+/// };
+/// let mut indexer = EventIndexer::new(
+///     provider,
+///     EventIndexerConfig::default(),
+///     "0x5FbDB2315678afecb367f032d93F642f64180aa3".parse()?,
+///     "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".parse()?,
+/// )?;
+/// indexer.index_events(0, 100).await?;
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Clone, Debug)]
-pub struct EventIndexer<P> {
+pub struct EventIndexer<P, T = BoxTransport, D = RawEventDecoder>
+where
+    D: EventDecoder,
+{
     provider: P,
     config: EventIndexerConfig,
-    contract_address: Address,
-    topic: B256,
-    last_indexed_block: u64,
-    is_indexing: bool,
+    /// Contracts to watch; a log emitted by any of them is indexed.
+    contract_addresses: Vec<Address>,
+    /// Event signatures to match; a log matching any of them is indexed. Empty matches every
+    /// log, including anonymous events, which have no signature in topic0.
+    topics: Vec<B256>,
+    progress: IndexerProgress,
+    /// Set once [`EventIndexer::run`] has positioned `last_indexed_block`; from then on every
+    /// indexed range must start right after it.
+    anchored: bool,
+    /// Set by [`IndexerCommand::Pause`]; new heads are followed but not indexed.
+    paused: bool,
+    /// `(number, hash)` of recent heads seen on the subscription, oldest first.
+    recent_heads: VecDeque<(u64, B256)>,
+    /// Timestamps of recently indexed blocks, by number.
+    block_timestamps: Option<Arc<Mutex<LruCache<u64, u64>>>>,
+    /// Retries charged against the retry budget; shared by clones of the indexer.
+    retry_window: Arc<Mutex<RetryWindow>>,
+    /// Logs already processed; shared by clones of the indexer.
+    seen_logs: Arc<Mutex<SeenLogs>>,
+    last_progress_log: Instant,
+    cancel: CancellationToken,
+    decoder: Arc<D>,
+    events: Option<Sender<IndexerEvent<D::Event>>>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    last_checkpointed_block: u64,
+    metrics: Option<IndexerMetrics>,
+    rate_limiter: Option<RateLimiter>,
+    _transport: PhantomData<T>,
 }
 
-impl<P: Provider + Clone + Send + Sync + 'static> EventIndexer<P> {
-    pub fn new(provider: P, config: EventIndexerConfig) -> Self {
-        Self {
+impl<P, T> EventIndexer<P, T>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    /// Creates an indexer for `topic` events emitted by `contract_address`. Both must be
+    /// non-zero.
+    pub fn new(
+        provider: P,
+        config: EventIndexerConfig,
+        contract_address: Address,
+        topic: B256,
+    ) -> Result<Self, EventIndexerError> {
+        Self::new_multi(provider, config, vec![contract_address], vec![topic])
+    }
+
+    /// Creates an indexer for any of `topics` emitted by any of `contract_addresses`, e.g. to
+    /// follow the inbox, outbox and bridge contracts at once. All values must be non-zero.
+    ///
+    /// With no `topics`, logs are matched by address and the configured
+    /// [`TopicFilter`](crate::event_indexer::common::TopicFilter) alone, so anonymous events are
+    /// indexed too.
+    pub fn new_multi(
+        provider: P,
+        config: EventIndexerConfig,
+        contract_addresses: Vec<Address>,
+        topics: Vec<B256>,
+    ) -> Result<Self, EventIndexerError> {
+        if contract_addresses.is_empty() || contract_addresses.iter().any(|a| a.is_zero()) {
+            return Err(EventIndexerError::InvalidConfig(
+                "contract addresses must be non-empty and non-zero".to_string(),
+            ));
+        }
+        if topics.iter().any(|topic| topic.is_zero()) {
+            return Err(EventIndexerError::InvalidConfig(
+                "event topics must be non-zero".to_string(),
+            ));
+        }
+
+        let rate_limiter = config
+            .max_requests_per_second
+            .map(|rate| RateLimiter::new(rate, 1));
+        let block_timestamps = NonZeroUsize::new(config.header_cache_size)
+            .map(|size| Arc::new(Mutex::new(LruCache::new(size))));
+
+        Ok(Self {
             provider,
             config,
-            contract_address: Address::ZERO,
-            topic: B256::ZERO,
-            last_indexed_block: 0,
-            is_indexing: false,
+            contract_addresses,
+            topics,
+            progress: IndexerProgress::default(),
+            anchored: false,
+            paused: false,
+            recent_heads: VecDeque::new(),
+            block_timestamps,
+            retry_window: Arc::new(Mutex::new(RetryWindow::new())),
+            seen_logs: Arc::new(Mutex::new(SeenLogs::default())),
+            last_progress_log: Instant::now(),
+            cancel: CancellationToken::new(),
+            decoder: Arc::new(RawEventDecoder),
+            events: None,
+            checkpoint_store: None,
+            last_checkpointed_block: 0,
+            metrics: None,
+            rate_limiter,
+            _transport: PhantomData,
+        })
+    }
+}
+
+impl<P, T, D> EventIndexer<P, T, D>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+    D: EventDecoder + 'static,
+    D::Event: 'static,
+{
+    /// Stops the backfill and the head-following loop once `cancel` fires, returning `Ok(())`
+    /// from [`EventIndexer::run`].
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Forwards every indexed log, and a notice for every reorg, to `events`. Sends are
+    /// awaited, so a slow consumer slows indexing down instead of losing events.
+    pub fn with_event_sender(mut self, events: Sender<IndexerEvent<D::Event>>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Emits logs decoded by `decoder` instead of raw ones. The event type changes with the
+    /// decoder, so any event sender set before this call is dropped; set it afterwards.
+    pub fn with_decoder<D2: EventDecoder + 'static>(self, decoder: D2) -> EventIndexer<P, T, D2> {
+        EventIndexer {
+            provider: self.provider,
+            config: self.config,
+            contract_addresses: self.contract_addresses,
+            topics: self.topics,
+            progress: self.progress,
+            anchored: self.anchored,
+            paused: self.paused,
+            recent_heads: self.recent_heads,
+            block_timestamps: self.block_timestamps,
+            retry_window: self.retry_window,
+            seen_logs: self.seen_logs,
+            last_progress_log: self.last_progress_log,
+            cancel: self.cancel,
+            decoder: Arc::new(decoder),
+            events: None,
+            checkpoint_store: self.checkpoint_store,
+            last_checkpointed_block: self.last_checkpointed_block,
+            metrics: self.metrics,
+            rate_limiter: self.rate_limiter,
+            _transport: PhantomData,
         }
     }
 
+    /// Resumes from `store` when [`EventIndexer::run`] is given no start block, and saves
+    /// progress to it every `checkpoint_interval` blocks and once more when cancelled.
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoint_store = Some(store);
+        self
+    }
+
+    /// Throttles provider requests through `limiter`, replacing the one built from
+    /// `max_requests_per_second`. Pass clones of one limiter to indexers sharing a provider.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Records indexing progress, fetch latency and retries to `metrics`.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, metrics: IndexerMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn last_indexed_block(&self) -> u64 {
+        self.progress.last_indexed_block()
+    }
+
+    pub fn is_indexing(&self) -> bool {
+        self.progress.is_indexing()
+    }
+
+    /// Returns a handle to read progress from other tasks while [`EventIndexer::run`] holds the
+    /// indexer.
+    pub fn progress(&self) -> IndexerProgress {
+        self.progress.clone()
+    }
+
+    /// Compares progress against the newest block the index target allows.
+    pub async fn sync_status(&self) -> Result<SyncStatus, EventIndexerError> {
+        let head = self.confirmed_block().await?;
+        Ok(self.progress.sync_status(head))
+    }
+
     pub async fn run(&mut self, start_block: Option<u64>) -> Result<(), EventIndexerError> {
-        // 1. Fetch the latest block number from the provider.
-        let latest_block = self.provider.get_block_number().await?;
-        info!("Latest block number: {}", latest_block);
+        self.run_inner(start_block, None).await
+    }
+
+    /// Like [`EventIndexer::run`], but also applies [`IndexerCommand`]s received on `control`
+    /// while following the head. Commands sent during the initial backfill wait until it is
+    /// done; once `control` closes the indexer keeps running as it was last configured.
+    pub async fn run_with_control(
+        &mut self,
+        start_block: Option<u64>,
+        control: Receiver<IndexerCommand>,
+    ) -> Result<(), EventIndexerError> {
+        self.run_inner(start_block, Some(control)).await
+    }
+
+    async fn run_inner(
+        &mut self,
+        start_block: Option<u64>,
+        mut control: Option<Receiver<IndexerCommand>>,
+    ) -> Result<(), EventIndexerError> {
+        // 1. Fetch the latest confirmed block number from the provider.
+        let latest_block = self.confirmed_block().await?;
+        info!("Latest confirmed block number: {}", latest_block);
 
-        let start_block = start_block.unwrap_or(self.last_indexed_block);
-        self.last_indexed_block = start_block;
+        let start_block = match start_block {
+            Some(block) => block,
+            None => self.resume_block().await?,
+        };
+        self.set_last_indexed_block(start_block.saturating_sub(1));
+        self.last_checkpointed_block = self.last_indexed_block();
+        self.anchored = true;
 
         // 2. Index historical events from start_block to latest_block.
-        if start_block < latest_block {
+        if start_block <= latest_block {
             info!(
                 "Starting indexing historical blocks: {} to {}",
                 start_block, latest_block
@@ -56,105 +393,2456 @@ impl<P: Provider + Clone + Send + Sync + 'static> EventIndexer<P> {
             );
         }
 
-        // 3. Subscribe to new blocks and index events in real-time.
-        self.subscribe_and_index().await?;
+        // 3. Follow the head and index events in real-time.
+        match self.config.tail_mode.clone() {
+            TailMode::Subscribe => self.subscribe_and_index(&mut control).await?,
+            TailMode::Poll { interval } => self.poll_and_index(interval, &mut control).await?,
+        }
 
+        if self.cancel.is_cancelled() {
+            // Save the exact stopping point, so a restart neither repeats nor skips blocks.
+            self.flush_checkpoint()?;
+        }
         Ok(())
     }
 
+    /// Indexes `[from_block, to_block]`, processing logs in block number, then log index order.
+    ///
+    /// The range is processed in `batch_size` batches, each fetched in spans of at most
+    /// `max_block_range` blocks.
     pub async fn index_events(
         &mut self,
         from_block: u64,
         to_block: u64,
     ) -> Result<(), EventIndexerError> {
-        self.is_indexing = true;
-        let _total_blocks = to_block - from_block + 1;
+        // The head can move backwards during a reorg; an inverted range has nothing to index.
+        if to_block < from_block {
+            info!(
+                "Nothing to index for inverted range {}-{}",
+                from_block, to_block
+            );
+            return Ok(());
+        }
+
+        self.progress.start_backfill(from_block, to_block);
+
+        if self.config.wide_query {
+            for (start, end) in chunk_range(from_block, to_block, self.config.max_block_range) {
+                if self.cancel.is_cancelled() {
+                    break;
+                }
+                // Try the whole window in one request first; sparse events over large ranges
+                // then only cost a single round-trip.
+                if let Some(logs) = self.try_wide_query(start, end).await {
+                    self.process_batch(&logs, start, end).await?;
+                } else {
+                    self.index_batches(start, end).await?;
+                }
+            }
+        } else {
+            self.index_batches(from_block, to_block).await?;
+        }
+
+        info!(
+            "Indexing complete up to block {}",
+            self.last_indexed_block()
+        );
+
+        self.progress.finish_backfill();
+        Ok(())
+    }
+
+    async fn subscribe_and_index(
+        &mut self,
+        control: &mut Option<Receiver<IndexerCommand>>,
+    ) -> Result<(), EventIndexerError> {
+        let cancel = self.cancel.clone();
+        let mut subscribed = false;
+        let mut failures = 0;
 
-        let mut current = from_block;
-        let mut all_logs = Vec::new();
+        loop {
+            if subscribed {
+                failures += 1;
+                let delay = self.retry_policy().delay(failures).min(MAX_RECONNECT_DELAY);
+                warn!(
+                    "Block subscription lost (attempt {}), resubscribing in {:?}",
+                    failures, delay
+                );
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        info!("Indexer cancelled at block {}", self.last_indexed_block());
+                        return Ok(());
+                    }
+                    _ = sleep(delay) => {}
+                }
 
-        while current <= to_block {
-            let end = (current + self.config.batch_size - 1).min(to_block);
+                // Catch up on blocks produced while disconnected before waiting for a new head.
+                match self.confirmed_block().await {
+                    Ok(head) if !self.paused && head > self.last_indexed_block() => {
+                        self.index_events(self.last_indexed_block() + 1, head)
+                            .await?;
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to fetch head while reconnecting: {}", e),
+                }
+            }
 
-            let logs = self.fetch_logs_range(current, end).await?;
+            let subscription = match self.provider.subscribe_blocks().await {
+                Ok(subscription) => subscription,
+                Err(e) if !subscribed => {
+                    let interval = Duration::from_millis(self.config.fallback_poll_interval_ms);
+                    warn!(
+                        "Block subscription unavailable, falling back to polling every {:?}: {}",
+                        interval, e
+                    );
+                    return self.poll_and_index(interval, control).await;
+                }
+                Err(e) => {
+                    warn!("Failed to resubscribe to blocks: {}", e);
+                    continue;
+                }
+            };
+            subscribed = true;
+            // Yield deserialization results so partial or null notifications (seen around reorgs
+            // on some providers) can be logged and skipped without ending the subscription.
+            let mut block_stream = subscription.into_result_stream();
 
-            info!("Fetched {} logs for blocks {}-{}", logs.len(), current, end);
-            all_logs.extend(logs);
+            info!("Subscribed to new blocks via WebSocket/IPC");
 
-            self.last_indexed_block = end;
-            current = end + 1;
-        }
+            loop {
+                let notification = tokio::select! {
+                    _ = cancel.cancelled() => {
+                        info!("Indexer cancelled at block {}", self.last_indexed_block());
+                        return Ok(());
+                    }
+                    Some(command) = next_command(control) => {
+                        self.handle_command(command).await?;
+                        continue;
+                    }
+                    notification = block_stream.next() => notification,
+                };
+                let Some(notification) = notification else {
+                    break;
+                };
 
-        info!("Indexing complete: {} total logs", all_logs.len());
+                failures = 0;
+                if !self.paused {
+                    self.handle_head_notification(notification).await?;
+                }
+            }
 
-        for log in all_logs {
-            self.process_log(&log).await?;
+            info!("Block subscription ended");
         }
-
-        self.is_indexing = false;
-        Ok(())
     }
 
-    async fn subscribe_and_index(&mut self) -> Result<(), EventIndexerError> {
-        let subscription = self.provider.subscribe_blocks().await?;
-        let mut block_stream = subscription.into_stream();
+    /// Indexes up to the confirmed block below a new head.
+    async fn handle_head_notification(
+        &mut self,
+        notification: Result<Header, serde_json::Error>,
+    ) -> Result<(), EventIndexerError> {
+        let block = match notification {
+            Ok(block) => block,
+            Err(e) => {
+                warn!("Skipping malformed block notification: {}", e);
+                return Ok(());
+            }
+        };
+        let block_number = block.number;
 
-        info!("Subscribed to new blocks via WebSocket/IPC");
+        let rewound = is_reorg(&self.recent_heads, block_number, block.parent_hash)
+            && self.handle_reorg().await?;
 
-        while let Some(block) = block_stream.next().await {
-            let block_number = block.number;
+        let from_block = self.last_indexed_block() + 1;
+        if block_number < from_block {
+            info!(
+                "Block {} is not past last indexed block {}, skipping",
+                block_number,
+                self.last_indexed_block()
+            );
+            return Ok(());
+        }
 
-            let from_block = self.last_indexed_block + 1;
-            let logs = self.fetch_logs_range(from_block, block_number).await?;
+        // Heads past the index target are only recorded; they get indexed once enough blocks
+        // are built on top of them or they are finalized.
+        let to_block = self.target_below(block_number).await?;
+        self.progress.set_head(to_block);
+        if to_block >= from_block {
+            if rewound && self.config.reorg_batch_size > 0 {
+                self.reindex_in_steps(from_block, to_block).await?;
+                self.record_head(block_number, block.hash);
+                return self.maybe_checkpoint();
+            }
+            if !self.backfill_gap(from_block).await? {
+                return Ok(());
+            }
+            let logs = self.fetch_logs_range(from_block, to_block).await?;
 
             if !logs.is_empty() {
                 info!(
                     "Blocks {}-{}: processing {} events",
                     from_block,
-                    block_number,
+                    to_block,
                     logs.len()
                 );
-                for log in &logs {
-                    self.process_log(log).await?;
+                self.process_logs(&logs).await?;
+            }
+
+            self.set_last_indexed_block(to_block);
+        }
+        self.record_head(block_number, block.hash);
+        self.maybe_checkpoint()
+    }
+
+    async fn handle_command(&mut self, command: IndexerCommand) -> Result<(), EventIndexerError> {
+        info!("Applying indexer command {:?}", command);
+        match command {
+            IndexerCommand::Pause => {
+                self.paused = true;
+            }
+            IndexerCommand::Resume => {
+                self.paused = false;
+                self.catch_up().await?;
+            }
+            IndexerCommand::SetAddress(address) => {
+                if address.is_zero() {
+                    warn!("Ignoring zero contract address");
+                } else {
+                    self.contract_addresses = vec![address];
+                }
+            }
+            IndexerCommand::RewindTo(block) => {
+                let depth = self.last_indexed_block().saturating_sub(block);
+                if depth == 0 {
+                    warn!(
+                        "Cannot rewind to block {}, last indexed block is {}",
+                        block,
+                        self.last_indexed_block()
+                    );
+                    return Ok(());
+                }
+
+                // Consumers handle the rewind like a reorg and drop what they stored after it.
+                self.set_last_indexed_block(block);
+                self.forget_seen_after(block);
+                self.recent_heads.retain(|(number, _)| *number <= block);
+                self.emit(IndexerEvent::reorg(depth, block)).await?;
+                self.maybe_checkpoint()?;
+                if !self.paused {
+                    self.catch_up().await?;
                 }
             }
+        }
+        Ok(())
+    }
+
+    /// Indexes from `last_indexed_block` up to the current confirmed block.
+    async fn catch_up(&mut self) -> Result<(), EventIndexerError> {
+        let head = self.confirmed_block().await?;
+        self.index_events(self.last_indexed_block() + 1, head).await
+    }
 
-            self.last_indexed_block = block_number;
+    fn record_head(&mut self, number: u64, hash: B256) {
+        self.recent_heads.push_back((number, hash));
+        while self.recent_heads.len() > self.config.max_reorg_depth {
+            self.recent_heads.pop_front();
         }
+    }
 
-        info!("Block subscription ended");
+    /// Re-indexes `[from, to]` after a reorg in `reorg_batch_size` steps, each processed before
+    /// the next is fetched.
+    async fn reindex_in_steps(&mut self, from: u64, to: u64) -> Result<(), EventIndexerError> {
+        for (start, end) in chunk_range(from, to, self.config.reorg_batch_size) {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            let logs = self.fetch_logs_range(start, end).await?;
+            info!(
+                "Re-indexing blocks {}-{} after reorg: {} events",
+                start,
+                end,
+                logs.len()
+            );
+            self.process_batch(&logs, start, end).await?;
+        }
         Ok(())
     }
 
-    async fn fetch_logs_range(&self, from: u64, to: u64) -> Result<Vec<Log>, EventIndexerError> {
-        let filter = Filter::new()
-            .from_block(BlockNumberOrTag::Number(from))
-            .to_block(BlockNumberOrTag::Number(to))
-            .address(self.contract_address)
-            .event_signature(self.topic);
+    /// Rewinds `last_indexed_block` to the newest recorded head that is still canonical and
+    /// notifies consumers, so the caller re-indexes forward from there. Returns whether
+    /// anything was rewound.
+    async fn handle_reorg(&mut self) -> Result<bool, EventIndexerError> {
+        let mut common_ancestor = None;
+
+        while let Some((number, hash)) = self.recent_heads.back().copied() {
+            self.throttle().await;
+            let canonical = self
+                .provider
+                .get_block_by_number(number.into(), BlockTransactionsKind::Hashes)
+                .await?;
+            if canonical.is_some_and(|block| block.header.hash == hash) {
+                common_ancestor = Some(number);
+                break;
+            }
+            self.recent_heads.pop_back();
+        }
+
+        // Every recorded head was replaced: the reorg is deeper than the window, so rewind by
+        // the whole window.
+        let common_ancestor = common_ancestor.unwrap_or_else(|| {
+            let fallback = self
+                .last_indexed_block()
+                .saturating_sub(self.config.max_reorg_depth as u64);
+            warn!(
+                "Reorg deeper than {} blocks, rewinding to block {}",
+                self.config.max_reorg_depth, fallback
+            );
+            fallback
+        });
+
+        let depth = self.last_indexed_block().saturating_sub(common_ancestor);
+        // With confirmations the replaced blocks may all lie above what was indexed.
+        if depth == 0 {
+            info!(
+                "Reorg above last indexed block {}, nothing to rewind",
+                self.last_indexed_block()
+            );
+            return Ok(false);
+        }
+        warn!(
+            "Reorg detected: rewinding {} blocks to common ancestor {}",
+            depth, common_ancestor
+        );
+
+        self.set_last_indexed_block(common_ancestor);
+        self.forget_seen_after(common_ancestor);
+        self.emit(IndexerEvent::reorg(depth, common_ancestor))
+            .await?;
+        Ok(true)
+    }
+
+    async fn throttle(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
+
+    /// Drops dedup entries above `block`: consumers delete that range on a reorg notice, so
+    /// logs in it that are still canonical must reach them again.
+    fn forget_seen_after(&self, block: u64) {
+        self.seen_logs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .forget_after(block);
+    }
+
+    fn set_last_indexed_block(&self, block: u64) {
+        self.progress.set_last_indexed_block(block);
+        if let Some(metrics) = &self.metrics {
+            metrics.set_last_indexed_block(block);
+        }
+    }
+
+    /// Returns the block after the saved checkpoint, or the current position if there is none.
+    /// A checkpointed block that was reorged out while the indexer was stopped is rewound by
+    /// `max_reorg_depth` blocks, and consumers are told to drop what they stored after that.
+    async fn resume_block(&self) -> Result<u64, EventIndexerError> {
+        let Some(store) = &self.checkpoint_store else {
+            return Ok(self.last_indexed_block());
+        };
+        let Some(checkpoint) = store.load()? else {
+            return Ok(self.last_indexed_block());
+        };
+        info!(
+            "Resuming from checkpoint at block {}",
+            checkpoint.block_number
+        );
+        if self.is_canonical(&checkpoint).await? {
+            return Ok(checkpoint.block_number + 1);
+        }
+
+        let ancestor = checkpoint
+            .block_number
+            .saturating_sub(self.config.max_reorg_depth as u64);
+        warn!(
+            "Checkpointed block {} was reorged out, rewinding to block {}",
+            checkpoint.block_number, ancestor
+        );
+        let depth = checkpoint.block_number - ancestor;
+        if depth > 0 {
+            self.emit(IndexerEvent::reorg(depth, ancestor)).await?;
+        }
+        Ok(ancestor + 1)
+    }
+
+    /// Whether the checkpointed block is still canonical; assumed so when its hash is unknown.
+    async fn is_canonical(&self, checkpoint: &Checkpoint) -> Result<bool, EventIndexerError> {
+        let Some(hash) = checkpoint.block_hash else {
+            return Ok(true);
+        };
+        self.throttle().await;
+        let canonical = self
+            .provider
+            .get_block_by_number(
+                checkpoint.block_number.into(),
+                BlockTransactionsKind::Hashes,
+            )
+            .await?;
+        Ok(canonical.is_some_and(|block| block.header.hash == hash))
+    }
+
+    /// Saves `last_indexed_block` once it moved `checkpoint_interval` blocks past the last save,
+    /// or immediately if a reorg rewound it behind the last save.
+    fn maybe_checkpoint(&mut self) -> Result<(), EventIndexerError> {
+        let block_number = self.last_indexed_block();
+        if block_number >= self.last_checkpointed_block
+            && block_number - self.last_checkpointed_block < self.config.checkpoint_interval
+        {
+            return Ok(());
+        }
+        self.flush_checkpoint()
+    }
+
+    /// Saves `last_indexed_block` unless it is already the last saved checkpoint.
+    fn flush_checkpoint(&mut self) -> Result<(), EventIndexerError> {
+        let Some(store) = &self.checkpoint_store else {
+            return Ok(());
+        };
+        let block_number = self.last_indexed_block();
+        if block_number == self.last_checkpointed_block {
+            return Ok(());
+        }
+
+        let block_hash = self
+            .recent_heads
+            .iter()
+            .rev()
+            .find(|(number, _)| *number == block_number)
+            .map(|(_, hash)| *hash);
+        store.save(&Checkpoint {
+            block_number,
+            block_hash,
+        })?;
+        self.last_checkpointed_block = block_number;
+        Ok(())
+    }
 
-        let mut retries = 0;
-        const MAX_RETRIES: u32 = 3;
+    /// Returns the newest block the index target allows.
+    async fn confirmed_block(&self) -> Result<u64, EventIndexerError> {
+        let latest_block = self.provider.get_block_number().await?;
+        let confirmed = self.target_below(latest_block).await?;
+        self.progress.set_head(confirmed);
+        Ok(confirmed)
+    }
+
+    /// Returns the newest block the index target allows while `head` is the chain head. The
+    /// `safe` and `finalized` tags are looked up again on every call.
+    async fn target_below(&self, head: u64) -> Result<u64, EventIndexerError> {
+        let tag = match self.config.index_target() {
+            IndexTarget::Latest => return Ok(head),
+            IndexTarget::Confirmations(confirmations) => {
+                return Ok(head.saturating_sub(confirmations))
+            }
+            IndexTarget::Safe => BlockNumberOrTag::Safe,
+            IndexTarget::Finalized => BlockNumberOrTag::Finalized,
+        };
+
+        self.throttle().await;
+        // Chains without finality yet (e.g. fresh devnets) have no such block: index nothing.
+        let target = self
+            .provider
+            .get_block_by_number(tag, BlockTransactionsKind::Hashes)
+            .await?
+            .map_or(0, |block| block.header.number);
+        Ok(target.min(head))
+    }
+
+    async fn poll_and_index(
+        &mut self,
+        interval: Duration,
+        control: &mut Option<Receiver<IndexerCommand>>,
+    ) -> Result<(), EventIndexerError> {
+        info!("Tailing new blocks by polling every {:?}", interval);
 
+        let cancel = self.cancel.clone();
         loop {
-            match self.provider.get_logs(&filter).await {
-                Ok(logs) => return Ok(logs),
-                Err(e) if retries < MAX_RETRIES => {
-                    retries += 1;
-                    info!("Retry {}/{}: {}", retries, MAX_RETRIES, e);
-                    sleep(Duration::from_millis(1000 * retries as u64)).await;
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    info!("Indexer cancelled at block {}", self.last_indexed_block());
+                    return Ok(());
+                }
+                Some(command) = next_command(control) => {
+                    self.handle_command(command).await?;
+                    continue;
                 }
-                Err(e) => return Err(e.into()),
+                _ = sleep(interval) => {}
+            }
+            if self.paused {
+                continue;
+            }
+
+            let latest_block = self.confirmed_block().await?;
+            if latest_block <= self.last_indexed_block() {
+                continue;
+            }
+
+            self.index_events(self.last_indexed_block() + 1, latest_block)
+                .await?;
+        }
+    }
+
+    /// Indexes `[from, to]` in `batch_size` chunks, processing each chunk before fetching the
+    /// next so memory stays bounded by a single batch.
+    async fn index_batches(&mut self, from: u64, to: u64) -> Result<(), EventIndexerError> {
+        for (start, end) in chunk_range(from, to, self.config.batch_size) {
+            if self.cancel.is_cancelled() {
+                break;
             }
+
+            let batch = self.fetch_logs_range(start, end).await?;
+
+            info!("Fetched {} logs for blocks {}-{}", batch.len(), start, end);
+            self.process_batch(&batch, start, end).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Processes `logs` for `[from, end]` in order, then marks everything up to `end` as indexed.
+    async fn process_batch(
+        &mut self,
+        logs: &[Log],
+        from: u64,
+        end: u64,
+    ) -> Result<(), EventIndexerError> {
+        if !self.backfill_gap(from).await? {
+            return Ok(());
         }
+        self.process_logs(logs).await?;
+        self.set_last_indexed_block(end);
+        self.log_progress();
+        self.maybe_checkpoint()
     }
 
-    async fn process_log(&self, log: &Log) -> Result<(), EventIndexerError> {
+    /// Logs how far the current backfill has come, at most once per `progress_log_interval_ms`.
+    fn log_progress(&mut self) {
+        let interval = Duration::from_millis(self.config.progress_log_interval_ms);
+        if interval.is_zero() || self.last_progress_log.elapsed() < interval {
+            return;
+        }
+        let Some(progress) = self.progress.backfill() else {
+            return;
+        };
+        self.last_progress_log = Instant::now();
+
+        let eta = progress.eta_secs.map_or("unknown".to_string(), |secs| {
+            format!("{:?}", Duration::from_secs(secs))
+        });
         info!(
-            "Event: block={}, tx={:?}",
-            log.block_number.unwrap_or_default(),
-            log.transaction_hash,
+            "Backfill {:.1}% ({}/{} blocks of {}-{}), {:.1} blocks/s, ETA {}",
+            progress.percent,
+            progress.indexed_blocks,
+            progress.to_block - progress.from_block + 1,
+            progress.from_block,
+            progress.to_block,
+            progress.blocks_per_sec,
+            eta
+        );
+    }
+
+    /// Indexes any blocks between `last_indexed_block` and `from` before a range starting at
+    /// `from` is processed, so `last_indexed_block` never moves past a block whose logs were not
+    /// fetched. Returns `false` if cancelled before the gap was closed.
+    async fn backfill_gap(&mut self, from: u64) -> Result<bool, EventIndexerError> {
+        if !self.anchored {
+            return Ok(true);
+        }
+        let Some((start, end)) = gap(self.last_indexed_block(), from) else {
+            return Ok(true);
+        };
+        warn!(
+            "Gap detected: blocks {}-{} were never indexed, backfilling",
+            start, end
         );
+
+        for (start, end) in chunk_range(start, end, self.config.batch_size) {
+            if self.cancel.is_cancelled() {
+                return Ok(false);
+            }
+            let logs = self.fetch_logs_range(start, end).await?;
+            self.process_logs(&logs).await?;
+            self.set_last_indexed_block(end);
+        }
+
+        Ok(true)
+    }
+
+    async fn try_wide_query(&self, from: u64, to: u64) -> Option<Vec<Log>> {
+        self.throttle().await;
+        let started = Instant::now();
+
+        match self.provider.get_logs(&self.filter(from, to)).await {
+            Ok(mut logs) => {
+                sort_logs(&mut logs);
+                info!(
+                    "Wide query for blocks {}-{} returned {} logs in {:?}",
+                    from,
+                    to,
+                    logs.len(),
+                    started.elapsed()
+                );
+                Some(logs)
+            }
+            Err(e) => {
+                info!(
+                    "Wide query for blocks {}-{} rejected after {:?}, falling back to batches of {}: {}",
+                    from,
+                    to,
+                    started.elapsed(),
+                    self.config.batch_size,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn filter(&self, from: u64, to: u64) -> Filter {
+        Filter::new()
+            .from_block(BlockNumberOrTag::Number(from))
+            .to_block(BlockNumberOrTag::Number(to))
+            .address(self.contract_addresses.clone())
+            .event_signature(self.topics.clone())
+            .topic1(self.config.topic_filter.topic1.clone())
+            .topic2(self.config.topic_filter.topic2.clone())
+            .topic3(self.config.topic_filter.topic3.clone())
+    }
+
+    /// Fetches `[from, to]` in spans of at most `max_block_range`, bisecting any span the
+    /// provider rejects as too large until every piece succeeds. Logs come back sorted by block
+    /// number, then log index, whatever order the provider returned them in.
+    async fn fetch_logs_range(
+        &mut self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<Log>, EventIndexerError> {
+        let mut spans = chunk_range(from, to, self.config.max_block_range);
+        // Pop from the back, so keep the lowest span last.
+        spans.reverse();
+
+        let mut logs = Vec::new();
+        while let Some((start, end)) = spans.pop() {
+            match self.get_logs_with_retry(start, end).await {
+                Ok(batch) => logs.extend(batch),
+                Err(e @ EventIndexerError::RangeTooLarge { .. }) => {
+                    let Some((lower, upper)) = bisect_range(start, end) else {
+                        return Err(e);
+                    };
+                    info!(
+                        "Range {}-{} too large, splitting at {}",
+                        start, end, lower.1
+                    );
+                    spans.push(upper);
+                    spans.push(lower);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        sort_logs(&mut logs);
+        Ok(logs)
+    }
+
+    /// Issues `eth_getLogs` for `[from, to]`, retrying transient failures. A range-too-large
+    /// rejection is returned as [`EventIndexerError::RangeTooLarge`] without retrying, so callers
+    /// can split the range.
+    async fn get_logs_with_retry(&self, from: u64, to: u64) -> Result<Vec<Log>, EventIndexerError> {
+        let filter = self.filter(from, to);
+        let mut attempts = 0;
+
+        retry_with_backoff(&self.retry_policy(), || {
+            attempts += 1;
+            let is_retry = attempts > 1;
+            let filter = &filter;
+            async move {
+                if is_retry {
+                    self.charge_retry_budget().await?;
+                    if let Some(metrics) = &self.metrics {
+                        metrics.record_retry();
+                    }
+                }
+
+                self.throttle().await;
+                let started = Instant::now();
+                let result = self.provider.get_logs(filter).await;
+                if let Some(metrics) = &self.metrics {
+                    metrics.observe_fetch_logs(started.elapsed());
+                }
+
+                result.map_err(|e| {
+                    if is_range_too_large(&e) {
+                        EventIndexerError::RangeTooLarge { from, to }
+                    } else {
+                        e.into()
+                    }
+                })
+            }
+        })
+        .await
+    }
+
+    /// Exponential backoff from `retry_delay_ms`, plus up to `retry_jitter_ms` of random jitter
+    /// so restarted indexers do not retry in lockstep.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.config.max_retries,
+            base_delay: Duration::from_millis(self.config.retry_delay_ms),
+            max_delay: Duration::MAX,
+            jitter: Duration::from_millis(self.config.retry_jitter_ms),
+        }
+    }
+
+    /// Charges one retry against the global retry budget, cooling down or halting once the
+    /// budget for the current window is spent.
+    async fn charge_retry_budget(&self) -> Result<(), EventIndexerError> {
+        let Some(budget) = &self.config.retry_budget else {
+            return Ok(());
+        };
+
+        let retries = {
+            let mut window = self.retry_window.lock().unwrap_or_else(|e| e.into_inner());
+            if window.start.elapsed() >= budget.window {
+                *window = RetryWindow::new();
+            }
+            window.retries += 1;
+            window.retries
+        };
+        if retries <= budget.max_retries {
+            return Ok(());
+        }
+
+        match budget.on_exhausted {
+            RetryBudgetAction::Cooldown(cooldown) => {
+                warn!(
+                    "Retry budget of {} per {:?} exhausted, cooling down for {:?}",
+                    budget.max_retries, budget.window, cooldown
+                );
+                sleep(cooldown).await;
+                *self.retry_window.lock().unwrap_or_else(|e| e.into_inner()) = RetryWindow::new();
+                Ok(())
+            }
+            RetryBudgetAction::Halt => {
+                error!(
+                    "Retry budget of {} per {:?} exhausted, halting indexer",
+                    budget.max_retries, budget.window
+                );
+                Err(EventIndexerError::RetryBudgetExhausted {
+                    retries,
+                    window: budget.window,
+                })
+            }
+        }
+    }
+
+    /// Decodes and emits `logs` in order. With `process_concurrency` above 1, up to that many
+    /// logs are decoded in parallel on blocking threads; emission order is unaffected.
+    async fn process_logs(&self, logs: &[Log]) -> Result<(), EventIndexerError> {
+        let logs = self.unseen(logs);
+        let logs = self.with_block_timestamps(&logs).await?;
+
+        if self.config.process_concurrency <= 1 {
+            for log in &logs {
+                let decoded = self.decoder.decode(log);
+                self.process_log(log, decoded).await?;
+            }
+            return Ok(());
+        }
+
+        let mut pending = VecDeque::with_capacity(self.config.process_concurrency);
+        let mut remaining = logs.iter();
+        loop {
+            while pending.len() < self.config.process_concurrency {
+                let Some(log) = remaining.next() else {
+                    break;
+                };
+                let decoder = self.decoder.clone();
+                let owned = log.clone();
+                pending.push_back((log, task::spawn_blocking(move || decoder.decode(&owned))));
+            }
+
+            let Some((log, handle)) = pending.pop_front() else {
+                break;
+            };
+            let decoded = handle
+                .await
+                .map_err(|e| EventIndexerError::Other(format!("decode task failed: {}", e)))?;
+            self.process_log(log, decoded).await?;
+        }
         Ok(())
     }
+
+    /// Copies the logs not processed before, skipping those within the dedup window.
+    fn unseen(&self, logs: &[Log]) -> Vec<Log> {
+        if self.config.dedup_window == 0 {
+            return logs.to_vec();
+        }
+
+        let mut seen = self.seen_logs.lock().unwrap_or_else(|e| e.into_inner());
+        logs.iter()
+            .filter(|log| {
+                let new = seen.insert(log, self.config.dedup_window);
+                if !new {
+                    info!(
+                        "Skipping already processed log {} in block {}",
+                        log.log_index.unwrap_or_default(),
+                        log.block_number.unwrap_or_default()
+                    );
+                }
+                new
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Copies `logs`, filling in each one's block timestamp if the provider left it out.
+    async fn with_block_timestamps(&self, logs: &[Log]) -> Result<Vec<Log>, EventIndexerError> {
+        let mut logs = logs.to_vec();
+        // Logs arrive in block order, so consecutive logs usually share a block.
+        let mut last: Option<(u64, u64)> = None;
+
+        for log in logs.iter_mut().filter(|log| log.block_timestamp.is_none()) {
+            let Some(number) = log.block_number else {
+                continue;
+            };
+            let timestamp = match last {
+                Some((cached, timestamp)) if cached == number => timestamp,
+                _ => self.block_timestamp(number).await?,
+            };
+            last = Some((number, timestamp));
+            log.block_timestamp = Some(timestamp);
+        }
+
+        Ok(logs)
+    }
+
+    async fn block_timestamp(&self, number: u64) -> Result<u64, EventIndexerError> {
+        if let Some(cache) = &self.block_timestamps {
+            if let Some(timestamp) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&number) {
+                return Ok(*timestamp);
+            }
+        }
+
+        self.throttle().await;
+        let timestamp = self
+            .provider
+            .get_block_by_number(number.into(), BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| EventIndexerError::Other(format!("block {} not found", number)))?
+            .header
+            .timestamp;
+
+        if let Some(cache) = &self.block_timestamps {
+            cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .put(number, timestamp);
+        }
+        Ok(timestamp)
+    }
+
+    async fn process_log(
+        &self,
+        log: &Log,
+        decoded: Result<D::Event, DecodeError>,
+    ) -> Result<(), EventIndexerError> {
+        info!(
+            "Event: block={}, tx={:?}, contract={}, topic={:?}",
+            log.block_number.unwrap_or_default(),
+            log.transaction_hash,
+            log.address(),
+            log.topic0(),
+        );
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record_log_indexed();
+        }
+
+        match decoded {
+            Ok(event) => self.emit(IndexerEvent::Log(event)).await,
+            Err(e) => {
+                warn!(
+                    "Skipping undecodable log in tx {:?}: {}",
+                    log.transaction_hash, e
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn emit(&self, event: IndexerEvent<D::Event>) -> Result<(), EventIndexerError> {
+        if let Some(events) = &self.events {
+            events
+                .send(event)
+                .await
+                .map_err(|_| EventIndexerError::ChannelClosed)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Receives the next command on `control`, or never resolves once it is absent or closed.
+async fn next_command(control: &mut Option<Receiver<IndexerCommand>>) -> Option<IndexerCommand> {
+    let Some(receiver) = control else {
+        return std::future::pending().await;
+    };
+    let command = receiver.recv().await;
+    if command.is_none() {
+        *control = None;
+    }
+    command
+}
+
+/// Sorts `logs` into chain order, as providers do not guarantee it within or across calls.
+fn sort_logs(logs: &mut [Log]) {
+    logs.sort_by_key(|log| {
+        (
+            log.block_number.unwrap_or_default(),
+            log.log_index.unwrap_or_default(),
+        )
+    });
+}
+
+/// Returns whether the provider rejected a `eth_getLogs` request because the block range or
+/// the number of results exceeded its limits (e.g. Infura's "query returned more than 10000
+/// results" or Alchemy's "Log response size exceeded").
+fn is_range_too_large(err: &TransportError) -> bool {
+    const LIMIT_EXCEEDED: i64 = -32005;
+    const PATTERNS: [&str; 6] = [
+        "query returned more than",
+        "response size exceeded",
+        "block range",
+        "range too large",
+        "too many results",
+        "limit exceeded",
+    ];
+
+    let Some(payload) = err.as_error_resp() else {
+        return false;
+    };
+    let message = payload.message.to_lowercase();
+
+    payload.code == LIMIT_EXCEEDED || PATTERNS.iter().any(|pattern| message.contains(pattern))
+}
+
+/// Fetches every log emitted by `address` matching any of `topics` in `[from_block, to_block]`
+/// as a one-shot query, reusing the indexer's batching and retries without subscribing or
+/// tracking progress. Logs are returned sorted by block number, then log index.
+pub async fn query_events<P, T>(
+    provider: P,
+    from_block: u64,
+    to_block: u64,
+    address: Address,
+    topics: Vec<B256>,
+) -> Result<Vec<Log>, EventIndexerError>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    let mut indexer = EventIndexer::new_multi(
+        provider,
+        EventIndexerConfig::default(),
+        vec![address],
+        topics,
+    )?;
+
+    let mut logs = Vec::new();
+    for (start, end) in chunk_range(from_block, to_block, indexer.config.batch_size) {
+        logs.extend(indexer.fetch_logs_range(start, end).await?);
+    }
+
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Future,
+        sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    };
+
+    use alloy::{
+        primitives::{Bytes, Log as PrimitiveLog, U256, U64},
+        providers::{ProviderBuilder, ProviderCall, RootProvider},
+        pubsub::{ConnectionHandle, PubSubConnect, PubSubFrontend, Subscription},
+        rpc::{
+            client::{NoParams, RpcClient},
+            types::{Block, BlockTransactions},
+        },
+        transports::{
+            http::{Client, Http},
+            TransportErrorKind, TransportResult,
+        },
+    };
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+    use tokio::sync::{mpsc, oneshot};
+
+    use super::*;
+    use crate::event_indexer::{
+        checkpoint::FileCheckpointStore,
+        common::{RetryBudget, TopicFilter},
+        decoder::TopicRouter,
+    };
+
+    const CONTRACT: Address = Address::repeat_byte(0x11);
+    const TOPIC: B256 = B256::repeat_byte(0x22);
+
+    /// An in-memory chain serving `eth_blockNumber`, `eth_getBlockByNumber` and `eth_getLogs`;
+    /// every other call goes to an unreachable node.
+    struct MockProvider {
+        root: RootProvider<Http<Client>>,
+        logs: Vec<Log>,
+        head: AtomicU64,
+        /// Block reported as `finalized`; the head when unset.
+        finalized: Mutex<Option<u64>>,
+        /// Widest span `eth_getLogs` serves; wider ones are rejected as too large.
+        max_range: Option<u64>,
+        /// Whether the `eth_getLogs` call with this 0-based index fails with a transient error.
+        fails: Box<dyn Fn(usize) -> bool + Send + Sync>,
+        /// `newHeads` notifications of each subscription to hand out, in order.
+        subscriptions: Mutex<VecDeque<Vec<Value>>>,
+        /// Keeps the clients behind handed out subscriptions alive.
+        connections: Mutex<Vec<RootProvider<PubSubFrontend>>>,
+        get_logs_calls: AtomicUsize,
+        /// `[from, to]` of every `eth_getLogs` call, in order.
+        get_logs_ranges: Mutex<Vec<(u64, u64)>>,
+        get_block_calls: AtomicUsize,
+    }
+
+    impl MockProvider {
+        fn new(logs: Vec<Log>) -> Self {
+            let head = logs
+                .iter()
+                .filter_map(|log| log.block_number)
+                .max()
+                .unwrap_or_default();
+            Self {
+                root: ProviderBuilder::new().on_http("http://127.0.0.1:1".parse().unwrap()),
+                logs,
+                head: AtomicU64::new(head),
+                finalized: Mutex::new(None),
+                max_range: None,
+                fails: Box::new(|_| false),
+                subscriptions: Mutex::new(VecDeque::new()),
+                connections: Mutex::new(Vec::new()),
+                get_logs_calls: AtomicUsize::new(0),
+                get_logs_ranges: Mutex::new(Vec::new()),
+                get_block_calls: AtomicUsize::new(0),
+            }
+        }
+
+        fn with_head(self, head: u64) -> Self {
+            self.head.store(head, Ordering::Relaxed);
+            self
+        }
+
+        fn with_failures(mut self, fails: impl Fn(usize) -> bool + Send + Sync + 'static) -> Self {
+            self.fails = Box::new(fails);
+            self
+        }
+
+        /// Hands out a block subscription delivering `heads`, then ending.
+        fn with_subscription(self, heads: Vec<Value>) -> Self {
+            self.subscriptions.lock().unwrap().push_back(heads);
+            self
+        }
+
+        fn with_max_range(mut self, max_range: u64) -> Self {
+            self.max_range = Some(max_range);
+            self
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Provider<Http<Client>> for MockProvider {
+        fn root(&self) -> &RootProvider<Http<Client>> {
+            &self.root
+        }
+
+        fn get_block_number(&self) -> ProviderCall<Http<Client>, NoParams, U64, u64> {
+            ProviderCall::ready(Ok(self.head.load(Ordering::Relaxed)))
+        }
+
+        async fn get_block_by_number(
+            &self,
+            number: BlockNumberOrTag,
+            _kind: BlockTransactionsKind,
+        ) -> TransportResult<Option<Block>> {
+            self.get_block_calls.fetch_add(1, Ordering::Relaxed);
+            let head = self.head.load(Ordering::Relaxed);
+            let number = match number {
+                BlockNumberOrTag::Number(number) => number,
+                BlockNumberOrTag::Finalized => self.finalized.lock().unwrap().unwrap_or(head),
+                _ => head,
+            };
+            Ok(Some(block(number)))
+        }
+
+        async fn subscribe_blocks(&self) -> TransportResult<Subscription<Header>> {
+            let Some(heads) = self.subscriptions.lock().unwrap().pop_front() else {
+                return self.root.subscribe_blocks().await;
+            };
+            let (start, started) = oneshot::channel();
+            let connection = ScriptedHeads {
+                script: Mutex::new(Some((heads, started))),
+            };
+            let client = RootProvider::new(RpcClient::connect_pubsub(connection).await?);
+            let subscription = client.subscribe_blocks().await?;
+            // Only deliver heads once the subscription exists, or they would be dropped.
+            let _ = start.send(());
+            self.connections.lock().unwrap().push(client);
+            Ok(subscription)
+        }
+
+        async fn get_logs(&self, filter: &Filter) -> TransportResult<Vec<Log>> {
+            let call = self.get_logs_calls.fetch_add(1, Ordering::Relaxed);
+            if (self.fails)(call) {
+                return Err(TransportErrorKind::custom_str("connection reset"));
+            }
+            let from = filter.get_from_block().unwrap_or_default();
+            let to = filter.get_to_block().unwrap_or(u64::MAX);
+            self.get_logs_ranges.lock().unwrap().push((from, to));
+            if self
+                .max_range
+                .is_some_and(|max_range| to - from >= max_range)
+            {
+                return Err(TransportError::ErrorResp(
+                    serde_json::from_value(serde_json::json!({
+                        "code": -32602,
+                        "message": "query returned more than 10000 results",
+                    }))
+                    .unwrap(),
+                ));
+            }
+            Ok(self
+                .logs
+                .iter()
+                .filter(|log| {
+                    (from..=to).contains(&log.block_number.unwrap_or_default())
+                        && filter.address.matches(&log.address())
+                        && filter.topics.iter().enumerate().all(|(position, topic)| {
+                            topic.is_empty()
+                                || log.topics().get(position).is_some_and(|t| topic.matches(t))
+                        })
+                })
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// Answers one `eth_subscribe` over an in-memory connection, delivers its heads once
+    /// started and then disconnects. Reconnecting fails, which ends the subscription.
+    struct ScriptedHeads {
+        script: Mutex<Option<(Vec<Value>, oneshot::Receiver<()>)>>,
+    }
+
+    impl PubSubConnect for ScriptedHeads {
+        fn is_local(&self) -> bool {
+            true
+        }
+
+        fn connect(&self) -> impl Future<Output = TransportResult<ConnectionHandle>> + Send {
+            let script = self.script.lock().unwrap().take();
+            async move {
+                let Some((heads, started)) = script else {
+                    return Err(TransportErrorKind::custom_str("connection closed"));
+                };
+                let (handle, mut interface) = ConnectionHandle::new();
+                tokio::spawn(async move {
+                    let Some(request) = interface.recv_from_frontend().await else {
+                        return;
+                    };
+                    let request: Value = serde_json::from_str(request.get()).unwrap();
+                    let id = "0x1";
+                    let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": id });
+                    let _ = interface.send_to_frontend(item(response));
+                    if started.await.is_err() {
+                        return;
+                    }
+                    for head in heads {
+                        let notification = json!({
+                            "jsonrpc": "2.0",
+                            "method": "eth_subscription",
+                            "params": { "subscription": id, "result": head },
+                        });
+                        let _ = interface.send_to_frontend(item(notification));
+                    }
+                });
+                Ok(handle)
+            }
+        }
+    }
+
+    /// Parses a message from the node; it borrows its keys, so it cannot come from a `Value`.
+    fn item<T: DeserializeOwned>(message: Value) -> T {
+        serde_json::from_str(&message.to_string()).unwrap()
+    }
+
+    fn block_hash(number: u64) -> B256 {
+        B256::from(U256::from(number + 1))
+    }
+
+    fn header(number: u64, hash: B256, parent_hash: B256) -> Header {
+        Header {
+            hash,
+            inner: alloy::consensus::Header {
+                number,
+                parent_hash,
+                timestamp: 1_700_000_000 + number * 12,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn block(number: u64) -> Block {
+        Block {
+            header: header(
+                number,
+                block_hash(number),
+                block_hash(number.saturating_sub(1)),
+            ),
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Hashes(Vec::new()),
+            withdrawals: None,
+        }
+    }
+
+    fn log(block_number: u64, log_index: u64) -> Log {
+        log_from(CONTRACT, vec![TOPIC], block_number, log_index)
+    }
+
+    fn log_from(address: Address, topics: Vec<B256>, block_number: u64, log_index: u64) -> Log {
+        Log {
+            inner: PrimitiveLog::new_unchecked(address, topics, Bytes::new()),
+            block_number: Some(block_number),
+            log_index: Some(log_index),
+            ..Default::default()
+        }
+    }
+
+    fn positions(logs: &[Log]) -> Vec<(u64, u64)> {
+        logs.iter()
+            .map(|log| (log.block_number.unwrap(), log.log_index.unwrap()))
+            .collect()
+    }
+
+    fn indexer(
+        provider: &MockProvider,
+        config: EventIndexerConfig,
+    ) -> (
+        EventIndexer<&MockProvider, Http<Client>>,
+        Receiver<IndexerEvent>,
+    ) {
+        let (sender, events) = mpsc::channel(1024);
+        let indexer = EventIndexer::new(provider, config, CONTRACT, TOPIC)
+            .unwrap()
+            .with_event_sender(sender);
+        (indexer, events)
+    }
+
+    /// Waits until `progress` reaches `block`, failing the test if it takes too long.
+    async fn wait_for(progress: &IndexerProgress, block: u64) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while progress.last_indexed_block() < block {
+                sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("indexer did not reach the block in time");
+    }
+
+    /// Takes the events emitted so far.
+    fn drain(events: &mut Receiver<IndexerEvent>) -> Vec<IndexerEvent> {
+        let mut drained = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            drained.push(event);
+        }
+        drained
+    }
+
+    /// Takes the events emitted so far, as `(block_number, log_index)` of each log.
+    fn emitted(events: &mut Receiver<IndexerEvent>) -> Vec<(u64, u64)> {
+        drain(events)
+            .into_iter()
+            .filter_map(|event| match event {
+                IndexerEvent::Log(event) => Some((event.block_number, event.log_index)),
+                IndexerEvent::Reorg { .. } => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn query_events_spans_batches() {
+        let provider = MockProvider::new(vec![
+            log(2_500, 1),
+            log(10, 0),
+            log(2_500, 0),
+            log(1_999, 3),
+            log(4_000, 0),
+        ]);
+
+        let logs = query_events(&provider, 0, 3_000, CONTRACT, Vec::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            positions(&logs),
+            vec![(10, 0), (1_999, 3), (2_500, 0), (2_500, 1)]
+        );
+        // One request per `batch_size` blocks.
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test]
+    async fn query_events_reaches_max_block() {
+        let provider = MockProvider::new(vec![log(u64::MAX, 0)]);
+
+        let logs = query_events(&provider, u64::MAX - 1, u64::MAX, CONTRACT, Vec::new())
+            .await
+            .unwrap();
+
+        assert_eq!(positions(&logs), vec![(u64::MAX, 0)]);
+    }
+
+    /// Counts the requests it forwards, like any middleware a caller might wrap a provider in.
+    struct CountingProvider<P> {
+        inner: P,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl<P: Provider<Http<Client>>> Provider<Http<Client>> for CountingProvider<P> {
+        fn root(&self) -> &RootProvider<Http<Client>> {
+            self.inner.root()
+        }
+
+        async fn get_block_by_number(
+            &self,
+            number: BlockNumberOrTag,
+            kind: BlockTransactionsKind,
+        ) -> TransportResult<Option<Block>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.inner.get_block_by_number(number, kind).await
+        }
+
+        async fn get_logs(&self, filter: &Filter) -> TransportResult<Vec<Log>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            self.inner.get_logs(filter).await
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_sees_calls() {
+        let chain = MockProvider::new(vec![log(10, 0), log(10, 1), log(1_500, 0)]);
+        let provider = CountingProvider {
+            inner: &chain,
+            calls: AtomicUsize::new(0),
+        };
+        let mut indexer =
+            EventIndexer::new(&provider, EventIndexerConfig::default(), CONTRACT, TOPIC).unwrap();
+
+        indexer.index_events(0, 2_500).await.unwrap();
+
+        // Three batches of logs, then one header per block with logs for its timestamp.
+        assert_eq!(chain.get_logs_calls.load(Ordering::Relaxed), 3);
+        assert_eq!(chain.get_block_calls.load(Ordering::Relaxed), 2);
+        assert_eq!(provider.calls.load(Ordering::Relaxed), 5);
+        assert_eq!(indexer.last_indexed_block(), 2_500);
+    }
+
+    #[tokio::test]
+    async fn wide_query_skips_batching() {
+        let provider = MockProvider::new(vec![log(5, 0), log(50, 0), log(95, 0)]);
+        let config = EventIndexerConfig {
+            batch_size: 10,
+            wide_query: true,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 100).await.unwrap();
+
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 1);
+        assert_eq!(emitted(&mut events), vec![(5, 0), (50, 0), (95, 0)]);
+        assert_eq!(indexer.last_indexed_block(), 100);
+    }
+
+    #[tokio::test]
+    async fn wide_query_falls_back_to_batches() {
+        let provider =
+            MockProvider::new(vec![log(5, 0), log(50, 0), log(95, 0)]).with_max_range(20);
+        let config = EventIndexerConfig {
+            batch_size: 10,
+            wide_query: true,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 100).await.unwrap();
+
+        // The rejected wide query, then blocks 0-100 in batches of 10.
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 12);
+        assert_eq!(emitted(&mut events), vec![(5, 0), (50, 0), (95, 0)]);
+    }
+
+    #[tokio::test]
+    async fn polling_advances_over_time() {
+        let provider = MockProvider::new(vec![log(5, 0), log(15, 0), log(25, 0)]).with_head(10);
+        let config = EventIndexerConfig {
+            tail_mode: TailMode::Poll {
+                interval: Duration::from_millis(10),
+            },
+            ..Default::default()
+        };
+        let (indexer, mut events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer.with_cancellation(cancel.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(0)), async {
+            wait_for(&progress, 10).await;
+            provider.head.store(20, Ordering::Relaxed);
+            wait_for(&progress, 20).await;
+            provider.head.store(30, Ordering::Relaxed);
+            wait_for(&progress, 30).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(emitted(&mut events), vec![(5, 0), (15, 0), (25, 0)]);
+    }
+
+    #[tokio::test]
+    async fn retry_budget_trips_across_calls() {
+        // Every request fails once and then succeeds, which no single call's retries notice.
+        let provider = MockProvider::new(vec![log(10, 0)]).with_failures(|call| call % 2 == 0);
+        let config = EventIndexerConfig {
+            max_retries: 10,
+            retry_delay_ms: 0,
+            retry_budget: Some(RetryBudget {
+                max_retries: 3,
+                window: Duration::from_secs(60),
+                on_exhausted: RetryBudgetAction::Halt,
+            }),
+            ..Default::default()
+        };
+        let (mut indexer, _events) = indexer(&provider, config);
+
+        let err = indexer.index_events(0, 9_999).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            EventIndexerError::RetryBudgetExhausted { retries: 4, .. }
+        ));
+        // The fourth batch is the first whose retry no longer fits in the budget.
+        assert_eq!(indexer.last_indexed_block(), 2_999);
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 7);
+    }
+
+    #[tokio::test]
+    async fn survives_malformed_block_notification() {
+        let head = |number| serde_json::to_value(block(number).header).unwrap();
+        let provider = MockProvider::new(vec![log(2, 0), log(3, 0)])
+            .with_head(1)
+            .with_subscription(vec![
+                Value::Null,
+                head(2),
+                json!({ "number": "0x3" }),
+                head(3),
+            ]);
+        let config = EventIndexerConfig {
+            retry_delay_ms: 10,
+            ..Default::default()
+        };
+        let (indexer, mut events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer.with_cancellation(cancel.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(1)), async {
+            wait_for(&progress, 3).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(emitted(&mut events), vec![(2, 0), (3, 0)]);
+    }
+
+    #[tokio::test]
+    async fn inverted_range_is_a_no_op() {
+        let provider = MockProvider::new(vec![log(7, 0)]);
+        let (mut indexer, _events) = indexer(&provider, EventIndexerConfig::default());
+        indexer.set_last_indexed_block(4);
+
+        indexer.index_events(10, 5).await.unwrap();
+
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 0);
+        assert_eq!(indexer.last_indexed_block(), 4);
+    }
+
+    #[tokio::test]
+    async fn head_moving_backwards_is_skipped() {
+        let provider = MockProvider::new(vec![log(7, 0)]);
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+        indexer.index_events(0, 10).await.unwrap();
+        assert_eq!(emitted(&mut events), vec![(7, 0)]);
+        let calls = provider.get_logs_calls.load(Ordering::Relaxed);
+
+        indexer
+            .handle_head_notification(Ok(block(8).header))
+            .await
+            .unwrap();
+
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), calls);
+        assert_eq!(indexer.last_indexed_block(), 10);
+        assert!(emitted(&mut events).is_empty());
+    }
+
+    #[test]
+    fn filter_carries_address_and_topic() {
+        let provider = MockProvider::new(Vec::new());
+        let (indexer, _events) = indexer(&provider, EventIndexerConfig::default());
+
+        let filter = indexer.filter(100, 200);
+
+        assert_eq!(filter.get_from_block(), Some(100));
+        assert_eq!(filter.get_to_block(), Some(200));
+        assert!(filter.address.matches(&CONTRACT));
+        assert!(!filter.address.matches(&Address::repeat_byte(0x33)));
+        assert!(filter.topics[0].matches(&TOPIC));
+        assert!(!filter.topics[0].matches(&B256::repeat_byte(0x44)));
+    }
+
+    #[tokio::test]
+    async fn indexes_several_addresses() {
+        let bridge = Address::repeat_byte(0x33);
+        let unwatched = Address::repeat_byte(0x44);
+        let provider = MockProvider::new(vec![
+            log_from(CONTRACT, vec![TOPIC], 1, 0),
+            log_from(unwatched, vec![TOPIC], 2, 0),
+            log_from(bridge, vec![TOPIC], 3, 0),
+        ]);
+        let (sender, mut events) = mpsc::channel(16);
+        let mut indexer = EventIndexer::new_multi(
+            &provider,
+            EventIndexerConfig::default(),
+            vec![CONTRACT, bridge],
+            vec![TOPIC],
+        )
+        .unwrap()
+        .with_event_sender(sender);
+
+        indexer.index_events(0, 3).await.unwrap();
+
+        let addresses: Vec<Address> = drain(&mut events)
+            .into_iter()
+            .filter_map(|event| match event {
+                IndexerEvent::Log(event) => Some(event.address),
+                IndexerEvent::Reorg { .. } => None,
+            })
+            .collect();
+        assert_eq!(addresses, vec![CONTRACT, bridge]);
+    }
+
+    #[tokio::test]
+    async fn events_reach_the_channel() {
+        let provider = MockProvider::new((1..=5).map(|block| log(block, 0)).collect());
+        // A single slot, so the indexer has to wait for the consumer rather than drop events.
+        let (sender, mut events) = mpsc::channel(1);
+        let mut indexer =
+            EventIndexer::new(&provider, EventIndexerConfig::default(), CONTRACT, TOPIC)
+                .unwrap()
+                .with_event_sender(sender);
+
+        let (result, received) = tokio::join!(indexer.index_events(0, 5), async {
+            let mut received = Vec::new();
+            while received.len() < 5 {
+                sleep(Duration::from_millis(5)).await;
+                match events.recv().await {
+                    Some(IndexerEvent::Log(event)) => received.push(event),
+                    other => panic!("unexpected event {:?}", other),
+                }
+            }
+            received
+        });
+
+        result.unwrap();
+        let blocks: Vec<u64> = received.iter().map(|event| event.block_number).collect();
+        assert_eq!(blocks, vec![1, 2, 3, 4, 5]);
+        assert_eq!(received[0].address, CONTRACT);
+        assert_eq!(received[0].topics, vec![TOPIC]);
+        assert_eq!(received[0].block_timestamp, 1_700_000_012);
+    }
+
+    #[tokio::test]
+    async fn oversized_ranges_are_split() {
+        let provider = MockProvider::new(vec![
+            log(3, 0),
+            log(40, 1),
+            log(40, 0),
+            log(41, 0),
+            log(99, 0),
+        ])
+        .with_max_range(10);
+        let config = EventIndexerConfig {
+            batch_size: 100,
+            max_block_range: 100,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 99).await.unwrap();
+
+        assert_eq!(
+            emitted(&mut events),
+            vec![(3, 0), (40, 0), (40, 1), (41, 0), (99, 0)]
+        );
+        assert_eq!(indexer.last_indexed_block(), 99);
+    }
+
+    #[tokio::test]
+    async fn retries_as_configured() {
+        let provider = MockProvider::new(vec![log(10, 0)]).with_failures(|_| true);
+        let config = EventIndexerConfig {
+            max_retries: 3,
+            retry_delay_ms: 0,
+            ..Default::default()
+        };
+        let (mut indexer, _events) = indexer(&provider, config);
+
+        assert!(indexer.index_events(0, 10).await.is_err());
+        // The first attempt and `max_retries` retries.
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 4);
+
+        indexer.config.retry_delay_ms = 100;
+        let policy = indexer.retry_policy();
+        let delays: Vec<Duration> = (1..=3).map(|retry| policy.delay(retry)).collect();
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_millis(100),
+                Duration::from_millis(200),
+                Duration::from_millis(400)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn competing_chain_rewinds_to_common_ancestor() {
+        let provider = MockProvider::new(vec![log(2, 0), log(4, 0), log(6, 0)]);
+        let config = EventIndexerConfig {
+            confirmations: 0,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        // Blocks 4 and 5 arrive from a fork that the canonical chain later replaces.
+        let fork_hash = |number: u64| B256::repeat_byte(0xf0 | number as u8);
+        let heads = [
+            header(1, block_hash(1), block_hash(0)),
+            header(2, block_hash(2), block_hash(1)),
+            header(3, block_hash(3), block_hash(2)),
+            header(4, fork_hash(4), block_hash(3)),
+            header(5, fork_hash(5), fork_hash(4)),
+        ];
+        for head in heads {
+            indexer.handle_head_notification(Ok(head)).await.unwrap();
+        }
+        assert_eq!(indexer.last_indexed_block(), 5);
+        drain(&mut events);
+
+        indexer
+            .handle_head_notification(Ok(header(6, block_hash(6), block_hash(5))))
+            .await
+            .unwrap();
+
+        let events = drain(&mut events);
+        assert_eq!(events.first(), Some(&IndexerEvent::reorg(2, 3)));
+        assert!(matches!(
+            events.last(),
+            Some(IndexerEvent::Log(event)) if event.block_number == 6
+        ));
+        assert_eq!(indexer.last_indexed_block(), 6);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_saved_checkpoint() {
+        let path =
+            std::env::temp_dir().join(format!("based-rollup-resume-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store: Arc<dyn CheckpointStore> = Arc::new(FileCheckpointStore::new(&path));
+        let provider = MockProvider::new(vec![log(5, 0), log(15, 0)]).with_head(10);
+        let config = EventIndexerConfig {
+            tail_mode: TailMode::Poll {
+                interval: Duration::from_millis(10),
+            },
+            checkpoint_interval: 100,
+            ..Default::default()
+        };
+
+        let run_until = |block: u64| {
+            let (indexer, events) = indexer(&provider, config.clone());
+            let cancel = CancellationToken::new();
+            let mut indexer = indexer
+                .with_cancellation(cancel.clone())
+                .with_checkpoint_store(store.clone());
+            let progress = indexer.progress();
+            async move {
+                let (result, _) = tokio::join!(indexer.run(None), async {
+                    wait_for(&progress, block).await;
+                    cancel.cancel();
+                });
+                result.unwrap();
+                events
+            }
+        };
+
+        let mut events = run_until(10).await;
+        assert_eq!(emitted(&mut events), vec![(5, 0)]);
+        assert_eq!(store.load().unwrap().unwrap().block_number, 10);
+
+        provider.head.store(20, Ordering::Relaxed);
+        let mut events = run_until(20).await;
+        assert_eq!(emitted(&mut events), vec![(15, 0)]);
+        assert_eq!(store.load().unwrap().unwrap().block_number, 20);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn batches_are_processed_as_they_arrive() {
+        let provider = MockProvider::new((0..10).map(|batch| log(batch * 10 + 5, 0)).collect());
+        let config = EventIndexerConfig {
+            batch_size: 10,
+            ..Default::default()
+        };
+        let (sender, mut events) = mpsc::channel(1);
+        let mut indexer = EventIndexer::new(&provider, config, CONTRACT, TOPIC)
+            .unwrap()
+            .with_event_sender(sender);
+
+        let (result, calls_per_event) = tokio::join!(indexer.index_events(0, 99), async {
+            let mut calls_per_event = Vec::new();
+            while calls_per_event.len() < 10 {
+                sleep(Duration::from_millis(5)).await;
+                events.recv().await.unwrap();
+                calls_per_event.push(provider.get_logs_calls.load(Ordering::Relaxed));
+            }
+            calls_per_event
+        });
+
+        result.unwrap();
+        // Each event arrives while the indexer is at most one batch ahead of the consumer.
+        for (received, calls) in calls_per_event.into_iter().enumerate() {
+            assert!(
+                calls <= received + 2,
+                "event {} arrived after {} batches",
+                received,
+                calls
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn confirmations_hold_back_the_tip() {
+        let provider = MockProvider::new(vec![log(95, 0), log(96, 0), log(100, 0)]);
+        let config = EventIndexerConfig {
+            confirmations: 5,
+            tail_mode: TailMode::Poll {
+                interval: Duration::from_millis(10),
+            },
+            ..Default::default()
+        };
+        let (indexer, mut events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer.with_cancellation(cancel.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(0)), async {
+            wait_for(&progress, 95).await;
+            // A few more polls of the same head.
+            sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(progress.last_indexed_block(), 95);
+        assert_eq!(emitted(&mut events), vec![(95, 0)]);
+    }
+
+    #[tokio::test]
+    async fn polls_when_subscription_is_unavailable() {
+        // No scripted subscription, so `eth_subscribe` fails like it does over HTTP.
+        let provider = MockProvider::new(vec![log(5, 0), log(15, 0)]).with_head(10);
+        let config = EventIndexerConfig {
+            tail_mode: TailMode::Subscribe,
+            fallback_poll_interval_ms: 10,
+            ..Default::default()
+        };
+        let (indexer, mut events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer.with_cancellation(cancel.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(0)), async {
+            wait_for(&progress, 10).await;
+            provider.head.store(20, Ordering::Relaxed);
+            wait_for(&progress, 20).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(emitted(&mut events), vec![(5, 0), (15, 0)]);
+    }
+
+    #[tokio::test]
+    async fn resubscribes_after_stream_ends() {
+        let head = |number| serde_json::to_value(block(number).header).unwrap();
+        let provider = MockProvider::new(vec![log(2, 0), log(3, 0), log(5, 0)])
+            .with_head(1)
+            .with_subscription(vec![head(2)])
+            .with_subscription(vec![head(5)]);
+        let config = EventIndexerConfig {
+            retry_delay_ms: 10,
+            ..Default::default()
+        };
+        let (indexer, mut events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer.with_cancellation(cancel.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(1)), async {
+            wait_for(&progress, 2).await;
+            // Block 3 is produced while the first subscription is gone.
+            provider.head.store(3, Ordering::Relaxed);
+            wait_for(&progress, 5).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(emitted(&mut events), vec![(2, 0), (3, 0), (5, 0)]);
+    }
+
+    #[tokio::test]
+    async fn sync_status_reports_lag_behind_head() {
+        let provider = MockProvider::new(Vec::new()).with_head(100);
+        let (mut indexer, _events) = indexer(&provider, EventIndexerConfig::default());
+
+        indexer.index_events(0, 60).await.unwrap();
+        let status = indexer.sync_status().await.unwrap();
+        assert_eq!(
+            status,
+            SyncStatus {
+                last_indexed_block: 60,
+                head: 100,
+                lag: 40,
+            }
+        );
+        assert!(!status.is_synced());
+
+        indexer.index_events(61, 100).await.unwrap();
+        let status = indexer.sync_status().await.unwrap();
+        assert_eq!(status.lag, 0);
+        assert!(status.is_synced());
+        assert_eq!(indexer.progress().status(), status);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn metrics_track_indexing() {
+        use prometheus::{Encoder, Registry, TextEncoder};
+
+        let registry = Registry::new();
+        let metrics = IndexerMetrics::register(&registry).unwrap();
+        let provider =
+            MockProvider::new(vec![log(5, 0), log(7, 0)]).with_failures(|call| call == 0);
+        let config = EventIndexerConfig {
+            retry_delay_ms: 0,
+            ..Default::default()
+        };
+        let (indexer, _events) = indexer(&provider, config);
+        let mut indexer = indexer.with_metrics(metrics);
+
+        indexer.index_events(0, 10).await.unwrap();
+
+        let mut scraped = Vec::new();
+        TextEncoder::new()
+            .encode(&registry.gather(), &mut scraped)
+            .unwrap();
+        let scraped = String::from_utf8(scraped).unwrap();
+        assert!(scraped.contains("logs_indexed_total 2"), "{}", scraped);
+        assert!(scraped.contains("last_indexed_block 10"), "{}", scraped);
+        assert!(scraped.contains("rpc_retries_total 1"), "{}", scraped);
+        assert!(
+            scraped.contains("fetch_logs_duration_seconds_count 2"),
+            "{}",
+            scraped
+        );
+    }
+
+    /// Takes a while to decode each log, like an expensive ABI decode.
+    #[derive(Clone)]
+    struct SlowDecoder;
+
+    impl EventDecoder for SlowDecoder {
+        type Event = u64;
+
+        fn decode(&self, log: &Log) -> Result<u64, DecodeError> {
+            std::thread::sleep(Duration::from_millis(25));
+            Ok(log.block_number.unwrap_or_default())
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_processing_is_faster_and_ordered() {
+        let provider = MockProvider::new((1..=8).map(|block| log(block, 0)).collect());
+
+        let mut elapsed = Vec::new();
+        for process_concurrency in [1, 4] {
+            let config = EventIndexerConfig {
+                process_concurrency,
+                ..Default::default()
+            };
+            let (sender, mut events) = mpsc::channel(16);
+            let mut indexer = EventIndexer::new(&provider, config, CONTRACT, TOPIC)
+                .unwrap()
+                .with_decoder(SlowDecoder)
+                .with_event_sender(sender);
+
+            let started = Instant::now();
+            indexer.index_events(0, 8).await.unwrap();
+            elapsed.push(started.elapsed());
+
+            let mut blocks = Vec::new();
+            while let Ok(IndexerEvent::Log(block)) = events.try_recv() {
+                blocks.push(block);
+            }
+            assert_eq!(blocks, (1..=8).collect::<Vec<_>>());
+        }
+
+        assert!(
+            elapsed[1] * 2 < elapsed[0],
+            "sequential {:?}, concurrent {:?}",
+            elapsed[0],
+            elapsed[1]
+        );
+    }
+
+    /// Takes a while to decode each log of one topic, tagging it with `self.0`.
+    struct SlowTopicDecoder(u8);
+
+    impl EventDecoder for SlowTopicDecoder {
+        type Event = (u8, u64, u64);
+
+        fn decode(&self, log: &Log) -> Result<(u8, u64, u64), DecodeError> {
+            std::thread::sleep(Duration::from_millis(25));
+            Ok((
+                self.0,
+                log.block_number.unwrap_or_default(),
+                log.log_index.unwrap_or_default(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn routed_decoders_share_the_pool_and_keep_each_topic_ordered() {
+        let other = B256::repeat_byte(0x33);
+        let provider = MockProvider::new(
+            (1..=4)
+                .flat_map(|block| {
+                    [
+                        log_from(CONTRACT, vec![TOPIC], block, 0),
+                        log_from(CONTRACT, vec![other], block, 1),
+                    ]
+                })
+                .collect(),
+        );
+
+        let mut elapsed = Vec::new();
+        for process_concurrency in [1, 4] {
+            let config = EventIndexerConfig {
+                process_concurrency,
+                ..Default::default()
+            };
+            let router = TopicRouter::new()
+                .with_route(TOPIC, SlowTopicDecoder(0))
+                .with_route(other, SlowTopicDecoder(1));
+            let (sender, mut events) = mpsc::channel(16);
+            let mut indexer =
+                EventIndexer::new_multi(&provider, config, vec![CONTRACT], vec![TOPIC, other])
+                    .unwrap()
+                    .with_decoder(router)
+                    .with_event_sender(sender);
+
+            let started = Instant::now();
+            indexer.index_events(0, 4).await.unwrap();
+            elapsed.push(started.elapsed());
+
+            let mut streams = [Vec::new(), Vec::new()];
+            while let Ok(IndexerEvent::Log((topic, block, log_index))) = events.try_recv() {
+                streams[topic as usize].push((block, log_index));
+            }
+            assert_eq!(
+                streams[0],
+                (1..=4).map(|block| (block, 0)).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                streams[1],
+                (1..=4).map(|block| (block, 1)).collect::<Vec<_>>()
+            );
+        }
+
+        assert!(
+            elapsed[1] * 2 < elapsed[0],
+            "one worker {:?}, four workers {:?}",
+            elapsed[0],
+            elapsed[1]
+        );
+    }
+
+    #[tokio::test]
+    async fn gap_is_backfilled_before_advancing() {
+        let provider = MockProvider::new(vec![log(15, 0), log(25, 0)]);
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+        indexer.anchored = true;
+        indexer.set_last_indexed_block(10);
+
+        // Blocks 21-30 arrive while 11-20 were never fetched.
+        let logs = indexer.fetch_logs_range(21, 30).await.unwrap();
+        indexer.process_batch(&logs, 21, 30).await.unwrap();
+
+        assert_eq!(emitted(&mut events), vec![(15, 0), (25, 0)]);
+        assert_eq!(provider.get_logs_calls.load(Ordering::Relaxed), 2);
+        assert_eq!(indexer.last_indexed_block(), 30);
+    }
+
+    #[tokio::test]
+    async fn fetches_each_header_once() {
+        let provider =
+            MockProvider::new(vec![log(3, 0), log(3, 1), log(3, 2), log(7, 0), log(7, 1)]);
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+
+        indexer.index_events(0, 10).await.unwrap();
+        assert_eq!(provider.get_block_calls.load(Ordering::Relaxed), 2);
+
+        // Re-indexing the blocks after a rewind is served from the header cache.
+        indexer
+            .handle_command(IndexerCommand::RewindTo(0))
+            .await
+            .unwrap();
+        assert_eq!(provider.get_block_calls.load(Ordering::Relaxed), 2);
+
+        let timestamps: Vec<u64> = drain(&mut events)
+            .into_iter()
+            .filter_map(|event| match event {
+                IndexerEvent::Log(event) => Some(event.block_timestamp),
+                IndexerEvent::Reorg { .. } => None,
+            })
+            .collect();
+        assert_eq!(timestamps.len(), 10);
+        assert!(timestamps[..3].iter().all(|t| *t == 1_700_000_036));
+        assert!(timestamps[3..5].iter().all(|t| *t == 1_700_000_084));
+    }
+
+    #[tokio::test]
+    async fn shuffled_logs_are_emitted_in_chain_order() {
+        let provider = MockProvider::new(vec![
+            log(7, 2),
+            log(3, 1),
+            log(7, 0),
+            log(12, 0),
+            log(3, 0),
+            log(7, 1),
+        ]);
+        let config = EventIndexerConfig {
+            batch_size: 100,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 12).await.unwrap();
+
+        assert_eq!(
+            emitted(&mut events),
+            vec![(3, 0), (3, 1), (7, 0), (7, 1), (7, 2), (12, 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn control_commands_change_indexer_state() {
+        let other = Address::repeat_byte(0x55);
+        let provider = MockProvider::new(vec![
+            log(5, 0),
+            log(15, 0),
+            log_from(other, vec![TOPIC], 25, 0),
+            log(25, 1),
+        ])
+        .with_head(10);
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+        indexer.index_events(0, 10).await.unwrap();
+        assert_eq!(emitted(&mut events), vec![(5, 0)]);
+
+        indexer.handle_command(IndexerCommand::Pause).await.unwrap();
+        assert!(indexer.paused);
+
+        provider.head.store(20, Ordering::Relaxed);
+        indexer
+            .handle_command(IndexerCommand::Resume)
+            .await
+            .unwrap();
+        assert!(!indexer.paused);
+        assert_eq!(indexer.last_indexed_block(), 20);
+        assert_eq!(emitted(&mut events), vec![(15, 0)]);
+
+        indexer
+            .handle_command(IndexerCommand::SetAddress(other))
+            .await
+            .unwrap();
+        provider.head.store(30, Ordering::Relaxed);
+        indexer
+            .handle_command(IndexerCommand::Resume)
+            .await
+            .unwrap();
+        assert_eq!(emitted(&mut events), vec![(25, 0)]);
+
+        indexer
+            .handle_command(IndexerCommand::SetAddress(CONTRACT))
+            .await
+            .unwrap();
+        indexer
+            .handle_command(IndexerCommand::RewindTo(12))
+            .await
+            .unwrap();
+        assert_eq!(indexer.last_indexed_block(), 30);
+        let drained = drain(&mut events);
+        assert!(matches!(
+            drained.first(),
+            Some(IndexerEvent::Reorg {
+                depth: 18,
+                common_ancestor: 12,
+                ..
+            })
+        ));
+        // Consumers dropped blocks 13-30 on the notice, so the canonical logs in them return.
+        let replayed: Vec<_> = drained
+            .iter()
+            .filter_map(|event| match event {
+                IndexerEvent::Log(event) => Some((event.block_number, event.log_index)),
+                IndexerEvent::Reorg { .. } => None,
+            })
+            .collect();
+        assert_eq!(replayed, vec![(15, 0), (25, 1)]);
+    }
+
+    #[tokio::test]
+    async fn finalized_block_caps_the_indexed_range() {
+        let provider = MockProvider::new(vec![log(5, 0), log(15, 0), log(25, 0)]).with_head(30);
+        *provider.finalized.lock().unwrap() = Some(20);
+        let config = EventIndexerConfig {
+            index_target: Some(IndexTarget::Finalized),
+            tail_mode: TailMode::Poll {
+                interval: Duration::from_millis(10),
+            },
+            ..Default::default()
+        };
+        let (indexer, mut events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer.with_cancellation(cancel.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(0)), async {
+            wait_for(&progress, 20).await;
+            // Several polls later, the head is still out of reach.
+            sleep(Duration::from_millis(100)).await;
+            assert_eq!(progress.last_indexed_block(), 20);
+            *provider.finalized.lock().unwrap() = Some(30);
+            wait_for(&progress, 30).await;
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(emitted(&mut events), vec![(5, 0), (15, 0), (25, 0)]);
+    }
+
+    #[tokio::test]
+    async fn topic1_filter_excludes_other_senders() {
+        let sender = B256::left_padding_from(Address::repeat_byte(0xaa).as_slice());
+        let other = B256::left_padding_from(Address::repeat_byte(0xbb).as_slice());
+        let provider = MockProvider::new(vec![
+            log_from(CONTRACT, vec![TOPIC, sender], 1, 0),
+            log_from(CONTRACT, vec![TOPIC, other], 2, 0),
+            log_from(CONTRACT, vec![TOPIC], 3, 0),
+            log_from(CONTRACT, vec![TOPIC, sender, other], 4, 0),
+        ]);
+        let config = EventIndexerConfig {
+            topic_filter: TopicFilter {
+                topic1: vec![sender],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 4).await.unwrap();
+
+        assert_eq!(emitted(&mut events), vec![(1, 0), (4, 0)]);
+    }
+
+    #[tokio::test]
+    async fn overlapping_reindex_processes_each_log_once() {
+        let provider = MockProvider::new(vec![log(3, 0), log(8, 0), log(8, 1), log(14, 0)]);
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+
+        indexer.index_events(0, 10).await.unwrap();
+        // Re-indexing from an earlier block, as after a reconnect.
+        indexer.index_events(5, 15).await.unwrap();
+
+        assert_eq!(emitted(&mut events), vec![(3, 0), (8, 0), (8, 1), (14, 0)]);
+    }
+
+    #[tokio::test]
+    async fn logs_beyond_the_dedup_window_are_processed_again() {
+        let provider = MockProvider::new(vec![log(3, 0), log(50, 0)]);
+        let config = EventIndexerConfig {
+            dedup_window: 10,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 50).await.unwrap();
+        indexer.index_events(0, 50).await.unwrap();
+
+        // Block 3 fell out of the window once block 50 was seen.
+        assert_eq!(emitted(&mut events), vec![(3, 0), (50, 0), (3, 0)]);
+    }
+
+    #[tokio::test]
+    async fn fetches_stay_within_max_block_range() {
+        let provider = MockProvider::new(vec![log(10, 0), log(45, 0), log(95, 0)]);
+        let config = EventIndexerConfig {
+            batch_size: 100,
+            max_block_range: 30,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        indexer.index_events(0, 99).await.unwrap();
+
+        assert_eq!(
+            *provider.get_logs_ranges.lock().unwrap(),
+            vec![(0, 29), (30, 59), (60, 89), (90, 99)]
+        );
+        assert_eq!(emitted(&mut events), vec![(10, 0), (45, 0), (95, 0)]);
+        assert_eq!(indexer.last_indexed_block(), 99);
+    }
+
+    /// Keeps every saved checkpoint in memory.
+    #[derive(Debug, Default)]
+    struct RecordingStore {
+        saved: Mutex<Vec<u64>>,
+    }
+
+    impl CheckpointStore for RecordingStore {
+        fn load(&self) -> Result<Option<Checkpoint>, EventIndexerError> {
+            Ok(None)
+        }
+
+        fn save(&self, checkpoint: &Checkpoint) -> Result<(), EventIndexerError> {
+            self.saved.lock().unwrap().push(checkpoint.block_number);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn cancellation_flushes_the_checkpoint() {
+        let store = Arc::new(RecordingStore::default());
+        let provider = MockProvider::new(vec![log(5, 0)]).with_head(10);
+        let config = EventIndexerConfig {
+            tail_mode: TailMode::Poll {
+                interval: Duration::from_millis(10),
+            },
+            checkpoint_interval: 1_000,
+            ..Default::default()
+        };
+        let (indexer, _events) = indexer(&provider, config);
+        let cancel = CancellationToken::new();
+        let mut indexer = indexer
+            .with_cancellation(cancel.clone())
+            .with_checkpoint_store(store.clone());
+        let progress = indexer.progress();
+
+        let (result, _) = tokio::join!(indexer.run(Some(0)), async {
+            wait_for(&progress, 10).await;
+            provider.head.store(13, Ordering::Relaxed);
+            wait_for(&progress, 13).await;
+            // Nothing was saved yet, well within the checkpoint interval.
+            assert!(store.saved.lock().unwrap().is_empty());
+            cancel.cancel();
+        });
+
+        result.unwrap();
+        assert_eq!(*store.saved.lock().unwrap(), vec![13]);
+    }
+
+    #[tokio::test]
+    async fn indexes_anonymous_events_without_topic0_filter() {
+        let indexed_arg = B256::repeat_byte(0x77);
+        let provider = MockProvider::new(vec![
+            log_from(CONTRACT, Vec::new(), 1, 0),
+            log_from(CONTRACT, vec![indexed_arg], 2, 0),
+            log(3, 0),
+            log_from(Address::repeat_byte(0x44), Vec::new(), 4, 0),
+        ]);
+        let (sender, mut events) = mpsc::channel(16);
+        let mut anonymous = EventIndexer::new_multi(
+            &provider,
+            EventIndexerConfig::default(),
+            vec![CONTRACT],
+            Vec::new(),
+        )
+        .unwrap()
+        .with_event_sender(sender);
+
+        anonymous.index_events(0, 4).await.unwrap();
+        assert_eq!(emitted(&mut events), vec![(1, 0), (2, 0), (3, 0)]);
+
+        // With a topic0 filter, the anonymous events are left out.
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+        indexer.index_events(0, 4).await.unwrap();
+        assert_eq!(emitted(&mut events), vec![(3, 0)]);
+    }
+
+    #[tokio::test]
+    async fn deep_reorg_is_reemitted_in_configured_steps() {
+        let provider = MockProvider::new((2..=9).map(|number| log(number, 0)).collect());
+        let config = EventIndexerConfig {
+            reorg_batch_size: 2,
+            ..Default::default()
+        };
+        let (mut indexer, mut events) = indexer(&provider, config);
+
+        // Blocks 4-8 arrive from a fork that the canonical chain later replaces.
+        let fork_hash = |number: u64| B256::repeat_byte(0xf0 | number as u8);
+        for number in 1..=8 {
+            let head = match number {
+                1..=3 => header(number, block_hash(number), block_hash(number - 1)),
+                4 => header(4, fork_hash(4), block_hash(3)),
+                _ => header(number, fork_hash(number), fork_hash(number - 1)),
+            };
+            indexer.handle_head_notification(Ok(head)).await.unwrap();
+        }
+        drain(&mut events);
+        provider.get_logs_ranges.lock().unwrap().clear();
+
+        indexer
+            .handle_head_notification(Ok(header(9, block_hash(9), block_hash(8))))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *provider.get_logs_ranges.lock().unwrap(),
+            vec![(4, 5), (6, 7), (8, 9)]
+        );
+        let events = drain(&mut events);
+        assert_eq!(events.first(), Some(&IndexerEvent::reorg(5, 3)));
+        let reemitted: Vec<u64> = events[1..]
+            .iter()
+            .map(|event| match event {
+                IndexerEvent::Log(event) => event.block_number,
+                IndexerEvent::Reorg { .. } => panic!("second reorg notice"),
+            })
+            .collect();
+        assert_eq!(reemitted, vec![4, 5, 6, 7, 8, 9]);
+        assert_eq!(indexer.last_indexed_block(), 9);
+    }
+
+    #[tokio::test]
+    async fn reorg_invalidates_stale_entries_before_reemitting_them() {
+        let provider = MockProvider::new(vec![log(2, 0), log(4, 0), log(5, 0), log(6, 0)]);
+        let (mut indexer, mut events) = indexer(&provider, EventIndexerConfig::default());
+
+        // Blocks 4 and 5 arrive from a fork that the canonical chain later replaces.
+        let fork_hash = |number: u64| B256::repeat_byte(0xf0 | number as u8);
+        let heads = [
+            header(1, block_hash(1), block_hash(0)),
+            header(2, block_hash(2), block_hash(1)),
+            header(3, block_hash(3), block_hash(2)),
+            header(4, fork_hash(4), block_hash(3)),
+            header(5, fork_hash(5), fork_hash(4)),
+        ];
+        for head in heads {
+            indexer.handle_head_notification(Ok(head)).await.unwrap();
+        }
+        let stale: Vec<u64> = emitted(&mut events)
+            .into_iter()
+            .map(|(block_number, _)| block_number)
+            .filter(|block_number| *block_number > 3)
+            .collect();
+        assert_eq!(stale, vec![4, 5]);
+
+        indexer
+            .handle_head_notification(Ok(header(6, block_hash(6), block_hash(5))))
+            .await
+            .unwrap();
+
+        let events = drain(&mut events);
+        let Some(IndexerEvent::Reorg { invalidated, .. }) = events.first() else {
+            panic!("expected a reorg notice first, got {:?}", events);
+        };
+        assert_eq!(*invalidated, 4..=5);
+        assert!(stale
+            .iter()
+            .all(|block_number| invalidated.contains(block_number)));
+        let reemitted: Vec<u64> = events[1..]
+            .iter()
+            .filter_map(|event| match event {
+                IndexerEvent::Log(event) => Some(event.block_number),
+                IndexerEvent::Reorg { .. } => None,
+            })
+            .collect();
+        assert_eq!(reemitted, vec![4, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn resume_rewinds_a_reorged_out_checkpoint() {
+        let path =
+            std::env::temp_dir().join(format!("based-rollup-reorged-{}.json", std::process::id()));
+        let store: Arc<dyn CheckpointStore> = Arc::new(FileCheckpointStore::new(&path));
+        let provider = MockProvider::new(vec![log(5, 0), log(15, 0)]).with_head(20);
+        let config = EventIndexerConfig {
+            max_reorg_depth: 8,
+            ..Default::default()
+        };
+        let resume = |block_hash| {
+            store
+                .save(&Checkpoint {
+                    block_number: 16,
+                    block_hash,
+                })
+                .unwrap();
+            let (indexer, events) = indexer(&provider, config.clone());
+            (indexer.with_checkpoint_store(store.clone()), events)
+        };
+
+        let (canonical, mut events) = resume(Some(block_hash(16)));
+        assert_eq!(canonical.resume_block().await.unwrap(), 17);
+        assert!(drain(&mut events).is_empty());
+
+        let (reorged, mut events) = resume(Some(B256::repeat_byte(0xee)));
+        assert_eq!(reorged.resume_block().await.unwrap(), 9);
+        assert_eq!(drain(&mut events), vec![IndexerEvent::reorg(8, 8)]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
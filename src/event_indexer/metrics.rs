@@ -0,0 +1,70 @@
+#[cfg(feature = "metrics")]
+use prometheus::{Histogram, HistogramOpts, IntCounter, IntGauge, Registry};
+
+/// Prometheus metrics recorded by the event indexer.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug)]
+pub struct IndexerMetrics {
+    logs_indexed: IntCounter,
+    last_indexed_block: IntGauge,
+    fetch_logs_duration: Histogram,
+    rpc_retries: IntCounter,
+}
+
+#[cfg(feature = "metrics")]
+impl IndexerMetrics {
+    /// Creates the indexer metrics and registers them with `registry`.
+    pub fn register(registry: &Registry) -> Result<Self, prometheus::Error> {
+        let metrics = Self {
+            logs_indexed: IntCounter::new("logs_indexed_total", "Logs processed by the indexer")?,
+            last_indexed_block: IntGauge::new(
+                "last_indexed_block",
+                "Last block fully processed by the indexer",
+            )?,
+            fetch_logs_duration: Histogram::with_opts(HistogramOpts::new(
+                "fetch_logs_duration_seconds",
+                "Duration of eth_getLogs requests",
+            ))?,
+            rpc_retries: IntCounter::new("rpc_retries_total", "eth_getLogs requests retried")?,
+        };
+
+        registry.register(Box::new(metrics.logs_indexed.clone()))?;
+        registry.register(Box::new(metrics.last_indexed_block.clone()))?;
+        registry.register(Box::new(metrics.fetch_logs_duration.clone()))?;
+        registry.register(Box::new(metrics.rpc_retries.clone()))?;
+        Ok(metrics)
+    }
+
+    pub(crate) fn record_log_indexed(&self) {
+        self.logs_indexed.inc();
+    }
+
+    pub(crate) fn set_last_indexed_block(&self, block: u64) {
+        self.last_indexed_block.set(block as i64);
+    }
+
+    pub(crate) fn observe_fetch_logs(&self, duration: std::time::Duration) {
+        self.fetch_logs_duration.observe(duration.as_secs_f64());
+    }
+
+    pub(crate) fn record_retry(&self) {
+        self.rpc_retries.inc();
+    }
+}
+
+/// Stand-in for the Prometheus metrics when the `metrics` feature is disabled; it cannot be
+/// constructed, so the indexer never records anything.
+#[cfg(not(feature = "metrics"))]
+#[derive(Clone, Debug)]
+pub enum IndexerMetrics {}
+
+#[cfg(not(feature = "metrics"))]
+impl IndexerMetrics {
+    pub(crate) fn record_log_indexed(&self) {}
+
+    pub(crate) fn set_last_indexed_block(&self, _block: u64) {}
+
+    pub(crate) fn observe_fetch_logs(&self, _duration: std::time::Duration) {}
+
+    pub(crate) fn record_retry(&self) {}
+}
@@ -1,2 +1,12 @@
+pub mod checkpoint;
 pub mod common;
+pub mod decoder;
+#[allow(clippy::module_inception)]
 pub mod event_indexer;
+pub mod metrics;
+pub mod plan;
+pub mod sink;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_sink;
+#[cfg(feature = "ws-server")]
+pub mod ws_server;
@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+
+use alloy::primitives::B256;
+
+/// An inclusive block range `[start, end]`.
+pub type BlockRange = (u64, u64);
+
+/// Splits `[from, to]` into consecutive ranges of at most `size` blocks, lowest first. An
+/// inverted range yields none.
+pub fn chunk_range(from: u64, to: u64, size: u64) -> Vec<BlockRange> {
+    let mut chunks = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(size.max(1) - 1).min(to);
+        chunks.push((start, end));
+        if end == to {
+            break;
+        }
+        start = end + 1;
+    }
+    chunks
+}
+
+/// Halves `[start, end]` after a provider rejected it as too large, or returns `None` if it is a
+/// single block and cannot be split further.
+pub fn bisect_range(start: u64, end: u64) -> Option<(BlockRange, BlockRange)> {
+    if start >= end {
+        return None;
+    }
+    let mid = start + (end - start) / 2;
+    Some(((start, mid), (mid + 1, end)))
+}
+
+/// Returns the blocks a range starting at `from` would skip past `last_indexed`, if any.
+pub fn gap(last_indexed: u64, from: u64) -> Option<BlockRange> {
+    let next = last_indexed + 1;
+    (from > next).then(|| (next, from - 1))
+}
+
+/// Returns whether a head at `number` with `parent_hash` does not extend the chain recorded in
+/// `recent_heads`, given as `(number, hash)` pairs, oldest first.
+pub fn is_reorg(recent_heads: &VecDeque<(u64, B256)>, number: u64, parent_hash: B256) -> bool {
+    recent_heads
+        .iter()
+        .rev()
+        .find(|(recorded, _)| *recorded + 1 == number)
+        .is_some_and(|(_, hash)| *hash != parent_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_exact_multiple() {
+        assert_eq!(chunk_range(0, 29, 10), vec![(0, 9), (10, 19), (20, 29)]);
+    }
+
+    #[test]
+    fn chunks_remainder() {
+        assert_eq!(chunk_range(5, 27, 10), vec![(5, 14), (15, 24), (25, 27)]);
+    }
+
+    #[test]
+    fn chunks_single_block() {
+        assert_eq!(chunk_range(7, 7, 10), vec![(7, 7)]);
+        assert_eq!(chunk_range(7, 9, 1), vec![(7, 7), (8, 8), (9, 9)]);
+    }
+
+    #[test]
+    fn chunks_edge_cases() {
+        assert!(chunk_range(10, 9, 5).is_empty());
+        // A zero size is treated as one block rather than looping forever.
+        assert_eq!(chunk_range(1, 2, 0), vec![(1, 1), (2, 2)]);
+        assert_eq!(
+            chunk_range(u64::MAX - 1, u64::MAX, 10),
+            vec![(u64::MAX - 1, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn bisects_ranges() {
+        assert_eq!(bisect_range(0, 9), Some(((0, 4), (5, 9))));
+        assert_eq!(bisect_range(4, 5), Some(((4, 4), (5, 5))));
+        assert_eq!(bisect_range(3, 3), None);
+    }
+
+    #[test]
+    fn finds_gaps() {
+        assert_eq!(gap(10, 11), None);
+        assert_eq!(gap(10, 5), None);
+        assert_eq!(gap(10, 15), Some((11, 14)));
+    }
+
+    #[test]
+    fn detects_reorgs() {
+        let recent_heads = VecDeque::from([(1, B256::repeat_byte(1)), (2, B256::repeat_byte(2))]);
+
+        assert!(!is_reorg(&recent_heads, 3, B256::repeat_byte(2)));
+        assert!(is_reorg(&recent_heads, 3, B256::repeat_byte(9)));
+        assert!(is_reorg(&recent_heads, 2, B256::repeat_byte(9)));
+        // Without a recorded parent there is nothing to compare against.
+        assert!(!is_reorg(&recent_heads, 5, B256::repeat_byte(9)));
+        assert!(!is_reorg(&VecDeque::new(), 3, B256::ZERO));
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{self, Stdout, Write};
+
+use tokio::sync::mpsc::{error::TryRecvError, Receiver};
+use tracing::info;
+
+use crate::event_indexer::common::{EventIndexerError, IndexerEvent};
+
+/// A consumer of the indexer's event channel that hands the events to a store or another
+/// process.
+pub trait EventSink: Send {
+    /// Applies `events` in order.
+    fn write(&mut self, events: &[IndexerEvent]) -> Result<(), EventIndexerError>;
+
+    /// Most events passed to a single [`write`](EventSink::write) by [`run`](EventSink::run).
+    fn batch_size(&self) -> usize {
+        1
+    }
+
+    /// Writes events from `events` until the indexer drops its sender, batching whatever is
+    /// already queued up to [`batch_size`](EventSink::batch_size).
+    ///
+    /// Blocks the calling thread; run it with `tokio::task::spawn_blocking`.
+    fn run(mut self, mut events: Receiver<IndexerEvent>) -> Result<(), EventIndexerError>
+    where
+        Self: Sized,
+    {
+        while let Some(event) = events.blocking_recv() {
+            let mut batch = vec![event];
+            while batch.len() < self.batch_size() {
+                match events.try_recv() {
+                    Ok(event) => batch.push(event),
+                    Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+                }
+            }
+            self.write(&batch)?;
+        }
+
+        info!("Event channel closed, sink stopped");
+        Ok(())
+    }
+}
+
+/// Writes each event as one JSON object per line (NDJSON), flushing after every line so a
+/// reader such as `jq` sees events as they are indexed.
+///
+/// Writes to its own stdout handle; logs must go elsewhere (the CLI sends them to stderr) for
+/// the stream to stay parseable.
+#[derive(Debug)]
+pub struct StdoutJsonSink<W = Stdout> {
+    out: W,
+}
+
+impl StdoutJsonSink {
+    pub fn new() -> Self {
+        Self { out: io::stdout() }
+    }
+}
+
+impl Default for StdoutJsonSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write + Send> StdoutJsonSink<W> {
+    /// Writes to `out` instead of stdout.
+    pub fn with_writer(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write + Send> EventSink for StdoutJsonSink<W> {
+    fn write(&mut self, events: &[IndexerEvent]) -> Result<(), EventIndexerError> {
+        for event in events {
+            serde_json::to_writer(&mut self.out, event)
+                .map_err(|e| EventIndexerError::SinkError(e.to_string()))?;
+            writeln!(self.out).map_err(write_error)?;
+            self.out.flush().map_err(write_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_error(err: io::Error) -> EventIndexerError {
+    EventIndexerError::SinkError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, Bytes, B256};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::event_indexer::common::IndexedEvent;
+
+    fn event(block_number: u64) -> IndexerEvent {
+        IndexerEvent::Log(IndexedEvent {
+            block_number,
+            block_timestamp: 1_700_000_000 + block_number * 12,
+            transaction_hash: B256::repeat_byte(block_number as u8),
+            log_index: 0,
+            address: Address::repeat_byte(0x11),
+            topics: vec![B256::repeat_byte(0x22)],
+            data: Bytes::from_static(b"batch"),
+        })
+    }
+
+    #[test]
+    fn writes_one_json_object_per_line() {
+        let sent = vec![event(5), IndexerEvent::reorg(1, 4), event(5)];
+        let (sender, events) = mpsc::channel(8);
+        for event in &sent {
+            sender.try_send(event.clone()).unwrap();
+        }
+        drop(sender);
+
+        let mut output = Vec::new();
+        StdoutJsonSink::with_writer(&mut output)
+            .run(events)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with('\n'));
+        let received: Vec<IndexerEvent> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(received, sent);
+    }
+}
@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use alloy::primitives::{Address, Bytes, B256};
+use rusqlite::{params, Connection, Row};
+
+use crate::event_indexer::{
+    common::{EventIndexerError, IndexedEvent, IndexerEvent},
+    sink::EventSink,
+};
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS events (
+    block_number INTEGER NOT NULL,
+    log_index INTEGER NOT NULL,
+    block_timestamp INTEGER NOT NULL,
+    transaction_hash BLOB NOT NULL,
+    address BLOB NOT NULL,
+    topics BLOB NOT NULL,
+    data BLOB NOT NULL,
+    UNIQUE (block_number, log_index)
+)";
+
+/// Persists the indexer's events into an `events` table.
+///
+/// Rows are unique on `(block_number, log_index)`, so re-indexing a range leaves the table
+/// unchanged, and a reorg deletes every row in its invalidated blocks. Topics are stored as
+/// their 32-byte words concatenated.
+#[derive(Debug)]
+pub struct SqliteEventSink {
+    conn: Connection,
+    batch_size: usize,
+}
+
+impl SqliteEventSink {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, EventIndexerError> {
+        Self::with_connection(Connection::open(path).map_err(sink_error)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, EventIndexerError> {
+        Self::with_connection(Connection::open_in_memory().map_err(sink_error)?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, EventIndexerError> {
+        conn.execute(SCHEMA, []).map_err(sink_error)?;
+        Ok(Self {
+            conn,
+            batch_size: 256,
+        })
+    }
+
+    /// Most events written per transaction by [`EventSink::run`].
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Reads back the events stored for blocks `[from, to]`, in block and log order.
+    pub fn events(&self, from: u64, to: u64) -> Result<Vec<IndexedEvent>, EventIndexerError> {
+        let mut select = self
+            .conn
+            .prepare_cached(
+                "SELECT block_number, log_index, block_timestamp, transaction_hash, address, \
+                 topics, data FROM events WHERE block_number BETWEEN ?1 AND ?2 \
+                 ORDER BY block_number, log_index",
+            )
+            .map_err(sink_error)?;
+
+        let rows = select
+            .query_map(params![from, to], read_event)
+            .map_err(sink_error)?;
+        rows.collect::<Result<_, _>>().map_err(sink_error)
+    }
+}
+
+impl EventSink for SqliteEventSink {
+    /// Applies `events` in order within a single transaction.
+    fn write(&mut self, events: &[IndexerEvent]) -> Result<(), EventIndexerError> {
+        let tx = self.conn.transaction().map_err(sink_error)?;
+        {
+            let mut insert = tx
+                .prepare_cached(
+                    "INSERT OR IGNORE INTO events (block_number, log_index, block_timestamp, \
+                     transaction_hash, address, topics, data) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )
+                .map_err(sink_error)?;
+
+            for event in events {
+                match event {
+                    IndexerEvent::Log(event) => {
+                        insert
+                            .execute(params![
+                                event.block_number,
+                                event.log_index,
+                                event.block_timestamp,
+                                event.transaction_hash.as_slice(),
+                                event.address.as_slice(),
+                                topics(event),
+                                event.data.as_ref(),
+                            ])
+                            .map_err(sink_error)?;
+                    }
+                    IndexerEvent::Reorg { invalidated, .. } => {
+                        tx.execute(
+                            "DELETE FROM events WHERE block_number BETWEEN ?1 AND ?2",
+                            params![invalidated.start(), invalidated.end()],
+                        )
+                        .map_err(sink_error)?;
+                    }
+                }
+            }
+        }
+        tx.commit().map_err(sink_error)
+    }
+
+    fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+}
+
+fn read_event(row: &Row<'_>) -> rusqlite::Result<IndexedEvent> {
+    let transaction_hash: Vec<u8> = row.get(3)?;
+    let address: Vec<u8> = row.get(4)?;
+    let topics: Vec<u8> = row.get(5)?;
+    let data: Vec<u8> = row.get(6)?;
+
+    Ok(IndexedEvent {
+        block_number: row.get(0)?,
+        log_index: row.get(1)?,
+        block_timestamp: row.get(2)?,
+        transaction_hash: B256::from_slice(&transaction_hash),
+        address: Address::from_slice(&address),
+        topics: topics.chunks_exact(32).map(B256::from_slice).collect(),
+        data: Bytes::from(data),
+    })
+}
+
+fn topics(event: &IndexedEvent) -> Vec<u8> {
+    event.topics.iter().flat_map(|topic| topic.0).collect()
+}
+
+fn sink_error(err: rusqlite::Error) -> EventIndexerError {
+    EventIndexerError::SinkError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(block_number: u64, log_index: u64) -> IndexerEvent {
+        IndexerEvent::Log(IndexedEvent {
+            block_number,
+            block_timestamp: 1_700_000_000 + block_number * 12,
+            transaction_hash: B256::repeat_byte(block_number as u8),
+            log_index,
+            address: Address::repeat_byte(0x11),
+            topics: vec![B256::repeat_byte(0x22), B256::repeat_byte(0x33)],
+            data: Bytes::from(vec![block_number as u8, log_index as u8]),
+        })
+    }
+
+    #[test]
+    fn stores_duplicates_once() {
+        let mut sink = SqliteEventSink::open_in_memory().unwrap();
+
+        sink.write(&[event(1, 0), event(1, 1), event(1, 0)])
+            .unwrap();
+        // Re-indexing the same blocks after a restart.
+        sink.write(&[event(1, 1), event(2, 0)]).unwrap();
+
+        let stored: Vec<IndexerEvent> = sink
+            .events(0, 10)
+            .unwrap()
+            .into_iter()
+            .map(IndexerEvent::Log)
+            .collect();
+        assert_eq!(stored, vec![event(1, 0), event(1, 1), event(2, 0)]);
+    }
+
+    #[test]
+    fn reorg_replaces_stale_entries() {
+        let mut sink = SqliteEventSink::open_in_memory().unwrap();
+        let replacement = |block_number: u64| {
+            let IndexerEvent::Log(mut log) = event(block_number, 0) else {
+                unreachable!()
+            };
+            log.data = Bytes::from_static(b"canonical");
+            IndexerEvent::Log(log)
+        };
+
+        sink.write(&[event(4, 0), event(5, 0), event(6, 0)])
+            .unwrap();
+        // Without the delete, the replacements would be ignored as duplicates.
+        sink.write(&[IndexerEvent::reorg(2, 4), replacement(5), replacement(6)])
+            .unwrap();
+
+        let stored: Vec<IndexerEvent> = sink
+            .events(0, 10)
+            .unwrap()
+            .into_iter()
+            .map(IndexerEvent::Log)
+            .collect();
+        assert_eq!(stored, vec![event(4, 0), replacement(5), replacement(6)]);
+    }
+}
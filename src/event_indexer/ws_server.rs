@@ -0,0 +1,219 @@
+use alloy::primitives::{Address, B256};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc::Receiver,
+    },
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::event_indexer::common::{IndexedEvent, IndexerEvent};
+
+/// Narrows what a WebSocket client receives. Reorg notices are always delivered.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    /// Only logs emitted by this contract.
+    pub address: Option<Address>,
+    /// Only logs whose first topic (the event signature) is this one.
+    pub topic: Option<B256>,
+    /// Only logs from this block on.
+    pub from_block: Option<u64>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &IndexerEvent) -> bool {
+        let IndexerEvent::Log(log) = event else {
+            return true;
+        };
+        self.matches_log(log)
+    }
+
+    fn matches_log(&self, log: &IndexedEvent) -> bool {
+        self.address.is_none_or(|address| log.address == address)
+            && self
+                .topic
+                .is_none_or(|topic| log.topics.first() == Some(&topic))
+            && self.from_block.is_none_or(|from| log.block_number >= from)
+    }
+}
+
+/// Serves the events read from `events` on `GET /events` of `listener` until `cancel` fires.
+/// The caller binds the listener, so a port in use fails startup rather than this task.
+///
+/// Every connected client receives each event as a JSON text message, from the moment it
+/// connects on. A client may send an [`EventFilter`] as JSON at any time to replace its
+/// current one. Clients falling more than `buffer` events behind are disconnected, so a slow
+/// client never holds up the indexer.
+pub async fn serve(
+    mut events: Receiver<IndexerEvent>,
+    listener: TcpListener,
+    buffer: usize,
+    cancel: CancellationToken,
+) -> std::io::Result<()> {
+    let (sender, _) = broadcast::channel(buffer);
+
+    let fanout = sender.clone();
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            // Fails only while no client is connected.
+            let _ = fanout.send(event);
+        }
+    });
+
+    let app = Router::new()
+        .route("/events", get(upgrade))
+        .with_state(sender);
+
+    info!(
+        "Serving indexed events on ws://{}/events",
+        listener.local_addr()?
+    );
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move { cancel.cancelled().await })
+        .await
+}
+
+async fn upgrade(
+    ws: WebSocketUpgrade,
+    State(sender): State<broadcast::Sender<IndexerEvent>>,
+) -> Response {
+    let events = sender.subscribe();
+    ws.on_upgrade(move |socket| stream_events(socket, events))
+}
+
+async fn stream_events(mut socket: WebSocket, mut events: broadcast::Receiver<IndexerEvent>) {
+    let mut filter = EventFilter::default();
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(new_filter) => filter = new_filter,
+                    Err(e) => warn!("Ignoring invalid event filter from client: {}", e),
+                },
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => return,
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if !filter.matches(&event) {
+                        continue;
+                    }
+                    let Ok(text) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Disconnecting event stream client {} events behind", skipped);
+                    let _ = socket.send(Message::Close(None)).await;
+                    return;
+                }
+                Err(RecvError::Closed) => {
+                    let _ = socket.send(Message::Close(None)).await;
+                    return;
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, time::Duration};
+
+    use alloy::primitives::Bytes;
+    use futures::{SinkExt, StreamExt};
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::{connect_async, tungstenite, MaybeTlsStream, WebSocketStream};
+
+    use super::*;
+    use crate::event_indexer::common::IndexedEvent;
+
+    type Client = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+    fn event(address: Address, block_number: u64) -> IndexerEvent {
+        IndexerEvent::Log(IndexedEvent {
+            block_number,
+            block_timestamp: 1_700_000_000 + block_number * 12,
+            transaction_hash: B256::repeat_byte(block_number as u8),
+            log_index: 0,
+            address,
+            topics: vec![B256::repeat_byte(0x22)],
+            data: Bytes::new(),
+        })
+    }
+
+    /// Connects to the server at `addr`, waiting for it to start listening.
+    async fn connect(addr: SocketAddr) -> Client {
+        let url = format!("ws://{}/events", addr);
+        for _ in 0..100 {
+            if let Ok((client, _)) = connect_async(url.as_str()).await {
+                return client;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("event server did not start");
+    }
+
+    async fn next_event(client: &mut Client) -> IndexerEvent {
+        let message = tokio::time::timeout(Duration::from_secs(5), client.next())
+            .await
+            .expect("no event received")
+            .expect("stream ended")
+            .unwrap();
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn client_receives_indexed_events() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (sender, events) = mpsc::channel(16);
+        let cancel = CancellationToken::new();
+        let server = tokio::spawn(serve(events, listener, 16, cancel.clone()));
+        let mut client = connect(addr).await;
+
+        let watched = Address::repeat_byte(0x11);
+        sender.send(event(watched, 1)).await.unwrap();
+        assert_eq!(next_event(&mut client).await, event(watched, 1));
+
+        let filter = EventFilter {
+            address: Some(watched),
+            ..Default::default()
+        };
+        client
+            .send(tungstenite::Message::text(
+                serde_json::to_string(&filter).unwrap(),
+            ))
+            .await
+            .unwrap();
+        // Give the server a moment to apply the filter before producing more events.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sender
+            .send(event(Address::repeat_byte(0x99), 2))
+            .await
+            .unwrap();
+        sender.send(event(watched, 3)).await.unwrap();
+        assert_eq!(next_event(&mut client).await, event(watched, 3));
+
+        cancel.cancel();
+        drop(client);
+        server.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    ffi::OsString,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{derivation::common::BlockPayloadAttributes, traits::EngineExecutor};
+
+/// One line of a [`FileBlockSink`]: the derived block, next to either `executed` with the
+/// executor's result or `failed` with its error.
+#[derive(Serialize)]
+struct BlockRecord<'a, T> {
+    block: &'a BlockPayloadAttributes,
+    #[serde(flatten)]
+    outcome: BlockOutcome<'a, T>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BlockOutcome<'a, T> {
+    Executed(&'a T),
+    Failed(String),
+}
+
+/// Appends every derived block and its execution outcome to a file as one JSON object per line
+/// (NDJSON), for offline analysis of the derivation output.
+///
+/// With a [size](Self::with_max_bytes) or [age](Self::with_max_age) limit, a full file is
+/// renamed to `<path>.<n>`, with `n` the first unused number from 1, and a fresh one started.
+/// A single line is never split across files, so a file exceeds the size limit only when one
+/// line alone does.
+#[derive(Debug)]
+pub struct FileBlockSink {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    max_age: Option<Duration>,
+    current: Mutex<OpenFile>,
+}
+
+#[derive(Debug)]
+struct OpenFile {
+    file: File,
+    bytes: u64,
+    opened: Instant,
+}
+
+impl OpenFile {
+    fn open(path: &PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            bytes: file.metadata()?.len(),
+            file,
+            opened: Instant::now(),
+        })
+    }
+}
+
+impl FileBlockSink {
+    /// Appends to `path`, creating it if it does not exist. Files are never rotated by default.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let current = OpenFile::open(&path)?;
+        Ok(Self {
+            path,
+            max_bytes: None,
+            max_age: None,
+            current: Mutex::new(current),
+        })
+    }
+
+    /// Starts a new file before a line would take the current one past `max_bytes`.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Starts a new file once the current one has been written to for `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Appends `block` and the outcome of executing it as one line, rotating the file first if
+    /// it is due.
+    pub fn write<T: Serialize, E: Display>(
+        &self,
+        block: &BlockPayloadAttributes,
+        outcome: &Result<T, E>,
+    ) -> io::Result<()> {
+        let record = BlockRecord {
+            block,
+            outcome: match outcome {
+                Ok(result) => BlockOutcome::Executed(result),
+                Err(e) => BlockOutcome::Failed(e.to_string()),
+            },
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if current.bytes > 0 && self.is_due(&current, line.len() as u64) {
+            *current = self.rotate()?;
+        }
+        current.file.write_all(&line)?;
+        current.bytes += line.len() as u64;
+        Ok(())
+    }
+
+    fn is_due(&self, current: &OpenFile, line_len: u64) -> bool {
+        self.max_bytes
+            .is_some_and(|max_bytes| current.bytes + line_len > max_bytes)
+            || self
+                .max_age
+                .is_some_and(|max_age| current.opened.elapsed() >= max_age)
+    }
+
+    /// Moves the current file aside and opens a fresh one in its place.
+    fn rotate(&self) -> io::Result<OpenFile> {
+        let mut n = 1;
+        let rotated = loop {
+            let mut rotated = OsString::from(self.path.as_os_str());
+            rotated.push(format!(".{}", n));
+            let rotated = PathBuf::from(rotated);
+            if !rotated.exists() {
+                break rotated;
+            }
+            n += 1;
+        };
+        fs::rename(&self.path, &rotated)?;
+        info!("Rotated {} to {}", self.path.display(), rotated.display());
+        OpenFile::open(&self.path)
+    }
+}
+
+/// Executes payloads with another executor, recording each one and its outcome, retries and
+/// failures included, to a [`FileBlockSink`]. A failed write is logged and never fails the
+/// execution.
+#[derive(Debug)]
+pub struct BlockSinkExecutor<E> {
+    inner: E,
+    sink: FileBlockSink,
+}
+
+impl<E> BlockSinkExecutor<E> {
+    pub fn new(inner: E, sink: FileBlockSink) -> Self {
+        Self { inner, sink }
+    }
+}
+
+#[async_trait]
+impl<E> EngineExecutor for BlockSinkExecutor<E>
+where
+    E: EngineExecutor<BlockPayloadAttributes = BlockPayloadAttributes> + Send + Sync,
+    E::ExecutionResult: Serialize + Send,
+    E::Error: Send,
+{
+    type BlockPayloadAttributes = BlockPayloadAttributes;
+    type ExecutionResult = E::ExecutionResult;
+    type Error = E::Error;
+
+    async fn execute(
+        &self,
+        payload: BlockPayloadAttributes,
+    ) -> Result<E::ExecutionResult, E::Error> {
+        let outcome = self.inner.execute(payload.clone()).await;
+        if let Err(e) = self.sink.write(&payload, &outcome) {
+            warn!(
+                "Failed to record block {} to {}: {}",
+                payload.l1_block_number,
+                self.sink.path.display(),
+                e
+            );
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        eips::eip1559::BaseFeeParams,
+        primitives::{Address, Bytes, B256},
+    };
+    use serde_json::Value;
+
+    use super::*;
+    use crate::execution_engine::common::ExecutionError;
+
+    /// Executes every payload but the one from L1 block 2, returning its L1 block as the hash.
+    struct StubExecutor;
+
+    #[async_trait]
+    impl EngineExecutor for StubExecutor {
+        type BlockPayloadAttributes = BlockPayloadAttributes;
+        type ExecutionResult = B256;
+        type Error = ExecutionError;
+
+        async fn execute(&self, payload: BlockPayloadAttributes) -> Result<B256, ExecutionError> {
+            match payload.l1_block_number {
+                2 => Err(ExecutionError::InvalidPayload("INVALID".to_string())),
+                block => Ok(B256::with_last_byte(block as u8)),
+            }
+        }
+    }
+
+    fn payload(l1_block_number: u64) -> BlockPayloadAttributes {
+        BlockPayloadAttributes {
+            l1_block_number,
+            timestamp: 1_700_000_000,
+            prev_randao: B256::ZERO,
+            suggested_fee_recipient: Address::ZERO,
+            gas_limit: 30_000_000,
+            base_fee_params: BaseFeeParams::ethereum(),
+            transactions: vec![Bytes::from_static(b"tx")],
+            is_empty_epoch: false,
+            empty_epoch_reason: None,
+        }
+    }
+
+    fn lines(path: &PathBuf) -> Vec<Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn records_blocks_and_rotates_at_the_size_limit() {
+        let dir =
+            std::env::temp_dir().join(format!("based-rollup-block-sink-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("blocks.ndjson");
+
+        let line_len = {
+            let sink = FileBlockSink::open(dir.join("probe.ndjson")).unwrap();
+            sink.write(
+                &payload(1),
+                &Ok::<_, ExecutionError>(B256::with_last_byte(1)),
+            )
+            .unwrap();
+            fs::metadata(dir.join("probe.ndjson")).unwrap().len()
+        };
+        // Room for two executed blocks per file.
+        let sink = FileBlockSink::open(&path)
+            .unwrap()
+            .with_max_bytes(2 * line_len);
+        let executor = BlockSinkExecutor::new(StubExecutor, sink);
+
+        for block in 1..=3 {
+            let _ = executor.execute(payload(block)).await;
+        }
+
+        let rotated = lines(&dir.join("blocks.ndjson.1"));
+        assert_eq!(rotated.len(), 2);
+        assert_eq!(rotated[0]["block"]["l1_block_number"], 1);
+        assert_eq!(rotated[0]["executed"], B256::with_last_byte(1).to_string());
+        assert_eq!(rotated[1]["block"]["l1_block_number"], 2);
+        assert_eq!(rotated[1]["failed"], "Invalid payload: INVALID");
+
+        let current = lines(&path);
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0]["block"]["l1_block_number"], 3);
+        assert!(!dir.join("blocks.ndjson.2").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
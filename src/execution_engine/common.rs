@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+
+use alloy::{
+    primitives::B256,
+    rpc::types::engine::{JwtError, JwtSecret},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::common::traits::ActorError;
+
+/// Environment variable holding the hex-encoded JWT secret, read when no secret file is
+/// configured.
+pub const JWT_SECRET_ENV: &str = "BASED_JWT_SECRET";
+
+/// Execution client that derived blocks are imported into.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Authenticated Engine API endpoint.
+    pub url: String,
+    /// File holding the hex-encoded JWT secret shared with the execution client; takes
+    /// precedence over [`JWT_SECRET_ENV`].
+    pub jwt_secret_path: Option<PathBuf>,
+    /// Hash of the L2 block the first derived block builds on.
+    pub head_block_hash: B256,
+    /// Validate payloads without ever moving the head.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl EngineConfig {
+    /// See [`load_jwt_secret`].
+    pub fn jwt_secret(&self) -> Result<JwtSecret, ExecutionError> {
+        load_jwt_secret(self.jwt_secret_path.as_deref())
+    }
+}
+
+/// Reads the 32-byte JWT secret from `path` if given, else from [`JWT_SECRET_ENV`]. Errors
+/// never include the secret, so they are safe to log.
+pub fn load_jwt_secret(path: Option<&Path>) -> Result<JwtSecret, ExecutionError> {
+    if let Some(path) = path {
+        return JwtSecret::from_file(path).map_err(|e| {
+            ExecutionError::JwtError(format!("secret in {} {}", path.display(), describe(e)))
+        });
+    }
+
+    let hex = std::env::var(JWT_SECRET_ENV).map_err(|_| {
+        ExecutionError::JwtError(format!(
+            "no secret file configured and {} is not set",
+            JWT_SECRET_ENV
+        ))
+    })?;
+    JwtSecret::from_hex(hex.trim())
+        .map_err(|e| ExecutionError::JwtError(format!("{} {}", JWT_SECRET_ENV, describe(e))))
+}
+
+/// Explains why a secret was rejected without echoing any of it.
+fn describe(err: JwtError) -> String {
+    match err {
+        JwtError::InvalidLength(expected, actual) => format!(
+            "must be {} hex characters (32 bytes), got {}",
+            expected, actual
+        ),
+        JwtError::JwtSecretHexDecodeError(_) => "is not valid hex".to_string(),
+        e => format!("could not be read: {}", e),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExecutionError {
+    #[error("RPC error: {0}")]
+    RpcError(String),
+    #[error("JWT error: {0}")]
+    JwtError(String),
+    /// The engine accepted the call but is still syncing and could not validate it yet; the
+    /// same payload can be retried.
+    #[error("Execution engine is syncing: {0}")]
+    Syncing(String),
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+    #[error("Other error: {0}")]
+    Other(String),
+}
+
+impl ActorError for ExecutionError {
+    /// A rejected payload or a bad JWT secret fails the same way on every retry.
+    fn is_unrecoverable(&self) -> bool {
+        matches!(
+            self,
+            ExecutionError::InvalidPayload(_) | ExecutionError::JwtError(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_secret_from_env_unless_a_file_is_given() {
+        let secret = "f".repeat(64);
+        let path =
+            std::env::temp_dir().join(format!("based-rollup-jwt-{}.hex", std::process::id()));
+        std::fs::write(&path, "1".repeat(64)).unwrap();
+
+        // The only test touching the variable, so setting it cannot race another.
+        std::env::set_var(JWT_SECRET_ENV, &secret);
+        let from_env = load_jwt_secret(None);
+        let from_file = load_jwt_secret(Some(&path));
+        std::env::set_var(JWT_SECRET_ENV, &secret[..62]);
+        let too_short = load_jwt_secret(None);
+        std::env::remove_var(JWT_SECRET_ENV);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(from_env.unwrap(), JwtSecret::from_hex(&secret).unwrap());
+        assert_eq!(
+            from_file.unwrap(),
+            JwtSecret::from_hex("1".repeat(64)).unwrap()
+        );
+        let message = too_short.unwrap_err().to_string();
+        assert!(message.contains("got 62"));
+        assert!(!message.contains("ffff"), "leaked the secret: {}", message);
+        assert!(matches!(
+            load_jwt_secret(None),
+            Err(ExecutionError::JwtError(message)) if message.contains(JWT_SECRET_ENV)
+        ));
+    }
+}
@@ -0,0 +1,579 @@
+use alloy::{
+    eips::eip1559::BaseFeeParams,
+    primitives::{Bytes, B256, B64, U64},
+    rpc::types::engine::{
+        Claims, ExecutionPayloadEnvelopeV3, ForkchoiceState, ForkchoiceUpdated, JwtSecret,
+        PayloadAttributes, PayloadStatus, PayloadStatusEnum,
+    },
+    transports::http::reqwest::{Client, Url},
+};
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Instant},
+};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    derivation::common::BlockPayloadAttributes, execution_engine::common::ExecutionError,
+    traits::EngineExecutor,
+};
+
+/// Engine API methods the executor calls.
+pub const ENGINE_METHODS: [&str; 3] = [
+    "engine_forkchoiceUpdatedV3",
+    "engine_getPayloadV3",
+    "engine_newPayloadV3",
+];
+
+/// Delay before re-issuing a call the engine answered with `SYNCING`; doubles on each retry.
+const SYNCING_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound on the delay between `SYNCING` retries.
+const MAX_SYNCING_RETRY_DELAY: Duration = Duration::from_secs(8);
+
+/// How long the engine may stay `SYNCING` before a call fails, by default.
+const DEFAULT_MAX_SYNCING_WAIT: Duration = Duration::from_secs(60);
+
+/// Payload attributes extended with the rollup's transaction list, which the engine must
+/// include in order instead of building from its own mempool, and the block's gas limit and
+/// EIP-1559 parameters.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RollupPayloadAttributes {
+    #[serde(flatten)]
+    payload_attributes: PayloadAttributes,
+    transactions: Vec<Bytes>,
+    no_tx_pool: bool,
+    gas_limit: U64,
+    eip1559_params: B64,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<R> {
+    result: Option<R>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Executes payloads on an execution client over the authenticated Engine API (Cancun, V3
+/// methods).
+///
+/// Each payload is built with `engine_forkchoiceUpdatedV3`, fetched with `engine_getPayloadV3`,
+/// imported with `engine_newPayloadV3` and then made the head with a second
+/// `engine_forkchoiceUpdatedV3`. The resulting block hash is the execution result.
+///
+/// A call answered with `SYNCING` (or `ACCEPTED`) is re-issued with backoff until the engine
+/// has caught up, for at most the [syncing wait](EngineApiExecutor::with_max_syncing_wait).
+/// `INVALID` fails right away.
+///
+/// In [dry-run](EngineApiExecutor::with_dry_run) mode the last call is skipped, so payloads are
+/// built and validated against the current head without ever moving it.
+#[derive(Debug)]
+pub struct EngineApiExecutor {
+    client: Client,
+    url: Url,
+    jwt_secret: JwtSecret,
+    /// Serializes executions, each of which builds on the previous head.
+    forkchoice: Mutex<ForkchoiceState>,
+    dry_run: bool,
+    max_syncing_wait: Duration,
+}
+
+impl EngineApiExecutor {
+    /// Creates an executor that builds on top of `head`, the current L2 head block hash.
+    pub fn new(url: Url, jwt_secret: JwtSecret, head: B256) -> Self {
+        Self {
+            client: Client::new(),
+            url,
+            jwt_secret,
+            forkchoice: Mutex::new(ForkchoiceState {
+                head_block_hash: head,
+                safe_block_hash: head,
+                finalized_block_hash: head,
+            }),
+            dry_run: false,
+            max_syncing_wait: DEFAULT_MAX_SYNCING_WAIT,
+        }
+    }
+
+    /// Validates each payload with `engine_newPayloadV3` but never makes it the head, for
+    /// shadow-running the driver against a live execution client.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// How long to keep re-issuing a call while the engine answers `SYNCING` before failing with
+    /// [`ExecutionError::Syncing`]; 60 seconds by default, `0` fails on the first answer.
+    pub fn with_max_syncing_wait(mut self, max_syncing_wait: Duration) -> Self {
+        self.max_syncing_wait = max_syncing_wait;
+        self
+    }
+
+    /// Exchanges the methods this executor calls for those the execution client supports,
+    /// e.g. to check that the client is reachable and accepts the JWT secret.
+    pub async fn exchange_capabilities(&self) -> Result<Vec<String>, ExecutionError> {
+        self.call("engine_exchangeCapabilities", json!([ENGINE_METHODS]))
+            .await
+    }
+
+    async fn call<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<R, ExecutionError> {
+        // Tokens are only valid for a minute around their `iat`, so sign one per request.
+        let token = self
+            .jwt_secret
+            .encode(&Claims::with_current_timestamp())
+            .map_err(|e| ExecutionError::JwtError(e.to_string()))?;
+
+        let response: JsonRpcResponse<R> = self
+            .client
+            .post(self.url.clone())
+            .bearer_auth(token)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| ExecutionError::RpcError(format!("{}: {}", method, e)))?
+            .json()
+            .await
+            .map_err(|e| ExecutionError::RpcError(format!("{}: {}", method, e)))?;
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(ExecutionError::RpcError(format!(
+                "{} failed with code {}: {}",
+                method, error.code, error.message
+            ))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(ExecutionError::RpcError(format!(
+                "{} returned no result",
+                method
+            ))),
+        }
+    }
+
+    async fn forkchoice_updated(
+        &self,
+        state: ForkchoiceState,
+        attributes: Option<RollupPayloadAttributes>,
+    ) -> Result<ForkchoiceUpdated, ExecutionError> {
+        let params = json!([state, attributes]);
+        self.until_synced("engine_forkchoiceUpdatedV3", || async {
+            let updated: ForkchoiceUpdated = self
+                .call("engine_forkchoiceUpdatedV3", params.clone())
+                .await?;
+            check_status("engine_forkchoiceUpdatedV3", &updated.payload_status)?;
+            Ok(updated)
+        })
+        .await
+    }
+
+    async fn new_payload(&self, params: Value) -> Result<(), ExecutionError> {
+        self.until_synced("engine_newPayloadV3", || async {
+            let status: PayloadStatus = self.call("engine_newPayloadV3", params.clone()).await?;
+            check_status("engine_newPayloadV3", &status)
+        })
+        .await
+    }
+
+    /// Runs `call` again with backoff while it fails with [`ExecutionError::Syncing`], until
+    /// `max_syncing_wait` has passed.
+    async fn until_synced<R, Fut>(
+        &self,
+        method: &str,
+        mut call: impl FnMut() -> Fut,
+    ) -> Result<R, ExecutionError>
+    where
+        Fut: Future<Output = Result<R, ExecutionError>>,
+    {
+        // An unrepresentable deadline means waiting for as long as it takes.
+        let deadline = Instant::now().checked_add(self.max_syncing_wait);
+        let mut delay = SYNCING_RETRY_DELAY;
+        loop {
+            let result = call().await;
+            let within_wait = match deadline {
+                Some(deadline) => Instant::now() + delay <= deadline,
+                None => true,
+            };
+            match result {
+                Err(ExecutionError::Syncing(e)) if within_wait => {
+                    warn!("{}, retrying {} in {:?}", e, method, delay);
+                    sleep(delay).await;
+                    delay = (delay * 2).min(MAX_SYNCING_RETRY_DELAY);
+                }
+                Err(ExecutionError::Syncing(e)) if !self.max_syncing_wait.is_zero() => {
+                    return Err(ExecutionError::Syncing(format!(
+                        "{} (still syncing after {:?})",
+                        e, self.max_syncing_wait
+                    )));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl EngineExecutor for EngineApiExecutor {
+    type BlockPayloadAttributes = BlockPayloadAttributes;
+    type ExecutionResult = B256;
+    type Error = ExecutionError;
+
+    #[instrument(skip_all, fields(l1_block_number = payload.l1_block_number))]
+    async fn execute(&self, payload: BlockPayloadAttributes) -> Result<B256, ExecutionError> {
+        let mut forkchoice = self.forkchoice.lock().await;
+
+        let attributes = RollupPayloadAttributes {
+            payload_attributes: PayloadAttributes {
+                timestamp: payload.timestamp,
+                prev_randao: payload.prev_randao,
+                suggested_fee_recipient: payload.suggested_fee_recipient,
+                withdrawals: Some(Vec::new()),
+                parent_beacon_block_root: Some(B256::ZERO),
+                target_blobs_per_block: None,
+                max_blobs_per_block: None,
+            },
+            transactions: payload.transactions,
+            no_tx_pool: true,
+            gas_limit: U64::from(payload.gas_limit),
+            eip1559_params: eip1559_params(&payload.base_fee_params),
+        };
+
+        let payload_id = self
+            .forkchoice_updated(*forkchoice, Some(attributes))
+            .await?
+            .payload_id
+            .ok_or_else(|| {
+                ExecutionError::Other("forkchoiceUpdated returned no payload id".to_string())
+            })?;
+
+        let envelope: ExecutionPayloadEnvelopeV3 = self
+            .call("engine_getPayloadV3", json!([payload_id]))
+            .await?;
+        let block_hash = envelope
+            .execution_payload
+            .payload_inner
+            .payload_inner
+            .block_hash;
+
+        self.new_payload(json!([
+            envelope.execution_payload,
+            Vec::<B256>::new(),
+            B256::ZERO
+        ]))
+        .await?;
+
+        if self.dry_run {
+            info!(
+                "Validated L2 block {} for L1 block {} (dry run, head unchanged)",
+                block_hash, payload.l1_block_number
+            );
+            return Ok(block_hash);
+        }
+
+        let new_forkchoice = ForkchoiceState {
+            head_block_hash: block_hash,
+            safe_block_hash: block_hash,
+            finalized_block_hash: forkchoice.finalized_block_hash,
+        };
+        self.forkchoice_updated(new_forkchoice, None).await?;
+        *forkchoice = new_forkchoice;
+
+        info!(
+            "Executed L2 block {} for L1 block {}",
+            block_hash, payload.l1_block_number
+        );
+        Ok(block_hash)
+    }
+}
+
+/// Packs the denominator and elasticity into 4 big-endian bytes each, as the engine expects.
+/// Both fit since [`PayloadConfig`](crate::derivation::common::PayloadConfig) is validated.
+fn eip1559_params(params: &BaseFeeParams) -> B64 {
+    let mut packed = [0u8; 8];
+    packed[..4].copy_from_slice(&(params.max_change_denominator as u32).to_be_bytes());
+    packed[4..].copy_from_slice(&(params.elasticity_multiplier as u32).to_be_bytes());
+    B64::from(packed)
+}
+
+/// Maps a non-`VALID` payload status to an error, keeping `SYNCING`/`ACCEPTED` (retryable)
+/// apart from `INVALID`.
+fn check_status(method: &str, status: &PayloadStatus) -> Result<(), ExecutionError> {
+    match &status.status {
+        PayloadStatusEnum::Valid => Ok(()),
+        PayloadStatusEnum::Invalid { validation_error } => Err(ExecutionError::InvalidPayload(
+            format!("{}: {}", method, validation_error),
+        )),
+        PayloadStatusEnum::Syncing | PayloadStatusEnum::Accepted => Err(ExecutionError::Syncing(
+            format!("{} returned {}", method, status.status),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    use alloy::{
+        primitives::{Address, Bloom, U256},
+        rpc::types::engine::{
+            BlobsBundleV1, ExecutionPayloadV1, ExecutionPayloadV2, ExecutionPayloadV3,
+        },
+    };
+    use axum::{extract::State, http::HeaderMap, routing::post, Json, Router};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::common::traits::ActorError;
+
+    const HEAD: B256 = B256::repeat_byte(0x01);
+    const BUILT: B256 = B256::repeat_byte(0x02);
+
+    /// A scripted execution client that records the methods called on it.
+    #[derive(Default)]
+    struct MockEngine {
+        calls: Vec<(String, Value)>,
+        unauthorized: usize,
+        /// `newPayload` calls still to answer with `SYNCING`.
+        syncing: usize,
+        /// Whether `newPayload` rejects every payload as `INVALID`.
+        invalid: bool,
+    }
+
+    type Engine = Arc<StdMutex<MockEngine>>;
+
+    async fn serve_engine(secret: JwtSecret, engine: Engine) -> Url {
+        async fn handle(
+            State((secret, engine)): State<(JwtSecret, Engine)>,
+            headers: HeaderMap,
+            Json(request): Json<Value>,
+        ) -> Json<Value> {
+            let mut engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+            let authorized = headers
+                .get("authorization")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .is_some_and(|token| secret.validate(token).is_ok());
+            if !authorized {
+                engine.unauthorized += 1;
+            }
+
+            let method = request["method"].as_str().unwrap_or_default().to_string();
+            let params = request["params"].clone();
+            let result = match method.as_str() {
+                "engine_forkchoiceUpdatedV3" => json!({
+                    "payloadStatus": valid(),
+                    "payloadId": params[1].is_object().then_some("0x0000000000000001"),
+                }),
+                "engine_getPayloadV3" => serde_json::to_value(envelope()).unwrap(),
+                "engine_newPayloadV3" if engine.invalid => json!({
+                    "status": "INVALID",
+                    "latestValidHash": HEAD,
+                    "validationError": "bad state root",
+                }),
+                "engine_newPayloadV3" if engine.syncing > 0 => {
+                    engine.syncing -= 1;
+                    json!({ "status": "SYNCING", "latestValidHash": null, "validationError": null })
+                }
+                "engine_newPayloadV3" => valid(),
+                _ => Value::Null,
+            };
+            engine.calls.push((method, params));
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        }
+
+        let app = Router::new()
+            .route("/", post(handle))
+            .with_state((secret, engine));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url.parse().unwrap()
+    }
+
+    fn valid() -> Value {
+        json!({ "status": "VALID", "latestValidHash": BUILT, "validationError": null })
+    }
+
+    fn envelope() -> ExecutionPayloadEnvelopeV3 {
+        ExecutionPayloadEnvelopeV3 {
+            execution_payload: ExecutionPayloadV3 {
+                payload_inner: ExecutionPayloadV2 {
+                    payload_inner: ExecutionPayloadV1 {
+                        parent_hash: HEAD,
+                        fee_recipient: Address::ZERO,
+                        state_root: B256::ZERO,
+                        receipts_root: B256::ZERO,
+                        logs_bloom: Bloom::ZERO,
+                        prev_randao: B256::ZERO,
+                        block_number: 1,
+                        gas_limit: 30_000_000,
+                        gas_used: 0,
+                        timestamp: 1_700_000_000,
+                        extra_data: Bytes::new(),
+                        base_fee_per_gas: U256::from(1),
+                        block_hash: BUILT,
+                        transactions: Vec::new(),
+                    },
+                    withdrawals: Vec::new(),
+                },
+                blob_gas_used: 0,
+                excess_blob_gas: 0,
+            },
+            block_value: U256::ZERO,
+            blobs_bundle: BlobsBundleV1 {
+                commitments: Vec::new(),
+                proofs: Vec::new(),
+                blobs: Vec::new(),
+            },
+            should_override_builder: false,
+        }
+    }
+
+    fn payload() -> BlockPayloadAttributes {
+        BlockPayloadAttributes {
+            l1_block_number: 100,
+            timestamp: 1_700_000_000,
+            prev_randao: B256::ZERO,
+            suggested_fee_recipient: Address::ZERO,
+            gas_limit: 30_000_000,
+            base_fee_params: BaseFeeParams::ethereum(),
+            transactions: vec![Bytes::from_static(&[0x02, 0x01])],
+            is_empty_epoch: false,
+            empty_epoch_reason: None,
+        }
+    }
+
+    async fn executor(engine: &Engine) -> EngineApiExecutor {
+        let secret = JwtSecret::random();
+        let url = serve_engine(secret, engine.clone()).await;
+        EngineApiExecutor::new(url, secret, HEAD)
+    }
+
+    fn methods(engine: &Engine) -> Vec<String> {
+        let engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+        engine
+            .calls
+            .iter()
+            .map(|(method, _)| method.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn builds_imports_and_sets_head() {
+        let engine = Engine::default();
+        let executor = executor(&engine).await;
+
+        let block_hash = executor.execute(payload()).await.unwrap();
+
+        assert_eq!(block_hash, BUILT);
+        assert_eq!(
+            methods(&engine),
+            vec![
+                "engine_forkchoiceUpdatedV3",
+                "engine_getPayloadV3",
+                "engine_newPayloadV3",
+                "engine_forkchoiceUpdatedV3",
+            ]
+        );
+        let engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(engine.unauthorized, 0);
+        let (_, build) = &engine.calls[0];
+        assert_eq!(build[0]["headBlockHash"], json!(HEAD));
+        assert_eq!(build[1]["transactions"], json!(["0x0201"]));
+        assert_eq!(build[1]["noTxPool"], json!(true));
+        let (_, set_head) = &engine.calls[3];
+        assert_eq!(set_head[0]["headBlockHash"], json!(BUILT));
+        assert_eq!(set_head[1], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn dry_run_leaves_head_unchanged() {
+        let engine = Engine::default();
+        let executor = executor(&engine).await.with_dry_run(true);
+
+        assert_eq!(executor.execute(payload()).await.unwrap(), BUILT);
+        assert_eq!(executor.execute(payload()).await.unwrap(), BUILT);
+
+        assert_eq!(
+            methods(&engine),
+            vec![
+                "engine_forkchoiceUpdatedV3",
+                "engine_getPayloadV3",
+                "engine_newPayloadV3",
+                "engine_forkchoiceUpdatedV3",
+                "engine_getPayloadV3",
+                "engine_newPayloadV3",
+            ]
+        );
+        // Both blocks are built on the original head, since neither was committed.
+        let engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(engine.calls[3].1[0]["headBlockHash"], json!(HEAD));
+    }
+
+    #[tokio::test]
+    async fn waits_out_syncing_engine() {
+        let engine = Engine::default();
+        engine.lock().unwrap().syncing = 2;
+        let executor = executor(&engine).await;
+
+        assert_eq!(executor.execute(payload()).await.unwrap(), BUILT);
+
+        assert_eq!(
+            methods(&engine),
+            vec![
+                "engine_forkchoiceUpdatedV3",
+                "engine_getPayloadV3",
+                "engine_newPayloadV3",
+                "engine_newPayloadV3",
+                "engine_newPayloadV3",
+                "engine_forkchoiceUpdatedV3",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_engine_syncing_past_max_wait() {
+        let engine = Engine::default();
+        engine.lock().unwrap().syncing = usize::MAX;
+        let executor = executor(&engine)
+            .await
+            .with_max_syncing_wait(Duration::from_millis(100));
+
+        let err = executor.execute(payload()).await.unwrap_err();
+
+        assert!(matches!(err, ExecutionError::Syncing(_)));
+        assert!(!err.is_unrecoverable());
+    }
+
+    #[tokio::test]
+    async fn invalid_payload_is_fatal() {
+        let engine = Engine::default();
+        engine.lock().unwrap().invalid = true;
+        let executor = executor(&engine).await;
+
+        let err = executor.execute(payload()).await.unwrap_err();
+
+        assert!(matches!(err, ExecutionError::InvalidPayload(_)));
+        assert!(err.is_unrecoverable());
+        // Neither retried nor made the head.
+        assert_eq!(methods(&engine).len(), 3);
+    }
+}
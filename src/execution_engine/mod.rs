@@ -0,0 +1,3 @@
+pub mod block_sink;
+pub mod common;
+pub mod engine_api;
@@ -1,7 +1,10 @@
 pub mod chain_iterator;
 pub mod common;
+pub mod config;
+pub mod da_watcher;
 pub mod datasource;
 pub mod derivation;
+pub mod driver;
 pub mod event_indexer;
 pub mod execution_engine;
 pub mod traits;
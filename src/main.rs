@@ -0,0 +1,1414 @@
+use std::{
+    fmt::Display, future::Future, io::Write, net::SocketAddr, path::PathBuf, sync::Arc,
+    time::Duration,
+};
+
+#[cfg(feature = "sqlite")]
+use alloy::primitives::Address;
+use alloy::{
+    primitives::B256,
+    providers::{Provider, RootProvider},
+    rpc::types::Filter,
+    transports::BoxTransport,
+};
+use anyhow::{bail, Result};
+#[cfg(feature = "ws-server")]
+use based_rollup_driver::event_indexer::ws_server;
+#[cfg(feature = "metrics")]
+use based_rollup_driver::{common::metrics, event_indexer::metrics::IndexerMetrics};
+use based_rollup_driver::{
+    common::{
+        hasher::HashScheme,
+        health::{self, HealthState, Readiness},
+        provider::{self, SplitProvider},
+        rate_limiter::RateLimiter,
+    },
+    config::{DriverConfig, DEFAULT_CONFIG_PATH},
+    da_watcher::{
+        common::{ProposalManifest, WatcherProgress},
+        da_watcher::DAWatcher,
+    },
+    datasource::{
+        blob_fetcher::BlobDataSourceFetcher, calldata_fetcher::CalldataDataSourceFetcher,
+        common::DataSourceKind, fallback_fetcher::FallbackFetcher,
+    },
+    derivation::{
+        common::{DerivationError, DerivationTrace},
+        derivation::DefaultDerivationPipeline,
+        metrics::DerivationMetrics,
+    },
+    driver::driver::BasedDriver,
+    event_indexer::{
+        checkpoint::FileCheckpointStore,
+        common::IndexerEvent,
+        event_indexer::{query_events, EventIndexer},
+        sink::{EventSink, StdoutJsonSink},
+    },
+    execution_engine::{
+        common::{load_jwt_secret, EngineConfig, ExecutionError},
+        engine_api::{EngineApiExecutor, ENGINE_METHODS},
+    },
+    traits::{BlockSource, Driver, EngineExecutor},
+};
+#[cfg(feature = "sqlite")]
+use based_rollup_driver::{
+    datasource::{common::DataQuery, event_fetcher::EventDataSourceFetcher},
+    derivation::common::{BlockPayloadAttributes, PayloadConfig, DEFAULT_GAS_LIMIT},
+    event_indexer::sqlite_sink::SqliteEventSink,
+    traits::{DataSourceFetcher, DerivationPipeline},
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use tokio::{signal, sync::mpsc};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use tracing_subscriber::{
+    fmt::{format::FmtSpan, writer::BoxMakeWriter},
+    EnvFilter,
+};
+
+/// Capacity of the indexer's event channels, and the events a WebSocket client may fall behind
+/// by before it is disconnected.
+const EVENTS_BUFFER: usize = 1024;
+
+type L1Provider = RootProvider<BoxTransport>;
+
+type RollupDriver = BasedDriver<
+    DAWatcher<FallbackFetcher>,
+    DefaultDerivationPipeline<FallbackFetcher>,
+    EngineApiExecutor,
+>;
+
+#[derive(Parser)]
+#[command(name = "based-rollup", version, about = "Based rollup driver")]
+struct Cli {
+    /// Base log level; `RUST_LOG` directives, when set, override it per module.
+    #[arg(long, global = true, value_enum, default_value_t = LogLevel::Info)]
+    log_level: LogLevel,
+
+    /// Log output format.
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Log how long each span (watch, fetch, derive, execute) took when it closes.
+    #[arg(long, global = true)]
+    span_timings: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SinkKind {
+    /// One JSON object per event and line on stdout; logs move to stderr.
+    Stdout,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the driver.
+    Run(RunArgs),
+    /// Print the sync status of a running driver.
+    Status(StatusArgs),
+    /// Check a config file without running, exiting non-zero on any problem.
+    ValidateConfig(ValidateConfigArgs),
+    /// Run connectivity checks against the configured endpoints, exiting non-zero on any
+    /// failure.
+    Test(TestArgs),
+    /// Print the configured contract's events in a block range as one JSON object per line,
+    /// without running the indexer.
+    Logs(LogsArgs),
+    /// Re-derive payload attributes from events stored by the SQLite sink, without L1.
+    #[cfg(feature = "sqlite")]
+    Replay(ReplayArgs),
+    /// Run one proposal manifest through derivation and a dry-run execution, printing every
+    /// intermediate stage. Moves no head and writes no checkpoint.
+    DebugProposal(DebugProposalArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Path to the TOML config file.
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Serve `/healthz` and `/readyz` on this port.
+    #[arg(long)]
+    health_port: Option<u16>,
+
+    /// Blocks the indexer may trail the head by while still reported ready.
+    #[arg(long, default_value_t = 10)]
+    ready_max_lag: u64,
+
+    /// Start without first checking that the L1 RPC, DA sources and engine answer.
+    #[arg(long)]
+    skip_warmup: bool,
+
+    /// Also write every indexed event to this sink.
+    #[arg(long, value_enum)]
+    sink: Option<SinkKind>,
+
+    /// Serve Prometheus metrics on this port.
+    #[cfg(feature = "metrics")]
+    #[arg(long)]
+    metrics_port: Option<u16>,
+
+    /// Stream indexed events to WebSocket clients on this port, at `/events`.
+    #[cfg(feature = "ws-server")]
+    #[arg(long)]
+    events_port: Option<u16>,
+
+    /// Persist indexed events to this SQLite database, e.g. as input for `replay`.
+    #[cfg(feature = "sqlite")]
+    #[arg(long)]
+    store_path: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct StatusArgs {
+    /// Base URL of the driver's health server (see `run --health-port`).
+    #[arg(long, default_value = "http://127.0.0.1:8080")]
+    endpoint: String,
+
+    /// Print the raw JSON readiness report.
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct ValidateConfigArgs {
+    /// Path to the TOML config file.
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Also connect to the RPC endpoints and confirm they answer.
+    #[arg(long)]
+    ping: bool,
+}
+
+#[derive(Args)]
+struct TestArgs {
+    /// Path to the TOML config file.
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// Also check the Engine API of the execution client at this URL.
+    #[arg(long)]
+    engine_url: Option<String>,
+
+    /// File holding the hex-encoded JWT secret shared with the execution client; read from
+    /// `BASED_JWT_SECRET` when not given.
+    #[arg(long)]
+    jwt_secret: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct LogsArgs {
+    /// Path to the TOML config file.
+    #[arg(short, long)]
+    config: Option<String>,
+
+    /// First L1 block to query.
+    #[arg(long)]
+    from: u64,
+
+    /// Last L1 block to query, inclusive.
+    #[arg(long)]
+    to: u64,
+}
+
+#[derive(Args)]
+struct DebugProposalArgs {
+    /// JSON file holding the proposal manifest.
+    file: PathBuf,
+
+    /// Path to the TOML config file.
+    #[arg(short, long)]
+    config: Option<String>,
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Args)]
+struct ReplayArgs {
+    /// SQLite database written by the event sink.
+    #[arg(long)]
+    store_path: PathBuf,
+
+    /// First L1 block to replay.
+    #[arg(long)]
+    from: u64,
+
+    /// Last L1 block to replay, inclusive.
+    #[arg(long)]
+    to: u64,
+
+    /// Fee recipient set on the derived payload attributes.
+    #[arg(long, default_value_t = Address::ZERO)]
+    fee_recipient: Address,
+
+    /// Gas limit set on the derived payload attributes.
+    #[arg(long, default_value_t = DEFAULT_GAS_LIMIT)]
+    gas_limit: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    init_tracing(
+        cli.log_level,
+        cli.log_format,
+        cli.span_timings,
+        prints_data(&cli.command),
+    );
+
+    match cli.command {
+        Command::Run(args) => run(args).await,
+        Command::Status(args) => status(args).await,
+        Command::ValidateConfig(args) => validate_config(args).await,
+        Command::Test(args) => self_test(args, &mut std::io::stdout()).await,
+        Command::Logs(args) => logs(args).await,
+        #[cfg(feature = "sqlite")]
+        Command::Replay(args) => replay(args).await,
+        Command::DebugProposal(args) => debug_proposal(args).await,
+    }
+}
+
+/// Whether `command` writes a data stream to stdout, which logs must then stay out of.
+fn prints_data(command: &Command) -> bool {
+    match command {
+        Command::Run(args) => args.sink.is_some(),
+        Command::Logs(_) => true,
+        #[cfg(feature = "sqlite")]
+        Command::Replay(_) => true,
+        _ => false,
+    }
+}
+
+/// Installs the global subscriber shared by every subcommand.
+fn init_tracing(level: LogLevel, format: LogFormat, span_timings: bool, to_stderr: bool) {
+    // Later directives replace earlier ones for the same target, so `RUST_LOG` wins over the flag.
+    let mut directives = level.as_str().to_string();
+    if let Ok(env) = std::env::var(EnvFilter::DEFAULT_ENV) {
+        directives.push(',');
+        directives.push_str(&env);
+    }
+
+    let span_events = if span_timings {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    let writer = if to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(directives))
+        .with_span_events(span_events)
+        .with_writer(writer);
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+async fn run(args: RunArgs) -> Result<()> {
+    let path = args
+        .config
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    let config = DriverConfig::load(&path)?;
+    info!("Loaded config from {}", path);
+
+    // Queries stay on `l1_rpc_url`; heads come from `ws_url` when one is configured.
+    let l1 = provider::connect(&config.l1_rpc_url, &config.client_id).await?;
+    let mut provider = SplitProvider::new(l1.clone());
+    if let Some(ws_url) = &config.ws_url {
+        provider = provider
+            .with_subscription_provider(provider::connect(ws_url, &config.client_id).await?);
+    }
+
+    let cancel = CancellationToken::new();
+    tokio::spawn(shutdown_on_ctrl_c(cancel.clone()));
+
+    #[cfg(feature = "metrics")]
+    let registry = args.metrics_port.map(|_| prometheus::Registry::new());
+    #[cfg(feature = "metrics")]
+    let derivation_metrics = registry
+        .as_ref()
+        .map(DerivationMetrics::register)
+        .transpose()?;
+    #[cfg(not(feature = "metrics"))]
+    let derivation_metrics = None;
+
+    let (driver, watcher) = match &config.engine {
+        Some(engine) => {
+            let (driver, watcher) =
+                build_driver(&config, engine, &l1, derivation_metrics, cancel.clone())?;
+            (Some(driver.with_warmup(!args.skip_warmup)), Some(watcher))
+        }
+        None => {
+            info!("No engine configured, only indexing events");
+            (None, None)
+        }
+    };
+
+    let mut indexer = EventIndexer::new_multi(
+        provider,
+        config.indexer,
+        vec![config.contract_address],
+        config.event_topic.into_iter().collect(),
+    )?
+    .with_cancellation(cancel.clone());
+    if let Some(path) = config.checkpoint_path {
+        indexer = indexer.with_checkpoint_store(Arc::new(FileCheckpointStore::new(path)));
+    }
+
+    if let Some(port) = args.health_port {
+        let mut state = HealthState::new(indexer.progress(), args.ready_max_lag);
+        if let Some(watcher) = watcher {
+            state = state.with_watcher(watcher);
+        }
+        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if let Err(e) = health::serve(state, addr, cancel).await {
+                warn!("Health server failed: {}", e);
+            }
+        });
+    }
+
+    let mut consumers = Vec::new();
+
+    if let Some(SinkKind::Stdout) = args.sink {
+        let (sender, events) = mpsc::channel(EVENTS_BUFFER);
+        consumers.push(sender);
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = StdoutJsonSink::new().run(events) {
+                warn!("Stdout sink failed: {}", e);
+            }
+        });
+    }
+
+    // Sinks are opened, and the event port bound, before the indexer starts, so one that
+    // cannot take events stops startup whether or not the driver warms up.
+    #[cfg(feature = "ws-server")]
+    if let Some(port) = args.events_port {
+        let (sender, events) = mpsc::channel(EVENTS_BUFFER);
+        consumers.push(sender);
+        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|e| anyhow::anyhow!("cannot serve events on port {}: {}", port, e))?;
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if let Err(e) = ws_server::serve(events, listener, EVENTS_BUFFER, cancel).await {
+                warn!("Event stream server failed: {}", e);
+            }
+        });
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &args.store_path {
+        let (sender, events) = mpsc::channel(EVENTS_BUFFER);
+        consumers.push(sender);
+        let sink = SqliteEventSink::open(path)?;
+        info!("Storing indexed events in {}", path.display());
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = sink.run(events) {
+                warn!("SQLite sink failed: {}", e);
+            }
+        });
+    }
+
+    if let Some(sender) = fan_out(consumers) {
+        indexer = indexer.with_event_sender(sender);
+    }
+
+    #[cfg(feature = "metrics")]
+    if let (Some(port), Some(registry)) = (args.metrics_port, registry) {
+        indexer = indexer.with_metrics(IndexerMetrics::register(&registry)?);
+        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(registry, addr, cancel).await {
+                warn!("Metrics server failed: {}", e);
+            }
+        });
+    }
+    let derive = async {
+        match &driver {
+            Some(driver) => driver.run().await,
+            None => Ok(()),
+        }
+    };
+    let (indexed, derived) = tokio::join!(
+        stop_on_error(indexer.run(config.start_block), &cancel),
+        stop_on_error(derive, &cancel),
+    );
+    indexed?;
+    derived?;
+
+    info!("Driver stopped");
+    Ok(())
+}
+
+/// Merges the indexer's event consumers behind a single sender, forwarding each event to all
+/// of them in order. Forwarding stops once any consumer goes away, so the indexer fails
+/// instead of silently feeding the others.
+fn fan_out(mut consumers: Vec<mpsc::Sender<IndexerEvent>>) -> Option<mpsc::Sender<IndexerEvent>> {
+    if consumers.len() <= 1 {
+        return consumers.pop();
+    }
+    let (sender, mut events) = mpsc::channel::<IndexerEvent>(EVENTS_BUFFER);
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            for consumer in &consumers {
+                if consumer.send(event.clone()).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Some(sender)
+}
+
+/// Builds the watcher, derivation pipeline and executor stack that imports proposals into
+/// `engine`, stopping once `cancel` fires, with a warmup check for each endpoint it talks to.
+/// Also returns the watcher's progress, for health checks.
+fn build_driver(
+    config: &DriverConfig,
+    engine: &EngineConfig,
+    l1: &L1Provider,
+    metrics: Option<DerivationMetrics>,
+    cancel: CancellationToken,
+) -> Result<(RollupDriver, WatcherProgress)> {
+    let mut watcher = DAWatcher::new(
+        data_source(config, l1)?,
+        Duration::from_millis(config.poll_interval_ms),
+        config.watcher.buffer_size,
+        config.watcher.dedup_window,
+        config.watcher.start,
+    )
+    .with_backpressure(config.watcher.backpressure)
+    .with_hasher(config.datasource.hash_scheme.hasher())
+    .with_cancellation(cancel.clone());
+    if let Some(limit) = config.watcher.rate_limit {
+        watcher = watcher.with_rate_limit(
+            RateLimiter::new(limit.proposals_per_second, limit.burst),
+            limit.mode,
+        );
+    }
+    let progress = watcher.progress();
+    let mut pipeline = DefaultDerivationPipeline::new(data_source(config, l1)?, config.payload)?;
+    if let Some(anchor) = config.anchor {
+        pipeline = pipeline.with_anchor(anchor);
+    }
+    pipeline = with_hash_scheme(pipeline, config.datasource.hash_scheme);
+    if let Some(fetch_ms) = config.timeouts.fetch_ms {
+        pipeline = pipeline.with_fetch_timeout(Duration::from_millis(fetch_ms));
+    }
+    if let Some(window) = config.sequencing_window_secs {
+        pipeline = pipeline.with_sequencing_window(Duration::from_secs(window));
+    }
+    if let Some(max_age) = config.max_proposal_age_secs {
+        pipeline = pipeline.with_max_proposal_age(Duration::from_secs(max_age));
+    }
+    if let Some(metrics) = metrics {
+        pipeline = pipeline.with_metrics(metrics);
+    }
+
+    let jwt_secret = engine.jwt_secret()?;
+    let executor = EngineApiExecutor::new(engine.url.parse()?, jwt_secret, engine.head_block_hash)
+        .with_dry_run(engine.dry_run);
+
+    let l1 = l1.clone();
+    let da = Arc::new(data_source(config, &l1)?);
+    // A separate client, so the check never touches the executor's forkchoice state.
+    let engine = Arc::new(EngineApiExecutor::new(
+        engine.url.parse()?,
+        jwt_secret,
+        engine.head_block_hash,
+    ));
+    let mut driver = BasedDriver::new(watcher, pipeline, executor)
+        .with_warmup_check("l1 rpc", move || {
+            let l1 = l1.clone();
+            async move { l1.get_chain_id().await.map(drop).map_err(|e| e.to_string()) }
+        })
+        .with_warmup_check("da source", move || {
+            let da = da.clone();
+            async move {
+                da.latest_block_number()
+                    .await
+                    .map(drop)
+                    .map_err(|e| e.to_string())
+            }
+        })
+        .with_warmup_check("engine api", move || {
+            let engine = engine.clone();
+            async move {
+                engine
+                    .exchange_capabilities()
+                    .await
+                    .map(drop)
+                    .map_err(|e| e.to_string())
+            }
+        })
+        .with_cancellation(cancel);
+    if let Some(derive_ms) = config.timeouts.derive_ms {
+        driver = driver.with_derive_timeout(Duration::from_millis(derive_ms));
+    }
+    if let Some(execute_ms) = config.timeouts.execute_ms {
+        driver = driver.with_execute_timeout(Duration::from_millis(execute_ms));
+    }
+    Ok((driver, progress))
+}
+
+/// Verifies data hashes under `scheme`, or under whichever scheme matches when it is `auto`.
+fn with_hash_scheme<F, B>(
+    pipeline: DefaultDerivationPipeline<F, B>,
+    scheme: HashScheme,
+) -> DefaultDerivationPipeline<F, B> {
+    match scheme {
+        HashScheme::Auto => pipeline.with_hash_auto_detection(),
+        scheme => pipeline.with_hasher(scheme.hasher()),
+    }
+}
+
+/// Reads batches from the configured DA sources, in priority order.
+fn data_source(config: &DriverConfig, l1: &L1Provider) -> Result<FallbackFetcher> {
+    let datasource = &config.datasource;
+    let inbox = datasource.inbox.unwrap_or(config.contract_address);
+
+    let mut fetcher = FallbackFetcher::new();
+    for kind in &datasource.sources {
+        fetcher = match kind {
+            DataSourceKind::Calldata => fetcher.with_source(
+                "calldata",
+                CalldataDataSourceFetcher::new(l1.clone(), inbox)
+                    .with_compression(datasource.compression.clone()),
+            ),
+            DataSourceKind::Blob => {
+                let Some(beacon_url) = &datasource.beacon_url else {
+                    bail!("the blob source needs datasource.beacon_url");
+                };
+                fetcher.with_source(
+                    "blob",
+                    BlobDataSourceFetcher::new(l1.clone(), beacon_url.parse()?, inbox)
+                        .with_compression(datasource.compression.clone()),
+                )
+            }
+        };
+    }
+    Ok(fetcher)
+}
+
+/// Cancels `cancel` if `task` fails, so the tasks running next to it stop too.
+async fn stop_on_error<E>(
+    task: impl Future<Output = Result<(), E>>,
+    cancel: &CancellationToken,
+) -> Result<(), E> {
+    let result = task.await;
+    if result.is_err() {
+        cancel.cancel();
+    }
+    result
+}
+
+async fn status(args: StatusArgs) -> Result<()> {
+    let readiness = fetch_readiness(&args.endpoint).await?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&readiness)?);
+    } else {
+        print!("{}", format_readiness(&readiness));
+    }
+    Ok(())
+}
+
+async fn fetch_readiness(endpoint: &str) -> Result<Readiness> {
+    let url = format!("{}/readyz", endpoint.trim_end_matches('/'));
+    // `/readyz` answers 503 with the same body when not ready, so don't treat it as an error.
+    Ok(reqwest::get(&url).await?.json().await?)
+}
+
+fn format_readiness(readiness: &Readiness) -> String {
+    let mut out = String::new();
+    let status = if readiness.ready {
+        "ready"
+    } else {
+        "not ready"
+    };
+    out += &format!("Status:             {}\n", status);
+    out += &format!(
+        "Last indexed block: {}\n",
+        readiness.status.last_indexed_block
+    );
+    out += &format!("Head block:         {}\n", readiness.status.head);
+    out += &format!(
+        "Sync lag:           {} blocks (max {})\n",
+        readiness.status.lag, readiness.max_lag
+    );
+    if let Some(backfill) = &readiness.backfill {
+        let eta = backfill.eta_secs.map_or("unknown".to_string(), |secs| {
+            format!("{:?}", Duration::from_secs(secs))
+        });
+        out += &format!(
+            "Backfill:           {:.1}% of blocks {}-{} ({:.1} blocks/s, ETA {})\n",
+            backfill.percent, backfill.from_block, backfill.to_block, backfill.blocks_per_sec, eta
+        );
+    }
+    if let Some(watcher) = &readiness.watcher {
+        out += &format!(
+            "Watcher:            {} ({} errors, {} dropped proposals)\n",
+            if watcher.running {
+                "running"
+            } else {
+                "stopped"
+            },
+            watcher.errors,
+            watcher.dropped_proposals
+        );
+    }
+    if !readiness.failed_checks.is_empty() {
+        out += &format!(
+            "Failed checks:      {}\n",
+            readiness.failed_checks.join(", ")
+        );
+    }
+    out
+}
+
+async fn validate_config(args: ValidateConfigArgs) -> Result<()> {
+    let path = args
+        .config
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    println!("Checking {}", path);
+
+    // Missing fields and malformed addresses or hashes are reported by the parse.
+    let config = match DriverConfig::load(&path) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("  FAIL  {}", e);
+            bail!("config {} is invalid", path);
+        }
+    };
+    println!("  ok    parsed");
+
+    let mut problems = 0;
+    for error in config.validate() {
+        println!("  FAIL  {}", error);
+        problems += 1;
+    }
+
+    if args.ping && problems == 0 {
+        let mut endpoints = vec![("l1_rpc_url", config.l1_rpc_url.as_str())];
+        if let Some(ws_url) = &config.ws_url {
+            endpoints.push(("ws_url", ws_url));
+        }
+        for (field, url) in endpoints {
+            match ping(url, &config.client_id).await {
+                Ok(chain_id) => println!("  ok    {} reachable (chain id {})", field, chain_id),
+                Err(e) => {
+                    println!("  FAIL  {} unreachable: {}", field, e);
+                    problems += 1;
+                }
+            }
+        }
+    }
+
+    if problems > 0 {
+        bail!("config {} has {} problem(s)", path, problems);
+    }
+    println!("Config is valid");
+    Ok(())
+}
+
+/// Returns the chain id reported by the node at `url`.
+async fn ping(url: &str, client_id: &str) -> Result<u64> {
+    timed(async {
+        let provider = provider::connect(url, client_id).await?;
+        Ok(provider.get_chain_id().await?)
+    })
+    .await
+}
+
+/// Fails `request` if it takes longer than 10 seconds.
+async fn timed<T>(request: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(Duration::from_secs(10), request)
+        .await
+        .unwrap_or_else(|_| bail!("timed out after 10s"))
+}
+
+/// Blocks queried by the `eth_getLogs` check, ending at the latest block.
+const TEST_LOG_RANGE: u64 = 10;
+
+/// Checks each configured endpoint, writing one row per check to `out`. Fails if any check did.
+async fn self_test(args: TestArgs, out: &mut impl Write) -> Result<()> {
+    let path = args
+        .config
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    let config = DriverConfig::load(&path)?;
+    writeln!(out, "Testing endpoints from {}", path)?;
+
+    let mut passed = true;
+    let rpc =
+        timed(async { Ok(provider::connect(&config.l1_rpc_url, &config.client_id).await?) }).await;
+    passed &= report(
+        out,
+        "l1 rpc",
+        rpc.as_ref().map(|_| config.l1_rpc_url.clone()),
+    )?;
+
+    if let Ok(provider) = &rpc {
+        let latest = timed(async { Ok(provider.get_block_number().await?) }).await;
+        passed &= report(out, "latest block", latest.as_ref().map(|n| n.to_string()))?;
+
+        if let Ok(latest) = latest {
+            let from = latest.saturating_sub(TEST_LOG_RANGE - 1);
+            let filter = Filter::new()
+                .from_block(from)
+                .to_block(latest)
+                .address(config.contract_address)
+                .event_signature(config.event_topic.into_iter().collect::<Vec<_>>());
+            let logs = timed(async { Ok(provider.get_logs(&filter).await?) }).await;
+            passed &= report(
+                out,
+                "eth_getLogs",
+                logs.map(|logs| format!("{} events in blocks {}-{}", logs.len(), from, latest)),
+            )?;
+        }
+    }
+
+    if let Some(ws_url) = &config.ws_url {
+        let subscribed = timed(async {
+            let provider = provider::connect(ws_url, &config.client_id).await?;
+            let _ = provider.subscribe_blocks().await?;
+            Ok(ws_url.clone())
+        })
+        .await;
+        passed &= report(out, "ws subscribe", subscribed)?;
+    }
+
+    if let Some(engine_url) = args.engine_url {
+        let jwt_secret = args.jwt_secret;
+        let engine = timed(async {
+            let secret = load_jwt_secret(jwt_secret.as_deref())?;
+            let executor = EngineApiExecutor::new(engine_url.parse()?, secret, B256::ZERO);
+            let supported = executor.exchange_capabilities().await?;
+            let missing: Vec<_> = ENGINE_METHODS
+                .iter()
+                .filter(|method| !supported.iter().any(|s| s == *method))
+                .collect();
+            if !missing.is_empty() {
+                bail!("client does not support {:?}", missing);
+            }
+            Ok(engine_url.clone())
+        })
+        .await;
+        passed &= report(out, "engine api", engine)?;
+    }
+
+    if !passed {
+        bail!("self-test against {} failed", path);
+    }
+    writeln!(out, "All checks passed")?;
+    Ok(())
+}
+
+/// Writes one row of the self-test table, returning whether the check passed.
+fn report<E: Display>(
+    out: &mut impl Write,
+    check: &str,
+    result: Result<String, E>,
+) -> Result<bool> {
+    match result {
+        Ok(detail) => {
+            writeln!(out, "  ok    {:<14}{}", check, detail)?;
+            Ok(true)
+        }
+        Err(e) => {
+            writeln!(out, "  FAIL  {:<14}{}", check, e)?;
+            Ok(false)
+        }
+    }
+}
+
+async fn logs(args: LogsArgs) -> Result<()> {
+    if args.from > args.to {
+        bail!("--from {} is after --to {}", args.from, args.to);
+    }
+    let path = args
+        .config
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    let config = DriverConfig::load(&path)?;
+
+    let provider = provider::connect(&config.l1_rpc_url, &config.client_id).await?;
+    let logs = query_events(
+        provider,
+        args.from,
+        args.to,
+        config.contract_address,
+        config.event_topic.into_iter().collect(),
+    )
+    .await?;
+    info!(
+        "Found {} events in blocks {}-{}",
+        logs.len(),
+        args.from,
+        args.to
+    );
+    for log in logs {
+        println!("{}", serde_json::to_string(&log)?);
+    }
+    Ok(())
+}
+
+/// Derives every block in the range that has stored events and prints the resulting payload
+/// attributes as one JSON object per line.
+#[cfg(feature = "sqlite")]
+async fn replay(args: ReplayArgs) -> Result<()> {
+    let sink = SqliteEventSink::open(&args.store_path)?;
+    let payload_config = PayloadConfig {
+        fee_recipient: args.fee_recipient,
+        gas_limit: args.gas_limit,
+        ..PayloadConfig::default()
+    };
+    for attributes in replay_attributes(&sink, args.from, args.to, payload_config).await? {
+        println!("{}", serde_json::to_string(&attributes)?);
+    }
+    Ok(())
+}
+
+/// Derives the payload attributes of every block in `[from, to]` that has events in `sink`.
+#[cfg(feature = "sqlite")]
+async fn replay_attributes(
+    sink: &SqliteEventSink,
+    from: u64,
+    to: u64,
+    payload_config: PayloadConfig,
+) -> Result<Vec<BlockPayloadAttributes>> {
+    let events = sink.events(from, to)?;
+    info!(
+        "Replaying {} events from blocks {}-{}",
+        events.len(),
+        from,
+        to
+    );
+
+    let mut blocks: Vec<(u64, u64)> = events
+        .iter()
+        .map(|event| (event.block_number, event.block_timestamp))
+        .collect();
+    blocks.dedup();
+
+    let fetcher = EventDataSourceFetcher::new(events);
+    let pipeline = DefaultDerivationPipeline::new(fetcher.clone(), payload_config)?;
+    let mut derived = Vec::with_capacity(blocks.len());
+    for (block_number, timestamp) in blocks {
+        // The stored events are the batch itself, so the manifest commits to what they hold.
+        let query = DataQuery {
+            from_block: block_number,
+            to_block: block_number,
+        };
+        let raw = fetcher.fetch(&query).await?;
+        let payload = fetcher.decompress(fetcher.decode(raw).await?).await?;
+        let manifest = ProposalManifest::new(block_number, timestamp, &payload);
+
+        derived.push(pipeline.derive(manifest).await?);
+    }
+    Ok(derived)
+}
+
+async fn debug_proposal(args: DebugProposalArgs) -> Result<()> {
+    let path = args
+        .config
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    let config = DriverConfig::load(&path)?;
+    let manifest: ProposalManifest = serde_json::from_slice(&std::fs::read(&args.file)?)?;
+
+    let l1 = provider::connect(&config.l1_rpc_url, &config.client_id).await?;
+    let pipeline = with_hash_scheme(
+        DefaultDerivationPipeline::new(data_source(&config, &l1)?, config.payload)?,
+        config.datasource.hash_scheme,
+    );
+    let trace = pipeline.trace(manifest).await;
+
+    // Always dry-run, so the engine validates the block without its head moving.
+    let outcome = match (&trace, &config.engine) {
+        (Ok(trace), Some(engine)) => {
+            let jwt_secret = engine.jwt_secret()?;
+            let executor =
+                EngineApiExecutor::new(engine.url.parse()?, jwt_secret, engine.head_block_hash)
+                    .with_dry_run(true);
+            Some(executor.execute(trace.attributes.clone()).await)
+        }
+        _ => None,
+    };
+    print!("{}", format_trace(&trace, outcome.as_ref())?);
+    if trace.is_err() || matches!(outcome, Some(Err(_))) {
+        bail!("proposal {} failed", args.file.display());
+    }
+    Ok(())
+}
+
+/// Renders each stage of a traced proposal, ending at the stage that failed, if any.
+fn format_trace(
+    trace: &Result<DerivationTrace, DerivationError>,
+    outcome: Option<&Result<B256, ExecutionError>>,
+) -> Result<String> {
+    let trace = match trace {
+        Ok(trace) => trace,
+        Err(e) => return Ok(format!("Derivation failed: {}\n", e)),
+    };
+    let mut out = String::new();
+    out += &format!("Raw ({} bytes):          {}\n", trace.raw.len(), trace.raw);
+    out += &format!(
+        "Decoded ({} bytes):      {}\n",
+        trace.decoded.len(),
+        trace.decoded
+    );
+    out += &format!(
+        "Decompressed ({} bytes): {}\n",
+        trace.decompressed.len(),
+        trace.decompressed
+    );
+    out += &format!("Transactions:           {}\n", trace.transactions.len());
+    for (index, transaction) in trace.transactions.iter().enumerate() {
+        out += &format!("  {:>4}  {}\n", index, transaction);
+    }
+    out += &format!(
+        "Attributes:             {}\n",
+        serde_json::to_string(&trace.attributes)?
+    );
+    out += &match outcome {
+        Some(Ok(block_hash)) => format!("Execution:              valid, block {}\n", block_hash),
+        Some(Err(e)) => format!("Execution:              failed: {}\n", e),
+        None => "Execution:              skipped, no engine configured\n".to_string(),
+    };
+    Ok(out)
+}
+
+/// Cancels `cancel` on the first Ctrl+C so every loop can wind down, and force-exits on the
+/// second in case a task is stuck.
+async fn shutdown_on_ctrl_c(cancel: CancellationToken) {
+    if signal::ctrl_c().await.is_err() {
+        return;
+    }
+    info!("Received Ctrl+C, shutting down (press again to force exit)");
+    cancel.cancel();
+
+    if signal::ctrl_c().await.is_ok() {
+        warn!("Received second Ctrl+C, forcing exit");
+        std::process::exit(130);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use alloy::{
+        eips::eip1559::BaseFeeParams,
+        primitives::{Address, Bytes},
+    };
+    use axum::{
+        extract::State,
+        http::StatusCode,
+        routing::{get, post},
+        Json, Router,
+    };
+    use based_rollup_driver::{
+        derivation::common::BlockPayloadAttributes, event_indexer::common::IndexedEvent,
+    };
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn event(block_number: u64, log_index: u64, data: Vec<u8>) -> IndexedEvent {
+        IndexedEvent {
+            block_number,
+            log_index,
+            block_timestamp: 1_700_000_000 + block_number * 12,
+            transaction_hash: B256::repeat_byte(block_number as u8),
+            address: Address::repeat_byte(0xaa),
+            topics: Vec::new(),
+            data: Bytes::from(data),
+        }
+    }
+
+    #[tokio::test]
+    async fn status_reports_watcher() {
+        let body = json!({
+            "ready": false,
+            "max_lag": 10,
+            "last_indexed_block": 95,
+            "head": 100,
+            "lag": 5,
+            "failed_checks": ["watcher"],
+            "watcher": { "running": false, "errors": 3, "dropped_proposals": 2 },
+        });
+        let app = Router::new().route(
+            "/readyz",
+            get(move || async move { (StatusCode::SERVICE_UNAVAILABLE, Json(body)) }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let readiness = fetch_readiness(&endpoint).await.unwrap();
+
+        assert_eq!(
+            format_readiness(&readiness),
+            "Status:             not ready\n\
+             Last indexed block: 95\n\
+             Head block:         100\n\
+             Sync lag:           5 blocks (max 10)\n\
+             Watcher:            stopped (3 errors, 2 dropped proposals)\n\
+             Failed checks:      watcher\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn fan_out_forwards_to_every_consumer() {
+        let (first, mut first_events) = mpsc::channel(8);
+        let (second, mut second_events) = mpsc::channel(8);
+        let sender = fan_out(vec![first, second]).unwrap();
+
+        let reorg = IndexerEvent::reorg(1, 4);
+        sender
+            .send(IndexerEvent::Log(event(5, 0, vec![1])))
+            .await
+            .unwrap();
+        sender.send(reorg.clone()).await.unwrap();
+
+        for events in [&mut first_events, &mut second_events] {
+            assert_eq!(
+                events.recv().await,
+                Some(IndexerEvent::Log(event(5, 0, vec![1])))
+            );
+            assert_eq!(events.recv().await, Some(reorg.clone()));
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn replays_stored_events() {
+        use alloy::rlp;
+
+        let batch = |transactions: &[&[u8]]| {
+            let transactions: Vec<Bytes> = transactions
+                .iter()
+                .map(|tx| Bytes::copy_from_slice(tx))
+                .collect();
+            rlp::encode(transactions)
+        };
+        // Block 6's batch is split across two logs, which replay joins back together.
+        let split = batch(&[b"tx2", b"tx3"]);
+        let (head, tail) = split.split_at(3);
+        let mut sink = SqliteEventSink::open_in_memory().unwrap();
+        sink.write(&[
+            IndexerEvent::Log(event(5, 0, batch(&[b"tx1"]))),
+            IndexerEvent::Log(event(6, 0, head.to_vec())),
+            IndexerEvent::Log(event(6, 1, tail.to_vec())),
+            IndexerEvent::Log(event(9, 0, batch(&[b"tx4"]))),
+        ])
+        .unwrap();
+
+        let derived = replay_attributes(&sink, 5, 8, PayloadConfig::default())
+            .await
+            .unwrap();
+
+        let summary: Vec<(u64, u64, Vec<Bytes>)> = derived
+            .into_iter()
+            .map(|attributes| {
+                (
+                    attributes.l1_block_number,
+                    attributes.timestamp,
+                    attributes.transactions,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (5, 1_700_000_060, vec![Bytes::from_static(b"tx1")]),
+                (
+                    6,
+                    1_700_000_072,
+                    vec![Bytes::from_static(b"tx2"), Bytes::from_static(b"tx3")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_proposal_reports_every_stage() {
+        let trace = DerivationTrace {
+            raw: Bytes::from_static(&[0x1f, 0x8b]),
+            decoded: Bytes::from_static(&[0x1f, 0x8b]),
+            decompressed: Bytes::from_static(&[0xc4, 0x83]),
+            transactions: vec![Bytes::from_static(b"tx1")],
+            attributes: BlockPayloadAttributes {
+                l1_block_number: 7,
+                timestamp: 1_700_000_000,
+                prev_randao: B256::ZERO,
+                suggested_fee_recipient: Address::ZERO,
+                gas_limit: 30_000_000,
+                base_fee_params: BaseFeeParams::ethereum(),
+                transactions: vec![Bytes::from_static(b"tx1")],
+                is_empty_epoch: false,
+                empty_epoch_reason: None,
+            },
+        };
+
+        let report = format_trace(&Ok(trace), Some(&Ok(B256::repeat_byte(0xbb)))).unwrap();
+
+        let stages: Vec<&str> = report
+            .lines()
+            .filter(|line| !line.starts_with(' '))
+            .map(|line| line.split([':', '(']).next().unwrap().trim())
+            .collect();
+        assert_eq!(
+            stages,
+            vec![
+                "Raw",
+                "Decoded",
+                "Decompressed",
+                "Transactions",
+                "Attributes",
+                "Execution"
+            ]
+        );
+        assert!(report.contains("Raw (2 bytes):          0x1f8b\n"));
+        assert!(report.contains("     0  0x747831\n"));
+        assert!(report.ends_with(&format!("valid, block {}\n", B256::repeat_byte(0xbb))));
+    }
+
+    #[test]
+    fn debug_proposal_reports_the_failed_stage() {
+        let trace = Err(DerivationError::MalformedBatch("empty".to_string()));
+
+        assert_eq!(
+            format_trace(&trace, None).unwrap(),
+            "Derivation failed: Malformed batch: empty\n"
+        );
+    }
+
+    #[test]
+    fn data_commands_keep_logs_off_stdout() {
+        let prints_data_for = |args: &[&str]| {
+            let cli = Cli::try_parse_from([&["based-rollup"], args].concat()).unwrap();
+            prints_data(&cli.command)
+        };
+
+        assert!(prints_data_for(&["logs", "--from", "1", "--to", "2"]));
+        assert!(prints_data_for(&["run", "--sink", "stdout"]));
+        #[cfg(feature = "sqlite")]
+        assert!(prints_data_for(&[
+            "replay",
+            "--store-path",
+            "events.db",
+            "--from",
+            "1",
+            "--to",
+            "2"
+        ]));
+        assert!(!prints_data_for(&["run"]));
+        assert!(!prints_data_for(&["status"]));
+    }
+
+    /// Answers each JSON-RPC method listed in `results`; every other method fails as unsupported.
+    async fn serve_json_rpc(results: Vec<(&'static str, Value)>) -> String {
+        type Results = Arc<HashMap<&'static str, Value>>;
+
+        async fn handle(State(results): State<Results>, Json(request): Json<Value>) -> Json<Value> {
+            let response = match request["method"].as_str().and_then(|m| results.get(m)) {
+                Some(result) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }),
+                None => json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "error": { "code": -32601, "message": "method not supported" },
+                }),
+            };
+            Json(response)
+        }
+
+        let app = Router::new()
+            .route("/", post(handle))
+            .with_state(Results::new(results.into_iter().collect()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    /// An L1 node at block 100 with no logs.
+    async fn healthy_l1() -> String {
+        serve_json_rpc(vec![
+            ("eth_blockNumber", json!("0x64")),
+            ("eth_getLogs", json!([])),
+        ])
+        .await
+    }
+
+    /// An execution client supporting every Engine API method the driver calls.
+    async fn healthy_engine() -> String {
+        serve_json_rpc(vec![("engine_exchangeCapabilities", json!(ENGINE_METHODS))]).await
+    }
+
+    /// Runs `self_test` against `l1_rpc_url` and `engine_url`, returning the printed rows after
+    /// the header.
+    async fn run_self_test(name: &str, l1_rpc_url: &str, engine_url: &str) -> (Vec<String>, bool) {
+        let path = |extension: &str| {
+            std::env::temp_dir().join(format!(
+                "based-rollup-self-test-{}-{}.{}",
+                name,
+                std::process::id(),
+                extension
+            ))
+        };
+        let config = path("toml");
+        std::fs::write(
+            &config,
+            format!(
+                "l1_rpc_url = \"{}\"\ncontract_address = \"{}\"\n",
+                l1_rpc_url,
+                Address::repeat_byte(0xaa)
+            ),
+        )
+        .unwrap();
+        let jwt_secret = path("hex");
+        std::fs::write(&jwt_secret, "ab".repeat(32)).unwrap();
+
+        let mut out = Vec::new();
+        let result = self_test(
+            TestArgs {
+                config: Some(config.display().to_string()),
+                engine_url: Some(engine_url.to_string()),
+                jwt_secret: Some(jwt_secret.clone()),
+            },
+            &mut out,
+        )
+        .await;
+        std::fs::remove_file(&config).unwrap();
+        std::fs::remove_file(&jwt_secret).unwrap();
+
+        let rows = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(str::to_string)
+            .collect();
+        (rows, result.is_ok())
+    }
+
+    #[tokio::test]
+    async fn self_test_passes_when_every_endpoint_answers() {
+        let (l1, engine) = (healthy_l1().await, healthy_engine().await);
+
+        let (rows, passed) = run_self_test("pass", &l1, &engine).await;
+
+        assert_eq!(
+            rows,
+            vec![
+                format!("  ok    l1 rpc        {}", l1),
+                "  ok    latest block  100".to_string(),
+                "  ok    eth_getLogs   0 events in blocks 91-100".to_string(),
+                format!("  ok    engine api    {}", engine),
+                "All checks passed".to_string(),
+            ]
+        );
+        assert!(passed);
+    }
+
+    #[tokio::test]
+    async fn self_test_fails_when_the_block_number_is_unavailable() {
+        let l1 = serve_json_rpc(vec![("eth_getLogs", json!([]))]).await;
+        let engine = healthy_engine().await;
+
+        let (rows, passed) = run_self_test("block-number", &l1, &engine).await;
+
+        // Without a latest block there is no range to query logs over.
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], format!("  ok    l1 rpc        {}", l1));
+        assert!(rows[1].starts_with("  FAIL  latest block  "));
+        assert!(rows[1].contains("method not supported"));
+        assert_eq!(rows[2], format!("  ok    engine api    {}", engine));
+        assert!(!passed);
+    }
+
+    #[tokio::test]
+    async fn self_test_fails_when_logs_cannot_be_queried() {
+        let l1 = serve_json_rpc(vec![("eth_blockNumber", json!("0x64"))]).await;
+        let engine = healthy_engine().await;
+
+        let (rows, passed) = run_self_test("get-logs", &l1, &engine).await;
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[1], "  ok    latest block  100");
+        assert!(rows[2].starts_with("  FAIL  eth_getLogs   "));
+        assert!(rows[2].contains("method not supported"));
+        assert_eq!(rows[3], format!("  ok    engine api    {}", engine));
+        assert!(!passed);
+    }
+
+    #[tokio::test]
+    async fn self_test_fails_when_the_engine_lacks_a_method() {
+        let l1 = healthy_l1().await;
+        let engine = serve_json_rpc(vec![(
+            "engine_exchangeCapabilities",
+            json!(["engine_newPayloadV3"]),
+        )])
+        .await;
+
+        let (rows, passed) = run_self_test("engine", &l1, &engine).await;
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[2], "  ok    eth_getLogs   0 events in blocks 91-100");
+        assert_eq!(
+            rows[3],
+            "  FAIL  engine api    client does not support \
+             [\"engine_forkchoiceUpdatedV3\", \"engine_getPayloadV3\"]"
+        );
+        assert!(!passed);
+    }
+
+    #[tokio::test]
+    async fn self_test_fails_when_the_engine_does_not_answer() {
+        let l1 = healthy_l1().await;
+        let engine = serve_json_rpc(Vec::new()).await;
+
+        let (rows, passed) = run_self_test("engine-down", &l1, &engine).await;
+
+        assert_eq!(rows.len(), 4);
+        assert!(rows[3].starts_with("  FAIL  engine api    "));
+        assert!(!passed);
+    }
+}
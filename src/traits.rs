@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use alloy::primitives::B256;
 use async_trait::async_trait;
 use tokio::sync::mpsc::Receiver;
 
@@ -20,7 +21,16 @@ pub trait DataAvailabilityWatcher {
     type DataSourceFetcher: DataSourceFetcher;
     type Error: Display;
 
-    async fn watch(&self) -> Result<Receiver<Self::ProposalManifest>, Self::Error>;
+    /// Starts watching, delivering failures in-band next to proposals. The channel closes after
+    /// an error the watcher cannot recover from.
+    async fn watch(
+        &self,
+    ) -> Result<Receiver<Result<Self::ProposalManifest, Self::Error>>, Self::Error>;
+}
+
+/// Items that belong to a block, so they can be put back in block order.
+pub trait BlockOrdered {
+    fn block_number(&self) -> u64;
 }
 
 #[async_trait]
@@ -33,6 +43,28 @@ pub trait DerivationPipeline {
         &self,
         proposal: Self::ProposalManifest,
     ) -> Result<Self::BlockPayloadAttributes, Self::Error>;
+
+    /// Derives `proposals` in order, e.g. while catching up on a backlog. An error fails the
+    /// whole batch, so implementations with per-proposal state should only commit it once every
+    /// proposal has derived, letting callers fall back to [`DerivationPipeline::derive`].
+    ///
+    /// Defaults to calling `derive` for each proposal; override it to share work across the
+    /// batch.
+    async fn derive_batch(
+        &self,
+        proposals: Vec<Self::ProposalManifest>,
+    ) -> Result<Vec<Self::BlockPayloadAttributes>, Self::Error>
+    where
+        Self: Sync,
+        Self::ProposalManifest: Send,
+        Self::BlockPayloadAttributes: Send,
+    {
+        let mut payloads = Vec::with_capacity(proposals.len());
+        for proposal in proposals {
+            payloads.push(self.derive(proposal).await?);
+        }
+        Ok(payloads)
+    }
 }
 
 #[async_trait]
@@ -67,3 +99,52 @@ pub trait DataSourceFetcher {
 
     fn compression_type(&self) -> Self::Compression;
 }
+
+/// Height and time lookups on the chain a [`DataSourceFetcher`] reads from.
+#[async_trait]
+pub trait BlockSource: DataSourceFetcher {
+    async fn latest_block_number(&self) -> Result<u64, Self::Error>;
+
+    async fn block_timestamp(&self, block_number: u64) -> Result<u64, Self::Error>;
+
+    async fn block_hash(&self, block_number: u64) -> Result<B256, Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Derives a block number into its label, keeping the default `derive_batch`.
+    struct LabelPipeline;
+
+    #[async_trait]
+    impl DerivationPipeline for LabelPipeline {
+        type ProposalManifest = u64;
+        type BlockPayloadAttributes = String;
+        type Error = String;
+
+        async fn derive(&self, proposal: u64) -> Result<String, String> {
+            match proposal {
+                0 => Err("no proposal in block 0".to_string()),
+                n => Ok(format!("block {}", n)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn default_derive_batch_matches_individual_derives() {
+        let pipeline = LabelPipeline;
+        let proposals = vec![3, 1, 4];
+
+        let mut individual = Vec::new();
+        for proposal in proposals.clone() {
+            individual.push(pipeline.derive(proposal).await.unwrap());
+        }
+
+        assert_eq!(pipeline.derive_batch(proposals).await.unwrap(), individual);
+        assert_eq!(
+            pipeline.derive_batch(vec![1, 0, 2]).await,
+            Err("no proposal in block 0".to_string())
+        );
+    }
+}